@@ -0,0 +1,173 @@
+//! `#[scriptit::class]`
+
+use crate::signature::{self, Options};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Error, FnArg, ImplItem, ImplItemFn, ItemImpl, Result, Type, Visibility};
+
+/// Names the JS glue of `register_class` defines itself
+const RESERVED_NAMES: [&str; 2] = ["constructor", "free"];
+
+/// Keeps the `impl` block, without the `#[scriptit(...)]` attributes of its functions, and
+/// implements `ScriptClass` for the type
+pub fn expand(options: Options, mut item: ItemImpl) -> Result<TokenStream> {
+    if let Some((_, path, _)) = &item.trait_ {
+        return Err(Error::new_spanned(
+            path,
+            "Trait implementations can't be exported to scripts",
+        ));
+    }
+    if let Some(param) = item.generics.params.first() {
+        return Err(Error::new_spanned(
+            param,
+            "Generic types can't be exported to scripts",
+        ));
+    }
+    let self_ty = item.self_ty.clone();
+    let type_name = match &*self_ty {
+        Type::Path(path) if path.qself.is_none() => path.path.segments.last().map(|s| &s.ident),
+        _ => None,
+    };
+    let class_name = match (options.name, type_name) {
+        (Some(name), _) => name,
+        (None, Some(ident)) => ident.to_string(),
+        (None, None) => {
+            return Err(Error::new_spanned(
+                &self_ty,
+                "Expected a type name, or a `name = \"...\"` option",
+            ))
+        }
+    };
+
+    let mut constructor = None;
+    let mut methods = Vec::new();
+    let mut registrations = Vec::new();
+    for impl_item in &mut item.items {
+        let method = match impl_item {
+            ImplItem::Fn(method) => method,
+            _ => continue,
+        };
+        let method_options = take_options(method)?;
+        if !matches!(method.vis, Visibility::Public(_)) {
+            continue;
+        }
+        let sig = &method.sig;
+        signature::check_signature(sig)?;
+        let ident = &sig.ident;
+        let is_static = match sig.inputs.first() {
+            Some(FnArg::Receiver(receiver)) => {
+                if receiver.reference.is_none() || receiver.colon_token.is_some() {
+                    return Err(Error::new_spanned(
+                        receiver,
+                        "Methods exported to scripts take `&self` or `&mut self`",
+                    ));
+                }
+                false
+            }
+            _ => true,
+        };
+        let is_constructor = is_static && ident == "new";
+        let name = method_options.name.unwrap_or_else(|| ident.to_string());
+        if !is_constructor && RESERVED_NAMES.contains(&name.as_str()) {
+            return Err(Error::new_spanned(
+                ident,
+                format!(
+                    "`{}` is defined by scriptit, rename the method with `#[scriptit(name = \"...\")]`",
+                    name
+                ),
+            ));
+        }
+        let params = signature::params(sig)?;
+        let (unpack, call_args) = signature::unpack(&params);
+        let metadata =
+            signature::metadata(&name, &signature::docs(&method.attrs), &params, &sig.output);
+        let handler_name = if is_constructor { "new" } else { &name };
+        let handler_name =
+            quote!(&::scriptit::core::export::handler_name(#class_name, #handler_name));
+
+        if is_constructor {
+            constructor = Some(metadata);
+            registrations.push(quote! {
+                let instances = ::std::clone::Clone::clone(instances);
+                s_env.register_func(#handler_name, ::std::boxed::Box::new(move |args| {
+                    #unpack
+                    let instance = ::scriptit::core::export::IntoInstance::<Self>::into_instance(
+                        Self::#ident(#(#call_args),*),
+                    )?;
+                    ::std::result::Result::Ok(::scriptit::core::value::ScriptValue::from(
+                        instances.insert(instance),
+                    ))
+                }));
+            });
+        } else if is_static {
+            methods.push(quote! {
+                ::scriptit::core::export::MethodMetadata { function: #metadata, is_static: true }
+            });
+            registrations.push(quote! {
+                s_env.register_func(#handler_name, ::std::boxed::Box::new(|args| {
+                    #unpack
+                    ::scriptit::core::export::IntoScriptResult::into_script_result(
+                        Self::#ident(#(#call_args),*),
+                    )
+                }));
+            });
+        } else {
+            methods.push(quote! {
+                ::scriptit::core::export::MethodMetadata { function: #metadata, is_static: false }
+            });
+            registrations.push(quote! {
+                let instances = ::std::clone::Clone::clone(instances);
+                s_env.register_func(#handler_name, ::std::boxed::Box::new(move |args| {
+                    instances.call(args, |this, args| {
+                        #unpack
+                        ::scriptit::core::export::IntoScriptResult::into_script_result(
+                            Self::#ident(this, #(#call_args),*),
+                        )
+                    })
+                }));
+            });
+        }
+    }
+
+    let docs = signature::docs(&item.attrs);
+    let constructor = match constructor {
+        Some(metadata) => quote!(::std::option::Option::Some(#metadata)),
+        None => quote!(::std::option::Option::None),
+    };
+    Ok(quote! {
+        #item
+
+        impl ::scriptit::core::export::ScriptClass for #self_ty {
+            const METADATA: ::scriptit::core::export::ClassMetadata =
+                ::scriptit::core::export::ClassMetadata {
+                    name: #class_name,
+                    docs: #docs,
+                    constructor: #constructor,
+                    methods: &[#(#methods),*],
+                };
+
+            fn register_methods(
+                s_env: &mut dyn ::scriptit::core::ScriptingEnvironment,
+                instances: &::scriptit::core::export::Instances<Self>,
+            ) {
+                #({ #registrations })*
+            }
+        }
+    })
+}
+
+/// Removes the `#[scriptit(...)]` attributes of `method`, returns their options
+fn take_options(method: &mut ImplItemFn) -> Result<Options> {
+    let mut options = Options::default();
+    let mut result = Ok(());
+    method.attrs.retain(|attr| {
+        if !attr.path().is_ident("scriptit") {
+            return true;
+        }
+        if result.is_ok() {
+            result = attr.parse_nested_meta(|meta| options.parse_meta(meta));
+        }
+        false
+    });
+    result.map(|_| options)
+}
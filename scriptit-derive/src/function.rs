@@ -0,0 +1,49 @@
+//! `#[scriptit::function]`
+
+use crate::signature::{self, Options};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Error, ItemFn, Result};
+
+/// Keeps the function as is, and adds a struct of the same name (in the type namespace)
+/// implementing `ScriptFunction`
+pub fn expand(options: Options, item: ItemFn) -> Result<TokenStream> {
+    let sig = &item.sig;
+    signature::check_signature(sig)?;
+    if let Some(receiver) = sig.receiver() {
+        return Err(Error::new_spanned(
+            receiver,
+            "Methods are exported with `#[scriptit::class]` on their `impl` block",
+        ));
+    }
+    let ident = &sig.ident;
+    let vis = &item.vis;
+    let name = options
+        .name
+        .unwrap_or_else(|| syn::ext::IdentExt::unraw(ident).to_string());
+    let params = signature::params(sig)?;
+    let (unpack, call_args) = signature::unpack(&params);
+    let metadata = signature::metadata(&name, &signature::docs(&item.attrs), &params, &sig.output);
+
+    Ok(quote! {
+        #item
+
+        #[doc(hidden)]
+        #[allow(non_camel_case_types, dead_code)]
+        #vis struct #ident {}
+
+        impl ::scriptit::core::export::ScriptFunction for #ident {
+            const METADATA: ::scriptit::core::export::FunctionMetadata = #metadata;
+
+            fn call(
+                args: &[::scriptit::core::value::ScriptValue],
+            ) -> ::std::result::Result<
+                ::scriptit::core::value::ScriptValue,
+                ::scriptit::core::error::ScriptError,
+            > {
+                #unpack
+                ::scriptit::core::export::IntoScriptResult::into_script_result(#ident(#(#call_args),*))
+            }
+        }
+    })
+}
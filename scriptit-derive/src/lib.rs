@@ -0,0 +1,47 @@
+//! Attributes exporting Rust functions and types to scriptit scripts
+//!
+//! They are used through scriptit, with its `derive` feature: `#[scriptit::function]` and
+//! `#[scriptit::class]`. See `scriptit::core::export` for what they generate.
+
+use proc_macro::TokenStream;
+use syn::{meta, parse::Parser, parse_macro_input, ItemFn, ItemImpl};
+
+mod class;
+mod function;
+mod signature;
+
+fn parse_options(args: TokenStream) -> syn::Result<signature::Options> {
+    let mut options = signature::Options::default();
+    meta::parser(|meta| options.parse_meta(meta)).parse(args)?;
+    Ok(options)
+}
+
+/// Exports a function to scripts, register it with `scriptit::core::export::register_function`
+///
+/// Arguments are converted with `FromScriptValue`, references (`&str`, `&[T]`...) are passed from
+/// owned values. The result is converted with `IntoScriptValue`, a returned `Err` is thrown in
+/// the script. The function goes by its Rust name in `ScriptIt.funcs`, unless renamed with
+/// `#[scriptit::function(name = "jsName")]`.
+#[proc_macro_attribute]
+pub fn function(args: TokenStream, item: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(item as ItemFn);
+    parse_options(args)
+        .and_then(|options| function::expand(options, item))
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Exports the `pub` functions of an `impl` block to scripts, register the type with
+/// `scriptit::core::export::register_class`
+///
+/// `new` becomes the constructor of `ScriptIt.classes.<Type>`, functions taking `&self` or
+/// `&mut self` its methods and the other ones its static methods. The class is renamed with
+/// `#[scriptit::class(name = "JsName")]`, functions with `#[scriptit(name = "jsName")]`.
+#[proc_macro_attribute]
+pub fn class(args: TokenStream, item: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(item as ItemImpl);
+    parse_options(args)
+        .and_then(|options| class::expand(options, item))
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,308 @@
+//! Parts shared by the attributes: options, parameters and metadata
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    ext::IdentExt, meta::ParseNestedMeta, parse_quote, Attribute, Error, Expr, FnArg,
+    GenericArgument, Lit, LitStr, Meta, Pat, PathArguments, Result, ReturnType, Signature, Type,
+};
+
+/// Longest argument list `from_args` converts, as a tuple
+const MAX_PARAMS: usize = 8;
+
+/// Options of the attributes, like `#[scriptit::function(name = "jsName")]`
+#[derive(Default)]
+pub struct Options {
+    pub name: Option<String>,
+}
+
+impl Options {
+    pub fn parse_meta(&mut self, meta: ParseNestedMeta) -> Result<()> {
+        if meta.path.is_ident("name") {
+            let name: LitStr = meta.value()?.parse()?;
+            if !is_js_identifier(&name.value()) {
+                return Err(Error::new(name.span(), "Expected a JavaScript identifier"));
+            }
+            self.name = Some(name.value());
+            Ok(())
+        } else {
+            Err(meta.error("Unsupported option, expected `name = \"...\"`"))
+        }
+    }
+}
+
+fn is_js_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    let is_part = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '$';
+    match chars.next() {
+        Some(first) => !first.is_ascii_digit() && is_part(first) && chars.all(is_part),
+        None => false,
+    }
+}
+
+/// Doc comment of an item, without the leading space of each line
+pub fn docs(attrs: &[Attribute]) -> String {
+    let mut lines = Vec::new();
+    for attr in attrs {
+        if let Meta::NameValue(meta) = &attr.meta {
+            if meta.path.is_ident("doc") {
+                if let Expr::Lit(expr) = &meta.value {
+                    if let Lit::Str(doc) = &expr.lit {
+                        let doc = doc.value();
+                        lines.push(doc.strip_prefix(' ').unwrap_or(&doc).to_string());
+                    }
+                }
+            }
+        }
+    }
+    lines.join("\n").trim().to_string()
+}
+
+/// Rejects the functions arguments can't be converted for
+pub fn check_signature(sig: &Signature) -> Result<()> {
+    if let Some(asyncness) = &sig.asyncness {
+        return Err(Error::new_spanned(
+            asyncness,
+            "async functions can't be exported to scripts",
+        ));
+    }
+    if let Some(param) = sig
+        .generics
+        .params
+        .iter()
+        .find(|param| !matches!(param, syn::GenericParam::Lifetime(_)))
+    {
+        return Err(Error::new_spanned(
+            param,
+            "Generic functions can't be exported to scripts",
+        ));
+    }
+    if let Some(variadic) = &sig.variadic {
+        return Err(Error::new_spanned(
+            variadic,
+            "Variadic functions can't be exported to scripts",
+        ));
+    }
+    Ok(())
+}
+
+enum Pass {
+    Value,
+    Ref,
+    RefMut,
+}
+
+/// A parameter, converted with `FromScriptValue` into `ty` then passed as the function expects
+pub struct Param {
+    name: String,
+    ty: Type,
+    pass: Pass,
+    ts_type: String,
+    optional: bool,
+}
+
+/// Parameters of `sig`, except the receiver
+pub fn params(sig: &Signature) -> Result<Vec<Param>> {
+    let mut params = Vec::new();
+    for (i, input) in sig.inputs.iter().enumerate() {
+        let input = match input {
+            FnArg::Receiver(_) => continue,
+            FnArg::Typed(input) => input,
+        };
+        let name = match &*input.pat {
+            Pat::Ident(pat) => pat.ident.unraw().to_string(),
+            _ => format!("arg{}", i),
+        };
+        let (ty, pass) = match &*input.ty {
+            Type::Reference(reference) if reference.mutability.is_some() => {
+                (owned(&reference.elem), Pass::RefMut)
+            }
+            Type::Reference(reference) => (owned(&reference.elem), Pass::Ref),
+            ty => (ty.clone(), Pass::Value),
+        };
+        if let Type::ImplTrait(_) = ty {
+            return Err(Error::new_spanned(
+                &input.ty,
+                "`impl Trait` parameters can't be exported to scripts",
+            ));
+        }
+        params.push(Param {
+            name,
+            ts_type: ts_type(&ty),
+            optional: is_option(&ty),
+            ty,
+            pass,
+        });
+    }
+    if params.len() > MAX_PARAMS {
+        return Err(Error::new_spanned(
+            &sig.inputs,
+            format!(
+                "Functions exported to scripts take at most {} parameters",
+                MAX_PARAMS
+            ),
+        ));
+    }
+    Ok(params)
+}
+
+/// Type a referenced parameter is converted into: `&str` is passed from a `String`
+fn owned(ty: &Type) -> Type {
+    match ty {
+        Type::Path(path) if path.qself.is_none() && path.path.is_ident("str") => {
+            parse_quote!(::std::string::String)
+        }
+        Type::Slice(slice) => {
+            let elem = &slice.elem;
+            parse_quote!(::std::vec::Vec<#elem>)
+        }
+        ty => ty.clone(),
+    }
+}
+
+/// Converts `args` into the parameters, returns the conversion and the arguments of the call
+pub fn unpack(params: &[Param]) -> (TokenStream, Vec<TokenStream>) {
+    let mut bindings = Vec::new();
+    let mut call_args = Vec::new();
+    for (i, param) in params.iter().enumerate() {
+        let ident = format_ident!("__arg{}", i);
+        match param.pass {
+            Pass::Value => {
+                bindings.push(quote!(#ident));
+                call_args.push(quote!(#ident));
+            }
+            Pass::Ref => {
+                bindings.push(quote!(#ident));
+                call_args.push(quote!(&#ident));
+            }
+            Pass::RefMut => {
+                bindings.push(quote!(mut #ident));
+                call_args.push(quote!(&mut #ident));
+            }
+        }
+    }
+    let types = params.iter().map(|param| &param.ty);
+    let unpack = quote! {
+        let (#(#bindings,)*): (#(#types,)*) = ::scriptit::core::value::from_args(args)?;
+    };
+    (unpack, call_args)
+}
+
+/// `FunctionMetadata` of a function
+pub fn metadata(name: &str, docs: &str, params: &[Param], output: &ReturnType) -> TokenStream {
+    let params = params.iter().map(|param| {
+        let name = &param.name;
+        let ts_type = &param.ts_type;
+        let optional = param.optional;
+        quote! {
+            ::scriptit::core::export::ParamMetadata {
+                name: #name,
+                ty: #ts_type,
+                optional: #optional,
+            }
+        }
+    });
+    let returns = match output {
+        ReturnType::Default => String::from("void"),
+        ReturnType::Type(_, ty) => match generic_args(ty, "Result") {
+            Some(args) => args
+                .first()
+                .map_or_else(|| String::from("unknown"), |ty| ts_return_type(ty)),
+            None => ts_return_type(ty),
+        },
+    };
+    quote! {
+        ::scriptit::core::export::FunctionMetadata {
+            name: #name,
+            docs: #docs,
+            params: &[#(#params),*],
+            returns: #returns,
+        }
+    }
+}
+
+fn ts_return_type(ty: &Type) -> String {
+    match ty {
+        Type::Tuple(tuple) if tuple.elems.is_empty() => String::from("void"),
+        ty => ts_type(ty),
+    }
+}
+
+/// Type arguments of `ty` when it is a `name<...>`, like `Option<T>`
+fn generic_args<'a>(ty: &'a Type, name: &str) -> Option<Vec<&'a Type>> {
+    let segment = match ty {
+        Type::Path(path) if path.qself.is_none() => path.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != name {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => Some(
+            args.args
+                .iter()
+                .filter_map(|arg| match arg {
+                    GenericArgument::Type(ty) => Some(ty),
+                    _ => None,
+                })
+                .collect(),
+        ),
+        _ => Some(Vec::new()),
+    }
+}
+
+fn is_option(ty: &Type) -> bool {
+    generic_args(ty, "Option").is_some()
+}
+
+fn array_of(ts_type: String) -> String {
+    if ts_type.contains(' ') {
+        format!("({})[]", ts_type)
+    } else {
+        format!("{}[]", ts_type)
+    }
+}
+
+/// TypeScript type of the values a Rust type converts from and to, `unknown` when not known
+fn ts_type(ty: &Type) -> String {
+    match ty {
+        Type::Reference(reference) => ts_type(&reference.elem),
+        Type::Paren(paren) => ts_type(&paren.elem),
+        Type::Group(group) => ts_type(&group.elem),
+        Type::Slice(slice) => array_of(ts_type(&slice.elem)),
+        Type::Array(array) => array_of(ts_type(&array.elem)),
+        Type::Tuple(tuple) if tuple.elems.is_empty() => String::from("null"),
+        Type::Tuple(tuple) => {
+            let elems: Vec<String> = tuple.elems.iter().map(ts_type).collect();
+            format!("[{}]", elems.join(", "))
+        }
+        Type::Path(path) if path.qself.is_none() => {
+            let ident = match path.path.segments.last() {
+                Some(segment) => segment.ident.to_string(),
+                None => return String::from("unknown"),
+            };
+            let first_arg = |name: &str| {
+                generic_args(ty, name)
+                    .and_then(|args| args.first().map(|arg| ts_type(arg)))
+                    .unwrap_or_else(|| String::from("unknown"))
+            };
+            match ident.as_str() {
+                "bool" => String::from("boolean"),
+                "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize"
+                | "f32" | "f64" => String::from("number"),
+                "String" | "str" => String::from("string"),
+                "Option" => format!("{} | null", first_arg("Option")),
+                "Vec" => array_of(first_arg("Vec")),
+                "HashMap" | "BTreeMap" => {
+                    let value = generic_args(ty, &ident)
+                        .and_then(|args| args.get(1).map(|arg| ts_type(arg)))
+                        .unwrap_or_else(|| String::from("unknown"));
+                    format!("Record<string, {}>", value)
+                }
+                "ScriptObject" => String::from("Record<string, unknown>"),
+                _ => String::from("unknown"),
+            }
+        }
+        _ => String::from("unknown"),
+    }
+}
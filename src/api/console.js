@@ -0,0 +1,57 @@
+// @ts-check
+
+// `console`, messages are formatted here and written by the host
+(() => {
+    /**
+     * Formats a single console argument
+     * @param {any} value
+     * @returns {string}
+     */
+    const format = (value) => {
+        if (typeof value === "string") {
+            return value;
+        }
+        if (value instanceof Error) {
+            // V8 stacks start with the name and the message, QuickJS ones only have the frames
+            const header = String(value);
+            const stack = typeof value.stack === "string" ? value.stack.trimEnd() : "";
+            if (!stack || stack.startsWith(header)) {
+                return stack || header;
+            }
+            return `${header}\n${stack}`;
+        }
+        if (typeof value === "function" || typeof value === "symbol") {
+            return String(value);
+        }
+        try {
+            const json = JSON.stringify(value);
+            return json === undefined ? String(value) : json;
+        } catch (e) {
+            // Cycles and BigInts can't be serialized
+            return String(value);
+        }
+    };
+
+    /**
+     * Creates a console method writing at `level`
+     * @param {string} level
+     * @returns {(...args: any[]) => void}
+     */
+    const writer = (level) => (...args) => {
+        const message = args.map(format).join(" ");
+        ScriptIt.core.callToRust("console$write", JSON.stringify({ level, message }));
+    };
+
+    Object.defineProperty(globalThis, "console", {
+        value: {
+            debug: writer("debug"),
+            log: writer("log"),
+            info: writer("info"),
+            warn: writer("warn"),
+            error: writer("error"),
+        },
+        writable: true,
+        configurable: true,
+        enumerable: false,
+    });
+})();
@@ -0,0 +1,68 @@
+//! `console`, with messages sent to a host-implemented handler
+//!
+//! Arguments are formatted in JS: strings as they are, errors with their stack and other values
+//! as JSON when they can be serialized.
+//!
+//! ```
+//! use scriptit::{
+//!     api::console::{self, ConsoleLevel},
+//!     core::ScriptingEnvironment,
+//!     platform::PlatformScriptingEnvironment,
+//! };
+//! use std::{cell::RefCell, rc::Rc};
+//!
+//! let logs = Rc::new(RefCell::new(Vec::new()));
+//! let mut s_env = PlatformScriptingEnvironment::new();
+//! let handler_logs = Rc::clone(&logs);
+//! console::install(&mut s_env, move |level: ConsoleLevel, message: &str| {
+//!     handler_logs.borrow_mut().push((level, message.to_string()));
+//! })
+//! .unwrap();
+//!
+//! s_env.run("console.warn('count:', { count: 2 })").unwrap();
+//! assert_eq!(
+//!     *logs.borrow(),
+//!     vec![(ConsoleLevel::Warn, String::from("count: {\"count\":2}"))]
+//! );
+//! ```
+
+use crate::core::{error::ScriptError, ScriptingEnvironment};
+use serde::Deserialize;
+
+/// Severity of a console message
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConsoleLevel {
+    /// `console.debug`
+    Debug,
+    /// `console.log`
+    Log,
+    /// `console.info`
+    Info,
+    /// `console.warn`
+    Warn,
+    /// `console.error`
+    Error,
+}
+
+#[derive(Deserialize)]
+struct ConsoleMessage {
+    level: ConsoleLevel,
+    message: String,
+}
+
+/// Installs `console` on `s_env`, messages go to `handler`
+pub fn install<H>(s_env: &mut dyn ScriptingEnvironment, mut handler: H) -> Result<(), ScriptError>
+where
+    H: FnMut(ConsoleLevel, &str) + 'static,
+{
+    s_env.register_core_handler(
+        "console$write",
+        Box::new(move |data| {
+            let message: ConsoleMessage = serde_json::from_str(data).map_err(|e| e.to_string())?;
+            handler(message.level, &message.message);
+            Ok(String::from("null"))
+        }),
+    );
+    s_env.run_named(include_str!("./console.js"), "scriptit:console.js")
+}
@@ -0,0 +1,89 @@
+// @ts-check
+
+// `crypto.getRandomValues`, `crypto.randomUUID` and `crypto.subtle.digest`, computed in Rust
+(() => {
+    const { toBytes } = ScriptIt.core;
+
+    const integerArrays = [
+        "Int8Array",
+        "Uint8Array",
+        "Uint8ClampedArray",
+        "Int16Array",
+        "Uint16Array",
+        "Int32Array",
+        "Uint32Array",
+        "BigInt64Array",
+        "BigUint64Array",
+    ];
+
+    /**
+     * Creates an error named like the `DOMException` browsers throw
+     * @param {string} name
+     * @param {string} message
+     * @returns {Error}
+     */
+    const domError = (name, message) => {
+        const error = new Error(message);
+        error.name = name;
+        return error;
+    };
+
+    /**
+     * Fills an integer typed array with secure random values
+     * @template {ArrayBufferView} T
+     * @param {T} array
+     * @returns {T}
+     */
+    const getRandomValues = (array) => {
+        const name = Object.prototype.toString.call(array).slice(8, -1);
+        if (!ArrayBuffer.isView(array) || !integerArrays.includes(name)) {
+            throw domError("TypeMismatchError", "The provided value is not an integer array");
+        }
+        if (array.byteLength > 65536) {
+            throw domError(
+                "QuotaExceededError",
+                `The byte length (${array.byteLength}) exceeds the number of bytes of entropy available (65536)`
+            );
+        }
+        const bytes = JSON.parse(
+            ScriptIt.core.callToRust("crypto$randomBytes", String(array.byteLength))
+        );
+        toBytes(array).set(bytes);
+        return array;
+    };
+
+    /**
+     * @returns {string} A random version 4 UUID
+     */
+    const randomUUID = () => ScriptIt.core.callToRust("crypto$randomUUID", "");
+
+    const subtle = {
+        /**
+         * Hashes data with `SHA-1`, `SHA-256` or `SHA-512`
+         * @param {string | { name: string }} algorithm
+         * @param {ArrayBuffer | ArrayBufferView} data
+         * @returns {Promise<ArrayBuffer>}
+         */
+        digest: (algorithm, data) =>
+            new Promise((resolve) => {
+                const name = typeof algorithm === "string" ? algorithm : algorithm && algorithm.name;
+                let hash;
+                try {
+                    hash = ScriptIt.core.callToRust(
+                        "crypto$digest",
+                        JSON.stringify({ algorithm: String(name), data: Array.from(toBytes(data)) })
+                    );
+                } catch (e) {
+                    throw e instanceof TypeError ? e : domError("NotSupportedError", e.message);
+                }
+                resolve(new Uint8Array(JSON.parse(hash)).buffer);
+            }),
+    };
+
+    Object.defineProperty(globalThis, "crypto", {
+        value: { getRandomValues, randomUUID, subtle },
+        writable: true,
+        configurable: true,
+        enumerable: false,
+    });
+})();
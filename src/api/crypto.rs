@@ -0,0 +1,75 @@
+//! A subset of Web Crypto: `crypto.getRandomValues`, `crypto.randomUUID` and
+//! `crypto.subtle.digest`
+//!
+//! Random values come from the secure random number generator of the operating system (or of
+//! the host engine on wasm). `digest` supports `SHA-1`, `SHA-256` and `SHA-512`.
+//!
+//! ```
+//! use scriptit::{
+//!     api::crypto,
+//!     core::{value::ScriptValue, ScriptingEnvironment},
+//!     platform::PlatformScriptingEnvironment,
+//! };
+//!
+//! let mut s_env = PlatformScriptingEnvironment::new();
+//! crypto::install(&mut s_env).unwrap();
+//!
+//! let res = s_env.eval_expression("crypto.randomUUID().length").unwrap();
+//! assert_eq!(res, ScriptValue::from(36));
+//! ```
+
+use crate::core::{error::ScriptError, value::ScriptValue, ScriptingEnvironment};
+use serde::Deserialize;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+
+/// Largest amount of bytes `getRandomValues` can fill at once, as in browsers
+const MAX_RANDOM_BYTES: usize = 65536;
+
+#[derive(Deserialize)]
+struct DigestRequest {
+    algorithm: String,
+    data: Vec<u8>,
+}
+
+fn random_bytes(data: &str) -> Result<String, String> {
+    let length: usize = data
+        .parse()
+        .map_err(|_| format!("Invalid length: {}", data))?;
+    if length > MAX_RANDOM_BYTES {
+        return Err(format!(
+            "{} bytes were requested, the limit is {}",
+            length, MAX_RANDOM_BYTES
+        ));
+    }
+    let mut bytes = vec![0u8; length];
+    getrandom::getrandom(&mut bytes).map_err(|e| e.to_string())?;
+    Ok(ScriptValue::from(bytes).to_string())
+}
+
+fn digest(data: &str) -> Result<String, String> {
+    let request: DigestRequest = serde_json::from_str(data).map_err(|e| e.to_string())?;
+    let hash = match request.algorithm.to_uppercase().as_str() {
+        "SHA-1" => Sha1::digest(&request.data).to_vec(),
+        "SHA-256" => Sha256::digest(&request.data).to_vec(),
+        "SHA-512" => Sha512::digest(&request.data).to_vec(),
+        _ => {
+            return Err(format!(
+                "Unsupported digest algorithm: {}",
+                request.algorithm
+            ))
+        }
+    };
+    Ok(ScriptValue::from(hash).to_string())
+}
+
+/// Installs `crypto` on `s_env`
+pub fn install(s_env: &mut dyn ScriptingEnvironment) -> Result<(), ScriptError> {
+    s_env.register_core_handler("crypto$randomBytes", Box::new(random_bytes));
+    s_env.register_core_handler(
+        "crypto$randomUUID",
+        Box::new(|_| Ok(uuid::Uuid::new_v4().to_string())),
+    );
+    s_env.register_core_handler("crypto$digest", Box::new(digest));
+    s_env.run_named(include_str!("./crypto.js"), "scriptit:crypto.js")
+}
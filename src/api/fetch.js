@@ -0,0 +1,269 @@
+// @ts-check
+
+// `fetch`, `Headers`, `Request` and `Response`, requests are sent to the Rust `FetchHandler`
+(() => {
+    const { toBytes, utf8Encode, utf8Decode } = ScriptIt.core;
+
+    /**
+     * Converts a request or response body to bytes
+     * @param {any} body
+     * @returns {{ bytes: number[] | null, contentType: string | null }}
+     */
+    const extractBody = (body) => {
+        if (body === undefined || body === null) {
+            return { bytes: null, contentType: null };
+        }
+        if (body instanceof ArrayBuffer || ArrayBuffer.isView(body)) {
+            return { bytes: Array.from(toBytes(body)), contentType: null };
+        }
+        if (typeof URLSearchParams === "function" && body instanceof URLSearchParams) {
+            return {
+                bytes: Array.from(utf8Encode(body.toString())),
+                contentType: "application/x-www-form-urlencoded;charset=UTF-8",
+            };
+        }
+        return {
+            bytes: Array.from(utf8Encode(String(body))),
+            contentType: "text/plain;charset=UTF-8",
+        };
+    };
+
+    class Headers {
+        /**
+         * @param {Headers | Record<string, string> | [string, string][]} init
+         */
+        constructor(init = undefined) {
+            /** @type {Map<string, string>} */
+            this._map = new Map();
+            if (init instanceof Headers) {
+                init.forEach((value, name) => this.append(name, value));
+            } else if (init && typeof init[Symbol.iterator] === "function") {
+                for (const [name, value] of /** @type {any} */ (init)) {
+                    this.append(name, value);
+                }
+            } else if (init) {
+                for (const name of Object.keys(init)) {
+                    this.append(name, init[name]);
+                }
+            }
+        }
+
+        append(name, value) {
+            const key = String(name).toLowerCase();
+            const current = this._map.get(key);
+            this._map.set(key, current === undefined ? String(value) : `${current}, ${value}`);
+        }
+
+        delete(name) {
+            this._map.delete(String(name).toLowerCase());
+        }
+
+        get(name) {
+            const value = this._map.get(String(name).toLowerCase());
+            return value === undefined ? null : value;
+        }
+
+        has(name) {
+            return this._map.has(String(name).toLowerCase());
+        }
+
+        set(name, value) {
+            this._map.set(String(name).toLowerCase(), String(value));
+        }
+
+        forEach(callback, thisArg) {
+            for (const [name, value] of this.entries()) {
+                callback.call(thisArg, value, name, this);
+            }
+        }
+
+        *entries() {
+            yield* [...this._map].sort(([a], [b]) => (a < b ? -1 : a > b ? 1 : 0));
+        }
+
+        *keys() {
+            for (const [name] of this.entries()) {
+                yield name;
+            }
+        }
+
+        *values() {
+            for (const [, value] of this.entries()) {
+                yield value;
+            }
+        }
+
+        [Symbol.iterator]() {
+            return this.entries();
+        }
+    }
+
+    /**
+     * Body methods shared by requests and responses
+     */
+    class Body {
+        /**
+         * @param {number[] | null} bytes
+         */
+        constructor(bytes) {
+            this._bytes = bytes;
+            this.bodyUsed = false;
+        }
+
+        /**
+         * @returns {number[]}
+         */
+        _consume() {
+            if (this.bodyUsed) {
+                throw new TypeError("The body has already been consumed");
+            }
+            this.bodyUsed = true;
+            return this._bytes || [];
+        }
+
+        async arrayBuffer() {
+            return new Uint8Array(this._consume()).buffer;
+        }
+
+        async bytes() {
+            return new Uint8Array(this._consume());
+        }
+
+        async text() {
+            // Invalid sequences are replaced by U+FFFD, like browsers do
+            return utf8Decode(new Uint8Array(this._consume()));
+        }
+
+        async json() {
+            return JSON.parse(await this.text());
+        }
+    }
+
+    class Request extends Body {
+        /**
+         * @param {string | Request | { toString(): string }} input
+         * @param {{ method?: string, headers?: any, body?: any }} init
+         */
+        constructor(input, init = {}) {
+            const source = input instanceof Request ? input : null;
+            const { bytes, contentType } =
+                init.body !== undefined
+                    ? extractBody(init.body)
+                    : { bytes: source ? source._bytes : null, contentType: null };
+            super(bytes);
+            this.url = source ? source.url : String(input);
+            this.method = String(init.method || (source ? source.method : "GET")).toUpperCase();
+            this.headers = new Headers(init.headers || (source ? source.headers : undefined));
+            if (contentType && !this.headers.has("content-type")) {
+                this.headers.set("content-type", contentType);
+            }
+            if (bytes !== null && (this.method === "GET" || this.method === "HEAD")) {
+                throw new TypeError("Request with GET/HEAD method cannot have body");
+            }
+        }
+
+        clone() {
+            return new Request(this);
+        }
+    }
+
+    class Response extends Body {
+        /**
+         * @param {any} body
+         * @param {{ status?: number, statusText?: string, headers?: any }} init
+         */
+        constructor(body = null, init = {}) {
+            const { bytes, contentType } = extractBody(body);
+            super(bytes);
+            this.status = init.status === undefined ? 200 : init.status;
+            this.statusText = init.statusText === undefined ? "" : String(init.statusText);
+            this.headers = new Headers(init.headers);
+            if (contentType && !this.headers.has("content-type")) {
+                this.headers.set("content-type", contentType);
+            }
+            this.url = "";
+            this.redirected = false;
+            this.type = "default";
+        }
+
+        get ok() {
+            return this.status >= 200 && this.status < 300;
+        }
+
+        clone() {
+            if (this.bodyUsed) {
+                throw new TypeError("The body has already been consumed");
+            }
+            const response = new Response(null, this);
+            response._bytes = this._bytes;
+            response.url = this.url;
+            return response;
+        }
+
+        /**
+         * @param {any} data
+         * @param {{ status?: number, statusText?: string, headers?: any }} init
+         */
+        static json(data, init = {}) {
+            const response = new Response(JSON.stringify(data), init);
+            response.headers.set("content-type", "application/json");
+            return response;
+        }
+    }
+
+    /** @type {Map<string, { resolve: (response: Response) => void, reject: (error: Error) => void, url: string }>} */
+    const pending = new Map();
+
+    /**
+     * Sends a request to the `FetchHandler` of the environment
+     * @param {string | Request} input
+     * @param {{ method?: string, headers?: any, body?: any }} init
+     * @returns {Promise<Response>}
+     */
+    const fetch = (input, init = undefined) =>
+        new Promise((resolve, reject) => {
+            const request = new Request(input, init);
+            const id = ScriptIt.core.callToRust(
+                "fetch$send",
+                JSON.stringify({
+                    method: request.method,
+                    url: request.url,
+                    headers: [...request.headers],
+                    body: request._bytes,
+                })
+            );
+            pending.set(id, { resolve, reject, url: request.url });
+        });
+
+    /**
+     * Settles a pending `fetch`, called by the event loop
+     * @param {string} id Id of the request
+     * @param {{ status: number, status_text: string, headers: [string, string][], body: number[] } | null} response
+     * @param {string | null} error
+     */
+    const settleFetch = (id, response, error) => {
+        const request = pending.get(id);
+        if (!request) {
+            return;
+        }
+        pending.delete(id);
+        if (response === null) {
+            request.reject(new TypeError(`Failed to fetch ${request.url}: ${error}`));
+            return;
+        }
+        const res = new Response(null, {
+            status: response.status,
+            statusText: response.status_text,
+            headers: response.headers,
+        });
+        res._bytes = response.body;
+        res.url = request.url;
+        request.resolve(res);
+    };
+
+    globalThis.Headers = Headers;
+    globalThis.Request = Request;
+    globalThis.Response = Response;
+    globalThis.fetch = fetch;
+    ScriptIt.core.settleFetch = settleFetch;
+})();
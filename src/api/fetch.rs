@@ -0,0 +1,165 @@
+//! `fetch`, with requests sent to a host-implemented `FetchHandler`
+//!
+//! Hosts decide where requests go: an in-process router, a mock or a real HTTP client. Responses
+//! can be given right away or later on, from any thread: `fetch` promises settle while the event
+//! loop of the environment runs.
+//!
+//! ```
+//! use scriptit::{
+//!     api::fetch::{self, FetchRequest, FetchResponse},
+//!     core::{value::ScriptValue, ScriptingEnvironment},
+//!     platform::PlatformScriptingEnvironment,
+//! };
+//!
+//! let mut s_env = PlatformScriptingEnvironment::new();
+//! fetch::install(&mut s_env, |request: FetchRequest| -> Result<FetchResponse, String> {
+//!     Ok(FetchResponse::new(200, format!("You asked for {}", request.url)))
+//! })
+//! .unwrap();
+//!
+//! s_env
+//!     .run("fetch('/users').then((res) => res.text()).then((text) => { globalThis.text = text; })")
+//!     .unwrap();
+//! s_env.run_event_loop().unwrap();
+//!
+//! let text = s_env.eval_expression("text").unwrap();
+//! assert_eq!(text, ScriptValue::String("You asked for /users".to_string()));
+//! ```
+
+use crate::core::{
+    error::ScriptError,
+    event_loop::RemoteTaskSender,
+    value::{self, ScriptValue},
+    ScriptingEnvironment,
+};
+use serde::{Deserialize, Serialize};
+
+/// A request sent by a script
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FetchRequest {
+    /// Uppercase HTTP method
+    pub method: String,
+    /// URL as given by the script, it can be relative
+    pub url: String,
+    /// Lowercase header names and their values
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+}
+
+/// A response to give back to a script
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FetchResponse {
+    pub status: u16,
+    pub status_text: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl FetchResponse {
+    pub fn new<B: Into<Vec<u8>>>(status: u16, body: B) -> FetchResponse {
+        FetchResponse {
+            status,
+            status_text: String::new(),
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    /// Adds a header to the response
+    pub fn header(mut self, name: &str, value: &str) -> FetchResponse {
+        self.headers.push((name.to_lowercase(), value.to_string()));
+        self
+    }
+}
+
+/// Settles the `fetch` promise of a request, it can be moved to another thread
+///
+/// Dropping it without responding rejects the promise.
+pub struct FetchResponder {
+    id: String,
+    tasks: RemoteTaskSender,
+    settled: bool,
+}
+
+impl FetchResponder {
+    /// Resolves the promise with `response`, or rejects it with a `TypeError` holding the error
+    pub fn respond(mut self, response: Result<FetchResponse, String>) {
+        self.settle(response);
+    }
+
+    fn settle(&mut self, response: Result<FetchResponse, String>) {
+        self.settled = true;
+        let id = self.id.clone();
+        let (response, error) = match response {
+            Ok(response) => (
+                value::to_value(&response).unwrap_or(ScriptValue::Null),
+                None,
+            ),
+            Err(error) => (ScriptValue::Null, Some(error)),
+        };
+        self.tasks.send(Box::new(move |s_env| {
+            s_env
+                .event_loop()
+                .borrow_mut()
+                .complete_pending_op(&format!("fetch${}", id));
+            s_env.run(&format!(
+                "ScriptIt.core.settleFetch({}, {}, {})",
+                ScriptValue::String(id),
+                response,
+                ScriptValue::from(error)
+            ))
+        }));
+    }
+}
+
+impl Drop for FetchResponder {
+    fn drop(&mut self) {
+        if !self.settled {
+            self.settle(Err(String::from("The request was dropped by the host")));
+        }
+    }
+}
+
+/// Transport behind `fetch`
+pub trait FetchHandler {
+    /// Handles a request, `responder` can be used right away or later on, from any thread
+    fn fetch(&mut self, request: FetchRequest, responder: FetchResponder);
+}
+
+/// Closures answering requests synchronously are handlers
+impl<F> FetchHandler for F
+where
+    F: FnMut(FetchRequest) -> Result<FetchResponse, String>,
+{
+    fn fetch(&mut self, request: FetchRequest, responder: FetchResponder) {
+        responder.respond(self(request));
+    }
+}
+
+/// Installs `fetch`, `Headers`, `Request` and `Response` on `s_env`, requests go to `handler`
+pub fn install<H>(s_env: &mut dyn ScriptingEnvironment, mut handler: H) -> Result<(), ScriptError>
+where
+    H: FetchHandler + 'static,
+{
+    let event_loop = s_env.event_loop();
+    s_env.register_core_handler(
+        "fetch$send",
+        Box::new(move |data| {
+            let request: FetchRequest = serde_json::from_str(data).map_err(|e| e.to_string())?;
+            let id = uuid::Uuid::new_v4().to_string();
+            let tasks = {
+                let mut event_loop = event_loop.borrow_mut();
+                event_loop.add_pending_op(&format!("fetch${}", id));
+                event_loop.remote()
+            };
+            let responder = FetchResponder {
+                id: id.clone(),
+                tasks,
+                settled: false,
+            };
+            handler.fetch(request, responder);
+            Ok(id)
+        }),
+    );
+    s_env.run_named(include_str!("./fetch.js"), "scriptit:fetch.js")
+}
@@ -0,0 +1,52 @@
+// @ts-check
+
+// `ScriptIt.fs`, confined to the root directory chosen by the host
+(() => {
+    /**
+     * @param {object} call
+     * @returns {any}
+     */
+    const callFs = (call) => JSON.parse(ScriptIt.core.callToRust("fs$call", JSON.stringify(call)));
+
+    ScriptIt.fs = {
+        /**
+         * Reads a UTF-8 file
+         * @param {string} path
+         * @returns {string}
+         */
+        readText: (path) => callFs({ op: "readText", path: String(path) }),
+        /**
+         * Reads a file
+         * @param {string} path
+         * @returns {Uint8Array}
+         */
+        readBytes: (path) => new Uint8Array(callFs({ op: "readBytes", path: String(path) })),
+        /**
+         * Creates or replaces a UTF-8 file
+         * @param {string} path
+         * @param {string} text
+         */
+        writeText: (path, text) => {
+            callFs({ op: "writeText", path: String(path), text: String(text) });
+        },
+        /**
+         * Lists the entries of a directory, sorted by name
+         * @param {string} path
+         * @returns {{ name: string, isFile: boolean, isDirectory: boolean }[]}
+         */
+        list: (path = "/") => callFs({ op: "list", path: String(path) }),
+        /**
+         * Describes a file or directory, `modified` is in milliseconds since the epoch
+         * @param {string} path
+         * @returns {{ size: number, isFile: boolean, isDirectory: boolean, modified: number | null }}
+         */
+        stat: (path) => callFs({ op: "stat", path: String(path) }),
+        /**
+         * Removes a file or an empty directory
+         * @param {string} path
+         */
+        remove: (path) => {
+            callFs({ op: "remove", path: String(path) });
+        },
+    };
+})();
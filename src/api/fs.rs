@@ -0,0 +1,215 @@
+//! A filesystem confined to a root directory, available to scripts as `ScriptIt.fs`
+//!
+//! Script paths are resolved from the root: `/notes.txt` and `notes.txt` are the same file.
+//! Paths leading outside of the root, through `..` or symbolic links, are rejected.
+//!
+//! ```
+//! use scriptit::{
+//!     api::fs::{self, FsMode},
+//!     core::{value::ScriptValue, ScriptingEnvironment},
+//!     platform::PlatformScriptingEnvironment,
+//! };
+//!
+//! let root = std::env::temp_dir().join("scriptit-fs-doc");
+//! std::fs::create_dir_all(&root).unwrap();
+//!
+//! let mut s_env = PlatformScriptingEnvironment::new();
+//! fs::install(&mut s_env, &root, FsMode::ReadWrite).unwrap();
+//!
+//! s_env.run("ScriptIt.fs.writeText('hello.txt', 'Hello fs!')").unwrap();
+//! let res = s_env.eval_expression("ScriptIt.fs.readText('/hello.txt')").unwrap();
+//! assert_eq!(res, ScriptValue::String("Hello fs!".to_string()));
+//! assert!(s_env.run("ScriptIt.fs.readText('../outside.txt')").is_err());
+//! ```
+
+use crate::core::{error::ScriptError, value::ScriptValue, ScriptingEnvironment};
+use serde::Deserialize;
+use std::{
+    ffi::OsString,
+    fs, io,
+    path::{Component, Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+/// What scripts are allowed to do with the files under the root
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FsMode {
+    /// `readText`, `readBytes`, `list` and `stat`
+    ReadOnly,
+    /// Everything, including `writeText` and `remove`
+    ReadWrite,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+enum FsCall {
+    ReadText { path: String },
+    ReadBytes { path: String },
+    WriteText { path: String, text: String },
+    List { path: String },
+    Stat { path: String },
+    Remove { path: String },
+}
+
+/// Symbolic links followed while resolving a path before giving up, like `ELOOP`
+const MAX_LINKS: usize = 40;
+
+/// Queues the names to walk through for `path`, `..` included, in reverse order
+fn push_components(remaining: &mut Vec<OsString>, path: &Path) {
+    let names: Vec<OsString> = path
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_os_string()),
+            Component::ParentDir => Some(OsString::from("..")),
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => None,
+        })
+        .collect();
+    remaining.extend(names.into_iter().rev());
+}
+
+struct SandboxedFs {
+    root: PathBuf,
+    mode: FsMode,
+}
+
+impl SandboxedFs {
+    /// Resolves a script path to a path under the root
+    ///
+    /// Symbolic links are resolved along the way, even dangling ones: the resolved path has no
+    /// link left in it, so the file operations can't follow one outside of the root.
+    fn resolve(&self, path: &str) -> Result<PathBuf, String> {
+        let outside = || format!("{}: the path leads outside of the filesystem root", path);
+        let mut resolved = self.root.clone();
+        let mut remaining = Vec::new();
+        push_components(&mut remaining, Path::new(path));
+        let mut links = 0;
+        while let Some(name) = remaining.pop() {
+            if name == ".." {
+                if resolved == self.root {
+                    return Err(outside());
+                }
+                resolved.pop();
+                continue;
+            }
+            resolved.push(&name);
+            let is_link = fs::symlink_metadata(&resolved)
+                .map(|metadata| metadata.file_type().is_symlink())
+                .unwrap_or(false);
+            if !is_link {
+                continue;
+            }
+            links += 1;
+            if links > MAX_LINKS {
+                return Err(format!("{}: too many levels of symbolic links", path));
+            }
+            let target = fs::read_link(&resolved).map_err(|e| format!("{}: {}", path, e))?;
+            resolved.pop();
+            if target.is_absolute() {
+                let relative = target.strip_prefix(&self.root).map_err(|_| outside())?;
+                resolved = self.root.clone();
+                push_components(&mut remaining, relative);
+            } else {
+                push_components(&mut remaining, &target);
+            }
+        }
+        Ok(resolved)
+    }
+
+    fn check_writable(&self, path: &str) -> Result<(), String> {
+        match self.mode {
+            FsMode::ReadWrite => Ok(()),
+            FsMode::ReadOnly => Err(format!("{}: the filesystem is read-only", path)),
+        }
+    }
+
+    fn call(&self, call: FsCall) -> Result<ScriptValue, String> {
+        let io_error = |path: &str, e: io::Error| format!("{}: {}", path, e);
+        match call {
+            FsCall::ReadText { path } => fs::read_to_string(self.resolve(&path)?)
+                .map(ScriptValue::String)
+                .map_err(|e| io_error(&path, e)),
+            FsCall::ReadBytes { path } => fs::read(self.resolve(&path)?)
+                .map(ScriptValue::from)
+                .map_err(|e| io_error(&path, e)),
+            FsCall::WriteText { path, text } => {
+                self.check_writable(&path)?;
+                fs::write(self.resolve(&path)?, text)
+                    .map(|_| ScriptValue::Null)
+                    .map_err(|e| io_error(&path, e))
+            }
+            FsCall::List { path } => {
+                let entries = fs::read_dir(self.resolve(&path)?).map_err(|e| io_error(&path, e))?;
+                let mut names = Vec::new();
+                for entry in entries {
+                    let entry = entry.map_err(|e| io_error(&path, e))?;
+                    let file_type = entry.file_type().map_err(|e| io_error(&path, e))?;
+                    names.push(ScriptValue::from(serde_json::json!({
+                        "name": entry.file_name().to_string_lossy(),
+                        "isFile": file_type.is_file(),
+                        "isDirectory": file_type.is_dir(),
+                    })));
+                }
+                names.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+                Ok(ScriptValue::Array(names))
+            }
+            FsCall::Stat { path } => {
+                let metadata =
+                    fs::metadata(self.resolve(&path)?).map_err(|e| io_error(&path, e))?;
+                let modified = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                    .map(|modified| modified.as_millis() as u64);
+                Ok(ScriptValue::from(serde_json::json!({
+                    "size": metadata.len(),
+                    "isFile": metadata.is_file(),
+                    "isDirectory": metadata.is_dir(),
+                    "modified": modified,
+                })))
+            }
+            FsCall::Remove { path } => {
+                self.check_writable(&path)?;
+                let resolved = self.resolve(&path)?;
+                if resolved == self.root {
+                    return Err(format!("{}: the filesystem root can't be removed", path));
+                }
+                // Directories have to be emptied first
+                let removed = if resolved.is_dir() {
+                    fs::remove_dir(resolved)
+                } else {
+                    fs::remove_file(resolved)
+                };
+                removed
+                    .map(|_| ScriptValue::Null)
+                    .map_err(|e| io_error(&path, e))
+            }
+        }
+    }
+}
+
+/// Installs `ScriptIt.fs` on `s_env`, confined to the existing directory `root`
+pub fn install<P: AsRef<Path>>(
+    s_env: &mut dyn ScriptingEnvironment,
+    root: P,
+    mode: FsMode,
+) -> Result<(), ScriptError> {
+    let root = root
+        .as_ref()
+        .canonicalize()
+        .map_err(|e| ScriptError::RuntimeError(e.to_string()))?;
+    if !root.is_dir() {
+        return Err(ScriptError::RuntimeError(format!(
+            "{}: the filesystem root has to be a directory",
+            root.display()
+        )));
+    }
+    let sandboxed_fs = SandboxedFs { root, mode };
+    s_env.register_core_handler(
+        "fs$call",
+        Box::new(move |data| {
+            let call: FsCall = serde_json::from_str(data).map_err(|e| e.to_string())?;
+            Ok(sandboxed_fs.call(call)?.to_string())
+        }),
+    );
+    s_env.run_named(include_str!("./fs.js"), "scriptit:fs.js")
+}
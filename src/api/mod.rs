@@ -0,0 +1,17 @@
+//! Optional capabilities that can be given to scripts
+//!
+//! Nothing here is available to scripts until it gets installed on an environment.
+
+/// `console` with a host-implemented output
+pub mod console;
+/// Secure random values and digests
+pub mod crypto;
+/// `fetch` with a host-implemented transport
+pub mod fetch;
+/// Filesystem confined to a root directory
+#[cfg(not(target_arch = "wasm32"))]
+pub mod fs;
+/// Key-value storage persisted by the host
+pub mod storage;
+/// Web-platform globals
+pub mod web;
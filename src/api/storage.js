@@ -0,0 +1,112 @@
+// @ts-check
+
+// `localStorage` and `ScriptIt.storage`, persisted by the host storage backend
+(() => {
+    /**
+     * @param {object} call
+     * @returns {any}
+     */
+    const callStorage = (call) =>
+        JSON.parse(ScriptIt.core.callToRust("storage$call", JSON.stringify(call)));
+
+    const localStorage = {
+        /**
+         * @param {string} key
+         * @returns {string | null}
+         */
+        getItem(key) {
+            return callStorage({ op: "get", key: String(key) });
+        },
+        /**
+         * @param {string} key
+         * @param {string} value
+         */
+        setItem(key, value) {
+            callStorage({ op: "set", key: String(key), value: String(value) });
+        },
+        /**
+         * @param {string} key
+         */
+        removeItem(key) {
+            callStorage({ op: "remove", key: String(key) });
+        },
+        clear() {
+            callStorage({ op: "clear" });
+        },
+        /**
+         * @param {number} index
+         * @returns {string | null}
+         */
+        key(index) {
+            const key = callStorage({ op: "keys" })[index];
+            return key === undefined ? null : key;
+        },
+        get length() {
+            return callStorage({ op: "keys" }).length;
+        },
+    };
+
+    /**
+     * Runs a storage call as a promise, the call is synchronous: the promise is already settled
+     * @param {() => any} call
+     * @returns {Promise<any>}
+     */
+    const promised = (call) => new Promise((resolve) => resolve(call()));
+
+    ScriptIt.storage = {
+        /**
+         * Reads a value, `undefined` when the key is missing
+         * @param {string} key
+         * @returns {Promise<any>}
+         */
+        get: (key) =>
+            promised(() => {
+                const value = callStorage({ op: "get", key: String(key) });
+                if (value === null) {
+                    return undefined;
+                }
+                try {
+                    return JSON.parse(value);
+                } catch (_) {
+                    // Set as a string through `localStorage`
+                    return value;
+                }
+            }),
+        /**
+         * Stores a JSON-serializable value, the promise is rejected for values JSON can't
+         * represent (`undefined`, functions, symbols)
+         * @param {string} key
+         * @param {any} value
+         * @returns {Promise<void>}
+         */
+        set: (key, value) =>
+            promised(() => {
+                const json = JSON.stringify(value);
+                if (json === undefined) {
+                    throw new TypeError(`Can't store ${String(value)}, use remove to delete a key`);
+                }
+                callStorage({ op: "set", key: String(key), value: json });
+            }),
+        /**
+         * @param {string} key
+         * @returns {Promise<void>}
+         */
+        remove: (key) =>
+            promised(() => {
+                callStorage({ op: "remove", key: String(key) });
+            }),
+        /**
+         * @returns {Promise<string[]>}
+         */
+        keys: () => promised(() => callStorage({ op: "keys" })),
+        /**
+         * @returns {Promise<void>}
+         */
+        clear: () =>
+            promised(() => {
+                callStorage({ op: "clear" });
+            }),
+    };
+
+    globalThis.localStorage = localStorage;
+})();
@@ -0,0 +1,253 @@
+//! Key-value storage persisted by the host: `localStorage` and the promise-based `ScriptIt.storage`
+//!
+//! Scripts get their own namespace in the backend, so that scripts sharing a backend don't see
+//! each other's keys. `localStorage` stores strings, `ScriptIt.storage` stores JSON values.
+//!
+//! Both APIs are synchronous host calls: the backend runs on the environment thread while the
+//! script waits for it. `ScriptIt.storage` only returns promises for compatibility with async
+//! storage APIs, they are settled by the time they are returned, without the event loop.
+//!
+//! ```
+//! use scriptit::{
+//!     api::storage::{self, MemoryStorage},
+//!     core::{value::ScriptValue, ScriptingEnvironment},
+//!     platform::PlatformScriptingEnvironment,
+//! };
+//!
+//! let backend = MemoryStorage::new();
+//!
+//! let mut s_env = PlatformScriptingEnvironment::new();
+//! storage::install(&mut s_env, backend.clone(), "my-script").unwrap();
+//! s_env.run("localStorage.setItem('runs', '1')").unwrap();
+//!
+//! // The backend outlives the environment
+//! let mut s_env = PlatformScriptingEnvironment::new();
+//! storage::install(&mut s_env, backend, "my-script").unwrap();
+//! let runs = s_env.eval_expression("localStorage.getItem('runs')").unwrap();
+//! assert_eq!(runs, ScriptValue::String("1".to_string()));
+//! ```
+
+use crate::core::{error::ScriptError, value::ScriptValue, ScriptingEnvironment};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+};
+
+/// Where stored values are persisted
+///
+/// Calls are synchronous: they run on the environment thread while the script waits for them.
+pub trait StorageBackend {
+    /// Value of `key` in `namespace`, `None` if it is not set
+    fn get(&mut self, namespace: &str, key: &str) -> Result<Option<String>, String>;
+    /// Sets `key` to `value` in `namespace`
+    fn set(&mut self, namespace: &str, key: &str, value: &str) -> Result<(), String>;
+    /// Removes `key` from `namespace`, nothing happens if it is not set
+    fn remove(&mut self, namespace: &str, key: &str) -> Result<(), String>;
+    /// Keys set in `namespace`, in ascending order
+    fn keys(&mut self, namespace: &str) -> Result<Vec<String>, String>;
+    /// Removes every key of `namespace`
+    fn clear(&mut self, namespace: &str) -> Result<(), String>;
+}
+
+type Namespaces = HashMap<String, BTreeMap<String, String>>;
+
+/// Keeps values in memory, clones share the same values (even across threads)
+#[derive(Clone, Default)]
+pub struct MemoryStorage {
+    namespaces: Arc<Mutex<Namespaces>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> MemoryStorage {
+        MemoryStorage::default()
+    }
+
+    fn with_namespace<R>(
+        &self,
+        namespace: &str,
+        f: impl FnOnce(&mut BTreeMap<String, String>) -> R,
+    ) -> Result<R, String> {
+        let mut namespaces = self
+            .namespaces
+            .lock()
+            .map_err(|_| String::from("The storage is poisoned"))?;
+        Ok(f(namespaces.entry(namespace.to_string()).or_default()))
+    }
+}
+
+impl StorageBackend for MemoryStorage {
+    fn get(&mut self, namespace: &str, key: &str) -> Result<Option<String>, String> {
+        self.with_namespace(namespace, |values| values.get(key).cloned())
+    }
+
+    fn set(&mut self, namespace: &str, key: &str, value: &str) -> Result<(), String> {
+        self.with_namespace(namespace, |values| {
+            values.insert(key.to_string(), value.to_string());
+        })
+    }
+
+    fn remove(&mut self, namespace: &str, key: &str) -> Result<(), String> {
+        self.with_namespace(namespace, |values| {
+            values.remove(key);
+        })
+    }
+
+    fn keys(&mut self, namespace: &str) -> Result<Vec<String>, String> {
+        self.with_namespace(namespace, |values| values.keys().cloned().collect())
+    }
+
+    fn clear(&mut self, namespace: &str) -> Result<(), String> {
+        self.with_namespace(namespace, |values| values.clear())
+    }
+}
+
+/// Keeps each namespace in a JSON file of a directory
+///
+/// Files are read and written on every operation: environments sharing a directory see each
+/// other's changes. Writes hold a lock on the `.lock` file of the directory so that concurrent
+/// writers, from other threads or processes, don't lose each other's changes.
+#[cfg(any(not(target_arch = "wasm32"), target_os = "wasi"))]
+pub struct FileStorage {
+    dir: std::path::PathBuf,
+}
+
+#[cfg(any(not(target_arch = "wasm32"), target_os = "wasi"))]
+impl FileStorage {
+    /// Stores namespaces in `dir`, it is created if needed
+    pub fn new<P: AsRef<std::path::Path>>(dir: P) -> Result<FileStorage, ScriptError> {
+        std::fs::create_dir_all(&dir).map_err(|e| ScriptError::RuntimeError(e.to_string()))?;
+        Ok(FileStorage {
+            dir: dir.as_ref().to_path_buf(),
+        })
+    }
+
+    fn path(&self, namespace: &str) -> std::path::PathBuf {
+        // Namespaces are escaped so they can't point outside of the directory
+        let file_name: String = namespace
+            .bytes()
+            .map(|byte| match byte {
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' => (byte as char).to_string(),
+                _ => format!("%{:02X}", byte),
+            })
+            .collect();
+        self.dir.join(format!("{}.json", file_name))
+    }
+
+    /// Runs `f` while holding the lock of the directory
+    fn locked<R>(&self, f: impl FnOnce() -> Result<R, String>) -> Result<R, String> {
+        // Escaped namespaces never start with a dot: the lock file can't be a namespace file
+        let lock_file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.dir.join(".lock"))
+            .map_err(|e| e.to_string())?;
+        match lock_file.lock() {
+            // Some platforms (wasi) have no file locks, writers are expected to be alone there
+            Err(e) if e.kind() == std::io::ErrorKind::Unsupported => {}
+            result => result.map_err(|e| e.to_string())?,
+        }
+        // The lock is released when the file gets closed
+        f()
+    }
+
+    fn load(&self, namespace: &str) -> Result<BTreeMap<String, String>, String> {
+        match std::fs::read_to_string(self.path(namespace)) {
+            Ok(json) => serde_json::from_str(&json).map_err(|e| e.to_string()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    fn save(&self, namespace: &str, values: &BTreeMap<String, String>) -> Result<(), String> {
+        let path = self.path(namespace);
+        let json = serde_json::to_string(values).map_err(|e| e.to_string())?;
+        // Written next to the file then renamed so a crash can't leave a truncated file
+        let tmp_path = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4()));
+        std::fs::write(&tmp_path, json).map_err(|e| e.to_string())?;
+        std::fs::rename(&tmp_path, &path).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(any(not(target_arch = "wasm32"), target_os = "wasi"))]
+impl StorageBackend for FileStorage {
+    fn get(&mut self, namespace: &str, key: &str) -> Result<Option<String>, String> {
+        Ok(self.load(namespace)?.remove(key))
+    }
+
+    fn set(&mut self, namespace: &str, key: &str, value: &str) -> Result<(), String> {
+        self.locked(|| {
+            let mut values = self.load(namespace)?;
+            values.insert(key.to_string(), value.to_string());
+            self.save(namespace, &values)
+        })
+    }
+
+    fn remove(&mut self, namespace: &str, key: &str) -> Result<(), String> {
+        self.locked(|| {
+            let mut values = self.load(namespace)?;
+            if values.remove(key).is_some() {
+                self.save(namespace, &values)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn keys(&mut self, namespace: &str) -> Result<Vec<String>, String> {
+        Ok(self.load(namespace)?.into_keys().collect())
+    }
+
+    fn clear(&mut self, namespace: &str) -> Result<(), String> {
+        self.locked(|| match std::fs::remove_file(self.path(namespace)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+            _ => Ok(()),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+enum StorageCall {
+    Get { key: String },
+    Set { key: String, value: String },
+    Remove { key: String },
+    Keys,
+    Clear,
+}
+
+/// Installs `localStorage` and `ScriptIt.storage` on `s_env`, keys are stored under `namespace`
+pub fn install<B>(
+    s_env: &mut dyn ScriptingEnvironment,
+    mut backend: B,
+    namespace: &str,
+) -> Result<(), ScriptError>
+where
+    B: StorageBackend + 'static,
+{
+    let namespace = namespace.to_string();
+    s_env.register_core_handler(
+        "storage$call",
+        Box::new(move |data| {
+            let call: StorageCall = serde_json::from_str(data).map_err(|e| e.to_string())?;
+            let res = match call {
+                StorageCall::Get { key } => ScriptValue::from(backend.get(&namespace, &key)?),
+                StorageCall::Set { key, value } => {
+                    backend.set(&namespace, &key, &value)?;
+                    ScriptValue::Null
+                }
+                StorageCall::Remove { key } => {
+                    backend.remove(&namespace, &key)?;
+                    ScriptValue::Null
+                }
+                StorageCall::Keys => ScriptValue::from(backend.keys(&namespace)?),
+                StorageCall::Clear => {
+                    backend.clear(&namespace)?;
+                    ScriptValue::Null
+                }
+            };
+            Ok(res.to_string())
+        }),
+    );
+    s_env.run_named(include_str!("./storage.js"), "scriptit:storage.js")
+}
@@ -0,0 +1,427 @@
+// @ts-check
+
+// Web-platform globals, host engine globals are used instead when available (on wasm)
+(() => {
+    /**
+     * Uses the global of the host engine when there is one
+     * @param {string} name
+     * @returns {boolean} Whether the host engine provides the global
+     */
+    const passthrough = (name) =>
+        typeof ScriptIt.core.passthroughGlobal === "function" &&
+        ScriptIt.core.passthroughGlobal(name);
+
+    /**
+     * Defines a global unless the host engine provides it
+     * @param {string} name
+     * @param {any} value
+     */
+    const define = (name, value) => {
+        if (!passthrough(name)) {
+            Object.defineProperty(globalThis, name, {
+                value,
+                writable: true,
+                configurable: true,
+                enumerable: false,
+            });
+        }
+    };
+
+    const { toBytes, utf8Encode, utf8Decode } = ScriptIt.core;
+
+    const utf8Labels = ["utf-8", "utf8", "unicode-1-1-utf-8"];
+
+    class TextEncoder {
+        get encoding() {
+            return "utf-8";
+        }
+
+        /**
+         * @param {string} input
+         * @returns {Uint8Array}
+         */
+        encode(input = "") {
+            return utf8Encode(String(input));
+        }
+
+        /**
+         * @param {string} input
+         * @param {Uint8Array} destination
+         * @returns {{ read: number, written: number }}
+         */
+        encodeInto(input, destination) {
+            const str = String(input);
+            let read = 0;
+            let written = 0;
+            for (const char of str) {
+                const bytes = this.encode(char);
+                if (written + bytes.length > destination.length) {
+                    break;
+                }
+                destination.set(bytes, written);
+                written += bytes.length;
+                read += char.length;
+            }
+            return { read, written };
+        }
+    }
+
+    class TextDecoder {
+        /**
+         * @param {string} label
+         * @param {{ fatal?: boolean, ignoreBOM?: boolean }} options
+         */
+        constructor(label = "utf-8", options = {}) {
+            if (!utf8Labels.includes(String(label).trim().toLowerCase())) {
+                throw new RangeError(`The encoding label provided ('${label}') is not supported`);
+            }
+            this.fatal = Boolean(options.fatal);
+            this.ignoreBOM = Boolean(options.ignoreBOM);
+        }
+
+        get encoding() {
+            return "utf-8";
+        }
+
+        /**
+         * @param {ArrayBuffer | ArrayBufferView} input
+         * @returns {string}
+         */
+        decode(input) {
+            if (input === undefined) {
+                return "";
+            }
+            return utf8Decode(toBytes(input), { fatal: this.fatal, ignoreBOM: this.ignoreBOM });
+        }
+    }
+
+    const base64Chars = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    /**
+     * @param {string} message
+     */
+    const invalidCharacter = (message) => {
+        const error = new Error(message);
+        error.name = "InvalidCharacterError";
+        return error;
+    };
+
+    /**
+     * Encodes a binary string to base64
+     * @param {any} data
+     * @returns {string}
+     */
+    const btoa = (data) => {
+        const str = String(data);
+        let out = "";
+        for (let i = 0; i < str.length; i += 3) {
+            const codes = [str.charCodeAt(i), str.charCodeAt(i + 1), str.charCodeAt(i + 2)];
+            for (const code of codes) {
+                if (code > 0xff) {
+                    throw invalidCharacter("The string to be encoded contains characters outside of the Latin1 range");
+                }
+            }
+            const [a, b, c] = codes;
+            out += base64Chars[a >> 2];
+            out += base64Chars[((a & 0x03) << 4) | ((b || 0) >> 4)];
+            out += Number.isNaN(b) ? "=" : base64Chars[((b & 0x0f) << 2) | ((c || 0) >> 6)];
+            out += Number.isNaN(c) ? "=" : base64Chars[c & 0x3f];
+        }
+        return out;
+    };
+
+    /**
+     * Decodes base64 to a binary string
+     * @param {any} data
+     * @returns {string}
+     */
+    const atob = (data) => {
+        let str = String(data).replace(/[\t\n\f\r ]/g, "");
+        if (str.length % 4 === 0) {
+            str = str.replace(/==?$/, "");
+        }
+        if (str.length % 4 === 1 || /[^A-Za-z0-9+/]/.test(str)) {
+            throw invalidCharacter("The string to be decoded is not correctly encoded");
+        }
+        let out = "";
+        let buffer = 0;
+        let bits = 0;
+        for (const char of str) {
+            buffer = (buffer << 6) | base64Chars.indexOf(char);
+            bits += 6;
+            if (bits >= 8) {
+                bits -= 8;
+                out += String.fromCharCode((buffer >> bits) & 0xff);
+            }
+        }
+        return out;
+    };
+
+    /**
+     * Percent-encodes following application/x-www-form-urlencoded
+     * @param {string} str
+     */
+    const formEncode = (str) =>
+        encodeURIComponent(str)
+            .replace(/[!'()~]/g, (c) => `%${c.charCodeAt(0).toString(16).toUpperCase()}`)
+            .replace(/%20/g, "+");
+
+    /**
+     * @param {string} str
+     */
+    const formDecode = (str) => {
+        const spaced = str.replace(/\+/g, " ");
+        try {
+            return decodeURIComponent(spaced);
+        } catch (_) {
+            return spaced;
+        }
+    };
+
+    class URLSearchParams {
+        /**
+         * @param {string | Record<string, string> | [string, string][] | URLSearchParams} init
+         */
+        constructor(init = "") {
+            /** @type {[string, string][]} */
+            this._list = [];
+            /** @type {URL | null} */
+            this._url = null;
+            if (typeof init === "object" && init !== null) {
+                const entries =
+                    typeof init[Symbol.iterator] === "function" ? init : Object.entries(init);
+                for (const pair of entries) {
+                    const [name, value] = [...pair];
+                    this._list.push([String(name), String(value)]);
+                }
+            } else {
+                this._parse(String(init));
+            }
+        }
+
+        /**
+         * @param {string} query
+         */
+        _parse(query) {
+            this._list = [];
+            for (const part of query.replace(/^\?/, "").split("&")) {
+                if (part === "") {
+                    continue;
+                }
+                const index = part.indexOf("=");
+                const name = index === -1 ? part : part.slice(0, index);
+                const value = index === -1 ? "" : part.slice(index + 1);
+                this._list.push([formDecode(name), formDecode(value)]);
+            }
+        }
+
+        _update() {
+            if (this._url) {
+                const query = this.toString();
+                this._url._setSearch(query === "" ? "" : `?${query}`);
+            }
+        }
+
+        get size() {
+            return this._list.length;
+        }
+
+        append(name, value) {
+            this._list.push([String(name), String(value)]);
+            this._update();
+        }
+
+        delete(name) {
+            this._list = this._list.filter(([n]) => n !== String(name));
+            this._update();
+        }
+
+        get(name) {
+            const entry = this._list.find(([n]) => n === String(name));
+            return entry ? entry[1] : null;
+        }
+
+        getAll(name) {
+            return this._list.filter(([n]) => n === String(name)).map(([, v]) => v);
+        }
+
+        has(name) {
+            return this._list.some(([n]) => n === String(name));
+        }
+
+        set(name, value) {
+            const index = this._list.findIndex(([n]) => n === String(name));
+            if (index === -1) {
+                this._list.push([String(name), String(value)]);
+            } else {
+                this._list[index][1] = String(value);
+                this._list = this._list.filter(([n], i) => i <= index || n !== String(name));
+            }
+            this._update();
+        }
+
+        sort() {
+            this._list.sort(([a], [b]) => (a < b ? -1 : a > b ? 1 : 0));
+            this._update();
+        }
+
+        forEach(callback, thisArg) {
+            for (const [name, value] of this._list) {
+                callback.call(thisArg, value, name, this);
+            }
+        }
+
+        *entries() {
+            yield* this._list.map(([n, v]) => [n, v]);
+        }
+
+        *keys() {
+            yield* this._list.map(([n]) => n);
+        }
+
+        *values() {
+            yield* this._list.map(([, v]) => v);
+        }
+
+        [Symbol.iterator]() {
+            return this.entries();
+        }
+
+        toString() {
+            return this._list.map(([n, v]) => `${formEncode(n)}=${formEncode(v)}`).join("&");
+        }
+    }
+
+    const urlComponents = [
+        "href",
+        "protocol",
+        "username",
+        "password",
+        "host",
+        "hostname",
+        "port",
+        "pathname",
+        "search",
+        "hash",
+    ];
+
+    /**
+     * Parses a URL in Rust, optionally setting one of its components
+     * @param {object} request
+     */
+    const parseUrl = (request) => {
+        const res = ScriptIt.core.callToRust("web$url", JSON.stringify(request));
+        return JSON.parse(res);
+    };
+
+    class URL {
+        /**
+         * @param {string} url
+         * @param {string | URL} base
+         */
+        constructor(url, base = undefined) {
+            const request = { input: String(url) };
+            if (base !== undefined) {
+                request.base = String(base);
+            }
+            const parts = parseUrl(request);
+            if (parts === null) {
+                throw new TypeError(`Invalid URL: ${url}`);
+            }
+            this._parts = parts;
+            this._searchParams = new URLSearchParams(parts.search);
+            this._searchParams._url = this;
+        }
+
+        static canParse(url, base = undefined) {
+            try {
+                new URL(url, base);
+                return true;
+            } catch (_) {
+                return false;
+            }
+        }
+
+        /**
+         * @param {string} component
+         * @param {string} value
+         */
+        _set(component, value) {
+            const parts = parseUrl({ input: this._parts.href, set: [component, String(value)] });
+            if (parts === null) {
+                throw new TypeError(`Invalid URL: ${value}`);
+            }
+            this._parts = parts;
+        }
+
+        /**
+         * @param {string} search
+         */
+        _setSearch(search) {
+            this._set("search", search);
+        }
+
+        get origin() {
+            return this._parts.origin;
+        }
+
+        get searchParams() {
+            return this._searchParams;
+        }
+
+        toString() {
+            return this._parts.href;
+        }
+
+        toJSON() {
+            return this._parts.href;
+        }
+    }
+
+    for (const component of urlComponents) {
+        Object.defineProperty(URL.prototype, component, {
+            get() {
+                return this._parts[component];
+            },
+            set(value) {
+                this._set(component, value);
+                if (component === "href" || component === "search") {
+                    this._searchParams._parse(this._parts.search);
+                }
+            },
+            configurable: true,
+            enumerable: true,
+        });
+    }
+
+    /**
+     * Deep copies a value following the structured clone algorithm
+     * @param {any} value
+     * @returns {any}
+     */
+    const structuredClone = (value) => ScriptIt.core.deserialize(ScriptIt.core.serialize(value));
+
+    const timeOrigin = Date.now() - Number(ScriptIt.core.callToRust("web$now", ""));
+    const performance = {
+        timeOrigin,
+        /**
+         * Milliseconds elapsed on the clock of the environment
+         * @returns {number}
+         */
+        now() {
+            return Number(ScriptIt.core.callToRust("web$now", ""));
+        },
+        toJSON() {
+            return { timeOrigin };
+        },
+    };
+
+    define("TextEncoder", TextEncoder);
+    define("TextDecoder", TextDecoder);
+    define("URL", URL);
+    define("URLSearchParams", URLSearchParams);
+    define("atob", atob);
+    define("btoa", btoa);
+    define("structuredClone", structuredClone);
+    define("performance", performance);
+})();
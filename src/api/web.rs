@@ -0,0 +1,131 @@
+//! Web-platform globals: `TextEncoder`, `TextDecoder`, `URL`, `URLSearchParams`, `atob`, `btoa`,
+//! `structuredClone` and `performance.now`
+//!
+//! On wasm, the globals of the host engine are passed through when it provides them.
+//!
+//! ```
+//! use scriptit::{
+//!     api::web,
+//!     core::{value::ScriptValue, ScriptingEnvironment},
+//!     platform::PlatformScriptingEnvironment,
+//! };
+//!
+//! let mut s_env = PlatformScriptingEnvironment::new();
+//! web::install(&mut s_env).unwrap();
+//!
+//! let res = s_env
+//!     .eval_expression("new URL('/docs?page=2', 'https://example.com').searchParams.get('page')")
+//!     .unwrap();
+//! assert_eq!(res, ScriptValue::String("2".to_string()));
+//! ```
+
+use crate::core::{error::ScriptError, ScriptingEnvironment};
+
+/// Installs the web-platform globals on `s_env`
+///
+/// `performance.now` is measured with the clock of the environment event loop.
+pub fn install(s_env: &mut dyn ScriptingEnvironment) -> Result<(), ScriptError> {
+    let event_loop = s_env.event_loop();
+    s_env.register_core_handler(
+        "web$now",
+        Box::new(move |_| {
+            let now = event_loop.borrow().clock().now();
+            Ok((now.as_secs_f64() * 1000.0).to_string())
+        }),
+    );
+    #[cfg(any(not(target_arch = "wasm32"), target_os = "wasi"))]
+    s_env.register_core_handler("web$url", Box::new(url_parts::handle));
+    s_env.run_named(include_str!("./web.js"), "scriptit:web.js")
+}
+
+#[cfg(any(not(target_arch = "wasm32"), target_os = "wasi"))]
+mod url_parts {
+    use serde::{Deserialize, Serialize};
+    use url::{quirks, Url};
+
+    #[derive(Deserialize)]
+    struct UrlRequest {
+        input: String,
+        base: Option<String>,
+        set: Option<(String, String)>,
+    }
+
+    #[derive(Serialize)]
+    struct UrlParts<'u> {
+        href: &'u str,
+        origin: String,
+        protocol: &'u str,
+        username: &'u str,
+        password: &'u str,
+        host: &'u str,
+        hostname: &'u str,
+        port: &'u str,
+        pathname: &'u str,
+        search: &'u str,
+        hash: &'u str,
+    }
+
+    impl<'u> UrlParts<'u> {
+        fn new(url: &'u Url) -> UrlParts<'u> {
+            UrlParts {
+                href: quirks::href(url),
+                origin: quirks::origin(url),
+                protocol: quirks::protocol(url),
+                username: quirks::username(url),
+                password: quirks::password(url),
+                host: quirks::host(url),
+                hostname: quirks::hostname(url),
+                port: quirks::port(url),
+                pathname: quirks::pathname(url),
+                search: quirks::search(url),
+                hash: quirks::hash(url),
+            }
+        }
+    }
+
+    /// Applies a setter of the WHATWG URL API, invalid values are ignored like in browsers
+    fn set_component(url: &mut Url, component: &str, value: &str) -> Result<(), ()> {
+        match component {
+            "href" => quirks::set_href(url, value).map_err(|_| ()),
+            "protocol" => quirks::set_protocol(url, value).or(Ok(())),
+            "username" => quirks::set_username(url, value).or(Ok(())),
+            "password" => quirks::set_password(url, value).or(Ok(())),
+            "host" => quirks::set_host(url, value).or(Ok(())),
+            "hostname" => quirks::set_hostname(url, value).or(Ok(())),
+            "port" => quirks::set_port(url, value).or(Ok(())),
+            "pathname" => {
+                quirks::set_pathname(url, value);
+                Ok(())
+            }
+            "search" => {
+                quirks::set_search(url, value);
+                Ok(())
+            }
+            "hash" => {
+                quirks::set_hash(url, value);
+                Ok(())
+            }
+            _ => Err(()),
+        }
+    }
+
+    /// Parses a URL, returns `null` when it is invalid
+    pub(super) fn handle(data: &str) -> Result<String, String> {
+        let request: UrlRequest = serde_json::from_str(data).map_err(|e| e.to_string())?;
+        let base = match request.base.as_deref().map(Url::parse) {
+            Some(Ok(base)) => Some(base),
+            Some(Err(_)) => return Ok(String::from("null")),
+            None => None,
+        };
+        let mut url = match Url::options().base_url(base.as_ref()).parse(&request.input) {
+            Ok(url) => url,
+            Err(_) => return Ok(String::from("null")),
+        };
+        if let Some((component, value)) = request.set {
+            if set_component(&mut url, &component, &value).is_err() {
+                return Ok(String::from("null"));
+            }
+        }
+        serde_json::to_string(&UrlParts::new(&url)).map_err(|e| e.to_string())
+    }
+}
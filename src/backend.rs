@@ -0,0 +1,211 @@
+//! Picks a scripting backend at runtime, among the ones compiled in
+//!
+//! `platform::PlatformScriptingEnvironment` is chosen when building. A
+//! `ScriptingEnvironmentFactory` goes through a list of backends instead, coming from
+//! configuration for instance, and falls back to the next one when a backend can't be created.
+//!
+//! ```
+//! use scriptit::{
+//!     backend::ScriptingEnvironmentFactory,
+//!     core::value::ScriptValue,
+//! };
+//!
+//! let factory = ScriptingEnvironmentFactory::default();
+//! let (backend, mut s_env) = factory.create().unwrap();
+//! assert_eq!(backend, factory.backends()[0]);
+//!
+//! let res = s_env.eval_expression("1 + 1").unwrap();
+//! assert_eq!(res, ScriptValue::from(2));
+//! ```
+
+use crate::core::ScriptingEnvironment;
+use std::{any::Any, fmt, panic, str::FromStr};
+
+/// A scripting engine scriptit can run scripts with
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// V8, on native targets with the `v8` feature
+    V8,
+    /// QuickJS, on native targets with the `quickjs` feature
+    QuickJs,
+    /// The host JS engine, on wasm32 targets other than wasm32-wasi
+    Wasm,
+    /// Boa, with the `boa` feature
+    Boa,
+}
+
+impl Backend {
+    const ALL: [Backend; 4] = [Backend::V8, Backend::QuickJs, Backend::Wasm, Backend::Boa];
+
+    /// Backends compiled in, the one behind `platform` first
+    pub fn available() -> Vec<Backend> {
+        Backend::ALL
+            .iter()
+            .copied()
+            .filter(|backend| backend.is_available())
+            .collect()
+    }
+
+    /// Name of the backend in configurations: `v8`, `quickjs`, `wasm` or `boa`
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::V8 => "v8",
+            Backend::QuickJs => "quickjs",
+            Backend::Wasm => "wasm",
+            Backend::Boa => "boa",
+        }
+    }
+
+    /// Whether the backend was compiled in
+    pub fn is_available(self) -> bool {
+        match self {
+            Backend::V8 => cfg!(all(not(target_arch = "wasm32"), feature = "v8")),
+            Backend::QuickJs => cfg!(all(not(target_arch = "wasm32"), feature = "quickjs")),
+            Backend::Wasm => cfg!(all(target_arch = "wasm32", not(target_os = "wasi"))),
+            Backend::Boa => cfg!(feature = "boa"),
+        }
+    }
+
+    /// Creates a scripting environment running on this backend
+    ///
+    /// Panics while initializing the engine are turned into `BackendError::InitFailed`, unless
+    /// panics abort.
+    pub fn create(self) -> Result<Box<dyn ScriptingEnvironment>, BackendError> {
+        if !self.is_available() {
+            return Err(BackendError::NotCompiled(self));
+        }
+        panic::catch_unwind(|| new_environment(self)).map_err(|payload| BackendError::InitFailed {
+            backend: self,
+            reason: panic_reason(payload),
+        })
+    }
+}
+
+fn new_environment(backend: Backend) -> Box<dyn ScriptingEnvironment> {
+    match backend {
+        #[cfg(all(not(target_arch = "wasm32"), feature = "v8"))]
+        Backend::V8 => Box::new(crate::platform::V8ScriptingEnvironment::new()),
+        #[cfg(all(not(target_arch = "wasm32"), feature = "quickjs"))]
+        Backend::QuickJs => Box::new(crate::quickjs::QuickJsScriptingEnvironment::new()),
+        #[cfg(all(target_arch = "wasm32", not(target_os = "wasi")))]
+        Backend::Wasm => Box::new(crate::platform::WASMScriptingEnvironment::new()),
+        #[cfg(feature = "boa")]
+        Backend::Boa => Box::new(crate::boa::BoaScriptingEnvironment::new()),
+        #[allow(unreachable_patterns)]
+        _ => unreachable!("{} is not compiled in", backend),
+    }
+}
+
+fn panic_reason(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(reason) => *reason,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(reason) => reason.to_string(),
+            Err(_) => String::from("unknown panic"),
+        },
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Backend {
+    type Err = BackendError;
+
+    fn from_str(name: &str) -> Result<Backend, BackendError> {
+        Backend::ALL
+            .iter()
+            .copied()
+            .find(|backend| backend.name().eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| BackendError::Unknown(name.to_string()))
+    }
+}
+
+/// Represents a scripting environment that could not be created
+#[derive(Debug)]
+pub enum BackendError {
+    /// No backend goes by this name
+    Unknown(String),
+    /// The backend was not compiled in, its cargo feature is missing
+    NotCompiled(Backend),
+    /// The engine failed to initialize
+    InitFailed { backend: Backend, reason: String },
+    /// Every backend of a factory failed, with the error of each of them
+    Exhausted(Vec<BackendError>),
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackendError::Unknown(name) => write!(f, "BackendError::Unknown: `{}`", name),
+            BackendError::NotCompiled(backend) => {
+                write!(f, "BackendError::NotCompiled: {}", backend)
+            }
+            BackendError::InitFailed { backend, reason } => {
+                write!(f, "BackendError::InitFailed: {}: {}", backend, reason)
+            }
+            BackendError::Exhausted(errors) => {
+                write!(f, "BackendError::Exhausted: ")?;
+                for (i, error) in errors.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "({})", error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for BackendError {}
+
+/// Creates scripting environments on the first backend of a list that works
+#[derive(Clone, Debug)]
+pub struct ScriptingEnvironmentFactory {
+    backends: Vec<Backend>,
+}
+
+impl ScriptingEnvironmentFactory {
+    /// Tries `backends` in order, backends that are not compiled in are skipped
+    pub fn new(backends: Vec<Backend>) -> ScriptingEnvironmentFactory {
+        ScriptingEnvironmentFactory { backends }
+    }
+
+    /// Parses a comma-separated list of backend names, like `"v8,quickjs"`
+    pub fn from_config(config: &str) -> Result<ScriptingEnvironmentFactory, BackendError> {
+        let backends = config
+            .split(',')
+            .filter(|name| !name.trim().is_empty())
+            .map(Backend::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ScriptingEnvironmentFactory::new(backends))
+    }
+
+    /// Backends tried by `create`, in order
+    pub fn backends(&self) -> &[Backend] {
+        &self.backends
+    }
+
+    /// Creates a scripting environment, along with the backend it runs on
+    pub fn create(&self) -> Result<(Backend, Box<dyn ScriptingEnvironment>), BackendError> {
+        let mut errors = Vec::new();
+        for &backend in &self.backends {
+            match backend.create() {
+                Ok(s_env) => return Ok((backend, s_env)),
+                Err(error) => errors.push(error),
+            }
+        }
+        Err(BackendError::Exhausted(errors))
+    }
+}
+
+impl Default for ScriptingEnvironmentFactory {
+    /// Tries every backend compiled in, the one behind `platform` first
+    fn default() -> Self {
+        ScriptingEnvironmentFactory::new(Backend::available())
+    }
+}
@@ -0,0 +1,99 @@
+use std::{cell::Cell, rc::Rc, time::Duration};
+
+/// Time source of an event loop, timers fire according to it
+pub trait Clock {
+    /// Time elapsed since the clock started
+    fn now(&self) -> Duration;
+    /// Called when the event loop has nothing to run until `deadline`
+    ///
+    /// Returns how long the event loop should wait for tasks queued from other threads before
+    /// checking its timers again.
+    fn idle_until(&self, deadline: Duration) -> Duration;
+}
+
+#[cfg(all(target_arch = "wasm32", not(target_os = "wasi")))]
+#[wasm_bindgen::prelude::wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = Date, js_name = now)]
+    fn date_now() -> f64;
+}
+
+/// Real time, the default clock
+pub struct SystemClock {
+    #[cfg(any(not(target_arch = "wasm32"), target_os = "wasi"))]
+    start: std::time::Instant,
+    // `Instant` is not available on wasm32-unknown-unknown
+    #[cfg(all(target_arch = "wasm32", not(target_os = "wasi")))]
+    start: f64,
+}
+
+impl SystemClock {
+    #[cfg(any(not(target_arch = "wasm32"), target_os = "wasi"))]
+    pub fn new() -> SystemClock {
+        SystemClock {
+            start: std::time::Instant::now(),
+        }
+    }
+
+    #[cfg(all(target_arch = "wasm32", not(target_os = "wasi")))]
+    pub fn new() -> SystemClock {
+        SystemClock { start: date_now() }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        SystemClock::new()
+    }
+}
+
+impl Clock for SystemClock {
+    #[cfg(any(not(target_arch = "wasm32"), target_os = "wasi"))]
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    #[cfg(all(target_arch = "wasm32", not(target_os = "wasi")))]
+    fn now(&self) -> Duration {
+        Duration::from_secs_f64(((date_now() - self.start) / 1000.0).max(0.0))
+    }
+
+    fn idle_until(&self, deadline: Duration) -> Duration {
+        deadline
+            .checked_sub(self.now())
+            .unwrap_or_else(|| Duration::from_secs(0))
+    }
+}
+
+/// A clock that only moves forward when told to, or when the event loop is idle
+///
+/// An idle event loop jumps straight to the next timer: scripts relying on timers run instantly
+/// and deterministically. Clones share the same time.
+#[derive(Clone, Default)]
+pub struct VirtualClock {
+    now: Rc<Cell<Duration>>,
+}
+
+impl VirtualClock {
+    pub fn new() -> VirtualClock {
+        VirtualClock::default()
+    }
+
+    /// Moves the clock forward by `duration`
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+
+    fn idle_until(&self, deadline: Duration) -> Duration {
+        if deadline > self.now.get() {
+            self.now.set(deadline);
+        }
+        Duration::from_secs(0)
+    }
+}
@@ -0,0 +1,36 @@
+/// Handle to a script compiled by a `ScriptingEnvironment`
+///
+/// The compiled code itself is kept by the environment that produced the handle: it can only be
+/// run by that environment, using `ScriptingEnvironment::run_compiled`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CompiledScript {
+    id: String,
+    script_name: String,
+    is_expression: bool,
+}
+
+impl CompiledScript {
+    /// Creates a new, unique, handle
+    pub fn new(script_name: &str, is_expression: bool) -> CompiledScript {
+        CompiledScript {
+            id: format!("compiled${}${}", script_name, uuid::Uuid::new_v4()),
+            script_name: script_name.to_string(),
+            is_expression,
+        }
+    }
+
+    /// Unique identifier of the compiled script in its environment
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Name (file path or URL) the script was compiled with
+    pub fn script_name(&self) -> &str {
+        &self.script_name
+    }
+
+    /// Whether the script was compiled as a single expression that returns a value
+    pub fn is_expression(&self) -> bool {
+        self.is_expression
+    }
+}
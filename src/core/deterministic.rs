@@ -0,0 +1,85 @@
+use super::{clock::VirtualClock, error::ScriptError, ScriptingEnvironment};
+use std::rc::Rc;
+
+/// Core handlers behind the removed APIs: scripts could still reach them through `callToRust`
+const REMOVED_HANDLERS: [&str; 3] = ["crypto$randomBytes", "crypto$randomUUID", "worker$spawn"];
+
+/// Options of the deterministic mode
+#[derive(Clone)]
+pub struct DeterministicOptions {
+    /// Seed of `Math.random`
+    pub seed: u64,
+    /// Value of `Date.now()` while `clock` is at zero, in milliseconds since the Unix epoch
+    pub start_time: u64,
+    /// Locale used when scripts don't give one to `Intl` and `toLocaleString` methods
+    pub locale: String,
+    /// Drives `Date`, `performance.now` and the timers of the environment
+    pub clock: VirtualClock,
+}
+
+impl Default for DeterministicOptions {
+    fn default() -> Self {
+        DeterministicOptions {
+            seed: 0,
+            start_time: 0,
+            locale: String::from("en-US"),
+            clock: VirtualClock::new(),
+        }
+    }
+}
+
+/// Expands the seed into the state of the script generator
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Makes script execution on `s_env` reproducible
+///
+/// `Math.random` gets seeded, `Date` follows `options.clock` and always uses UTC, locale-dependent
+/// APIs default to `options.locale` and APIs depending on thread scheduling, the garbage collector
+/// or entropy (`Worker`, `WeakRef`, `FinalizationRegistry`, `SharedArrayBuffer`, `Atomics` and
+/// `crypto`) are removed, along with the handlers behind them. Optional APIs have to be installed
+/// before.
+pub fn install(
+    s_env: &mut dyn ScriptingEnvironment,
+    options: DeterministicOptions,
+) -> Result<(), ScriptError> {
+    let mut seed = options.seed;
+    let (first, second) = (splitmix64(&mut seed), splitmix64(&mut seed));
+    let state = [
+        (first >> 32) as u32,
+        first as u32,
+        (second >> 32) as u32,
+        second as u32,
+    ];
+    let config = serde_json::json!({ "state": state, "locale": options.locale }).to_string();
+    s_env.register_core_handler(
+        "deterministic$config",
+        Box::new(move |_| Ok(config.clone())),
+    );
+
+    let event_loop = s_env.event_loop();
+    event_loop
+        .borrow_mut()
+        .set_clock(Rc::new(options.clock.clone()));
+    let start_time = options.start_time;
+    s_env.register_core_handler(
+        "deterministic$now",
+        Box::new(move |_| {
+            let now = event_loop.borrow().clock().now();
+            Ok((start_time + now.as_millis() as u64).to_string())
+        }),
+    );
+    for handler_name in REMOVED_HANDLERS.iter() {
+        let message = format!("{} is not available in deterministic mode", handler_name);
+        s_env.register_core_handler(handler_name, Box::new(move |_| Err(message.clone())));
+    }
+    s_env.run_named(
+        include_str!("../js/deterministic.js"),
+        "scriptit:deterministic.js",
+    )
+}
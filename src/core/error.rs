@@ -32,3 +32,16 @@ impl std::fmt::Display for ScriptError {
 }
 
 impl std::error::Error for ScriptError {}
+
+/// Messages become runtime errors, so functions exported to scripts can fail with a `String`
+impl From<String> for ScriptError {
+    fn from(message: String) -> Self {
+        ScriptError::RuntimeError(message)
+    }
+}
+
+impl From<&str> for ScriptError {
+    fn from(message: &str) -> Self {
+        ScriptError::RuntimeError(message.to_string())
+    }
+}
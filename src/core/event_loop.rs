@@ -0,0 +1,207 @@
+use super::{
+    clock::{Clock, SystemClock},
+    error::ScriptError,
+    ScriptingEnvironment,
+};
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    rc::Rc,
+    sync::mpsc::{channel, Receiver, Sender, TryRecvError},
+    time::Duration,
+};
+
+/// A unit of work run by the event loop, with access to the environment
+pub type Task = Box<dyn FnOnce(&mut dyn ScriptingEnvironment) -> Result<(), ScriptError>>;
+/// A `Task` that can be queued from another thread
+pub type RemoteTask =
+    Box<dyn FnOnce(&mut dyn ScriptingEnvironment) -> Result<(), ScriptError> + Send>;
+
+/// Queues tasks on an event loop from any thread
+#[derive(Clone)]
+pub struct RemoteTaskSender {
+    sender: Sender<RemoteTask>,
+}
+
+impl RemoteTaskSender {
+    /// Queues `task`, returns `false` if the event loop is gone
+    pub fn send(&self, task: RemoteTask) -> bool {
+        self.sender.send(task).is_ok()
+    }
+}
+
+/// Tasks waiting to run in an environment, and what keeps the environment waiting for more
+///
+/// Handlers registered on the environment queue tasks (timers firing, messages arriving...) that
+/// `ScriptingEnvironment::run_event_loop` runs in order. Pending operations are keys representing
+/// work that will queue tasks later on, the event loop only stops once none is left, and no timer
+/// is left to fire.
+pub struct EventLoop {
+    tasks: VecDeque<Task>,
+    remote_sender: Sender<RemoteTask>,
+    remote_tasks: Receiver<RemoteTask>,
+    pending_ops: HashSet<String>,
+    clock: Rc<dyn Clock>,
+    // Ordered by deadline, then by id to keep timers with the same deadline in order
+    timers: BTreeMap<(Duration, u64), Task>,
+    next_timer_id: u64,
+}
+
+impl EventLoop {
+    pub fn new() -> EventLoop {
+        let (remote_sender, remote_tasks) = channel();
+        EventLoop {
+            tasks: VecDeque::new(),
+            remote_sender,
+            remote_tasks,
+            pending_ops: HashSet::new(),
+            clock: Rc::new(SystemClock::new()),
+            timers: BTreeMap::new(),
+            next_timer_id: 0,
+        }
+    }
+
+    /// Replaces the clock timers are measured with, timers already set keep their deadline
+    pub fn set_clock(&mut self, clock: Rc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    pub fn clock(&self) -> Rc<dyn Clock> {
+        Rc::clone(&self.clock)
+    }
+
+    /// Queues `task` once `delay` has elapsed, returns an id to cancel it with `cancel_timer`
+    pub fn queue_timer(&mut self, delay: Duration, task: Task) -> u64 {
+        let id = self.next_timer_id;
+        self.next_timer_id += 1;
+        self.timers.insert((self.clock.now() + delay, id), task);
+        id
+    }
+
+    /// Cancels a timer that did not fire yet
+    pub fn cancel_timer(&mut self, id: u64) {
+        let key = self
+            .timers
+            .keys()
+            .find(|(_, timer_id)| *timer_id == id)
+            .cloned();
+        if let Some(key) = key {
+            self.timers.remove(&key);
+        }
+    }
+
+    /// Queues a task from the environment thread
+    pub fn queue_task(&mut self, task: Task) {
+        self.tasks.push_back(task);
+    }
+
+    /// Returns a sender to queue tasks from other threads
+    pub fn remote(&self) -> RemoteTaskSender {
+        RemoteTaskSender {
+            sender: self.remote_sender.clone(),
+        }
+    }
+
+    /// Keeps the event loop running until `complete_pending_op` is called with the same key
+    pub fn add_pending_op(&mut self, key: &str) {
+        self.pending_ops.insert(key.to_string());
+    }
+
+    pub fn complete_pending_op(&mut self, key: &str) {
+        self.pending_ops.remove(key);
+    }
+
+    pub fn has_pending_ops(&self) -> bool {
+        !self.pending_ops.is_empty()
+    }
+
+    fn next_timer_deadline(&self) -> Option<Duration> {
+        self.timers.keys().next().map(|(deadline, _)| *deadline)
+    }
+
+    fn next_ready_task(&mut self) -> Option<Task> {
+        if let Some(task) = self.tasks.pop_front() {
+            return Some(task);
+        }
+        match self.next_timer_deadline() {
+            Some(deadline) if deadline <= self.clock.now() => {
+                let key = *self.timers.keys().next().unwrap();
+                return self.timers.remove(&key);
+            }
+            _ => {}
+        }
+        match self.remote_tasks.try_recv() {
+            Ok(task) => Some(task),
+            Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => None,
+        }
+    }
+
+    /// Returns the next task to run, waiting for it if needed, or `None` once there is nothing left to do
+    fn next_task(&mut self) -> Result<Option<Task>, ScriptError> {
+        loop {
+            if let Some(task) = self.next_ready_task() {
+                return Ok(Some(task));
+            }
+            match self.next_timer_deadline() {
+                Some(deadline) => {
+                    let timeout = self.clock.idle_until(deadline);
+                    if let Some(task) = self.wait_for_remote_task(Some(timeout))? {
+                        return Ok(Some(task));
+                    }
+                }
+                None if self.has_pending_ops() => return self.wait_for_remote_task(None),
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Waits for a task from another thread, `None` when `timeout` elapsed first
+    #[cfg(any(not(target_arch = "wasm32"), target_os = "wasi"))]
+    fn wait_for_remote_task(
+        &mut self,
+        timeout: Option<Duration>,
+    ) -> Result<Option<Task>, ScriptError> {
+        let task = match timeout {
+            Some(timeout) => self.remote_tasks.recv_timeout(timeout).ok(),
+            None => self.remote_tasks.recv().ok(),
+        };
+        Ok(task.map(|task| task as Task))
+    }
+
+    // Blocking would freeze the host JS engine: only ready tasks run on wasm, and timers that are
+    // due right away (as with a `VirtualClock`). Having to wait for anything else is an error.
+    #[cfg(all(target_arch = "wasm32", not(target_os = "wasi")))]
+    fn wait_for_remote_task(
+        &mut self,
+        timeout: Option<Duration>,
+    ) -> Result<Option<Task>, ScriptError> {
+        match timeout {
+            Some(timeout) if timeout == Duration::from_secs(0) => Ok(None),
+            _ => Err(ScriptError::RuntimeError(format!(
+                "The event loop can't wait on wasm, {} timer(s) and {} pending operation(s) left",
+                self.timers.len(),
+                self.pending_ops.len()
+            ))),
+        }
+    }
+}
+
+impl Default for EventLoop {
+    fn default() -> Self {
+        EventLoop::new()
+    }
+}
+
+/// Runs the event loop of `s_env` until no task or pending operation is left
+///
+/// This is what `ScriptingEnvironment::run_event_loop` implementations call.
+pub fn run(s_env: &mut dyn ScriptingEnvironment) -> Result<(), ScriptError> {
+    let event_loop = s_env.event_loop();
+    loop {
+        // The borrow has to be released before running the task: it may queue other tasks
+        let task = event_loop.borrow_mut().next_task()?;
+        match task {
+            Some(task) => task(s_env)?,
+            None => return Ok(()),
+        }
+    }
+}
@@ -0,0 +1,411 @@
+//! Rust functions and types exported to scripts
+//!
+//! `#[scriptit::function]` (with the `derive` feature) implements `ScriptFunction` for a
+//! function: its arguments are converted with `FromScriptValue`, its result with
+//! `IntoScriptValue`, and a returned `Err` is thrown in the script. `#[scriptit::class]` does the
+//! same for the `pub` functions of an `impl` block with `ScriptClass`: `new` becomes the
+//! constructor of `ScriptIt.classes.<Type>`, methods and associated functions become its
+//! methods and static methods.
+//!
+//! ```
+//! # #[cfg(feature = "derive")]
+//! # {
+//! use scriptit::{
+//!     core::{
+//!         export::{self, TypeDefinitions},
+//!         value::ScriptValue,
+//!         ScriptingEnvironment,
+//!     },
+//!     platform::PlatformScriptingEnvironment,
+//! };
+//!
+//! /// Greets someone
+//! #[scriptit::function]
+//! fn greet(name: &str, excited: Option<bool>) -> String {
+//!     format!("Hello {}{}", name, if excited == Some(true) { "!" } else { "." })
+//! }
+//!
+//! pub struct Counter {
+//!     count: u32,
+//! }
+//!
+//! #[scriptit::class]
+//! impl Counter {
+//!     pub fn new(start: u32) -> Counter {
+//!         Counter { count: start }
+//!     }
+//!
+//!     pub fn increment(&mut self) -> Result<u32, String> {
+//!         self.count = self.count.checked_add(1).ok_or("Too many")?;
+//!         Ok(self.count)
+//!     }
+//! }
+//!
+//! let mut s_env = PlatformScriptingEnvironment::new();
+//! export::register_function::<greet>(&mut s_env);
+//! export::register_class::<Counter>(&mut s_env).unwrap();
+//!
+//! let res = s_env
+//!     .eval_expression("new ScriptIt.classes.Counter(41).increment()")
+//!     .unwrap();
+//! assert_eq!(res, ScriptValue::from(42));
+//!
+//! let definitions = TypeDefinitions::new().function::<greet>().to_string();
+//! assert!(definitions.contains("function greet(name: string, excited?: boolean | null): string;"));
+//! # }
+//! ```
+
+use super::{
+    error::ScriptError,
+    value::{FromScriptValue, IntoScriptValue, ScriptValue},
+    ScriptingEnvironment,
+};
+use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
+
+/// Describes a function exported to scripts, for documentation and type definitions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FunctionMetadata {
+    /// Name of the function in scripts
+    pub name: &'static str,
+    /// Doc comment of the Rust function
+    pub docs: &'static str,
+    pub params: &'static [ParamMetadata],
+    /// TypeScript type of the result
+    pub returns: &'static str,
+}
+
+/// Describes a parameter of a function exported to scripts
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParamMetadata {
+    pub name: &'static str,
+    /// TypeScript type of the parameter
+    pub ty: &'static str,
+    /// Whether `null` is accepted (the Rust parameter is an `Option`), so it can be left out
+    pub optional: bool,
+}
+
+/// Describes a class exported to scripts, for documentation and type definitions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClassMetadata {
+    /// Name of the class in `ScriptIt.classes`
+    pub name: &'static str,
+    /// Doc comment of the `impl` block
+    pub docs: &'static str,
+    /// The `new` function, without it the class can't be constructed from scripts
+    pub constructor: Option<FunctionMetadata>,
+    pub methods: &'static [MethodMetadata],
+}
+
+/// Describes a method of a class exported to scripts
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MethodMetadata {
+    pub function: FunctionMetadata,
+    /// Whether it is an associated function, a `static` method in scripts
+    pub is_static: bool,
+}
+
+/// A Rust function callable from scripts, implemented by `#[scriptit::function]`
+pub trait ScriptFunction {
+    const METADATA: FunctionMetadata;
+
+    /// Converts the arguments, calls the function and converts its result
+    fn call(args: &[ScriptValue]) -> Result<ScriptValue, ScriptError>;
+}
+
+/// A Rust type constructible from scripts, implemented by `#[scriptit::class]`
+pub trait ScriptClass: Sized + 'static {
+    const METADATA: ClassMetadata;
+
+    /// Registers the constructor and methods, instances are kept in `instances`
+    #[doc(hidden)]
+    fn register_methods(s_env: &mut dyn ScriptingEnvironment, instances: &Instances<Self>);
+}
+
+/// Registers `F` as `ScriptIt.funcs.<name>`
+pub fn register_function<F: ScriptFunction>(s_env: &mut dyn ScriptingEnvironment) {
+    s_env.register_func(F::METADATA.name, Box::new(|args| F::call(args)));
+}
+
+/// Registers `C` as `ScriptIt.classes.<name>`
+///
+/// Instances live in Rust until `free()` is called on them in scripts, or until they are garbage
+/// collected on engines with `FinalizationRegistry`.
+pub fn register_class<C: ScriptClass>(
+    s_env: &mut dyn ScriptingEnvironment,
+) -> Result<(), ScriptError> {
+    let name = C::METADATA.name;
+    let instances = Instances::<C>::default();
+    C::register_methods(s_env, &instances);
+    s_env.register_func(
+        &handler_name(name, "free"),
+        Box::new(move |args| {
+            let (id,): (u64,) = super::value::from_args(args)?;
+            instances.remove(id);
+            Ok(ScriptValue::Null)
+        }),
+    );
+    s_env.run_named(
+        &class_source(&C::METADATA),
+        &format!("scriptit:class:{}", name),
+    )
+}
+
+/// Name of the function behind a constructor (`new`) or method of a class
+#[doc(hidden)]
+pub fn handler_name(class_name: &str, method_name: &str) -> String {
+    format!("{}${}", class_name, method_name)
+}
+
+fn class_source(metadata: &ClassMetadata) -> String {
+    let name = metadata.name;
+    let constructor = if metadata.constructor.is_some() {
+        format!(
+            "const id = funcs[\"{}\"](...args);
+            this[ID] = id;
+            if (registry) registry.register(this, id, this);",
+            handler_name(name, "new")
+        )
+    } else {
+        format!("throw new TypeError(\"{} has no constructor\");", name)
+    };
+    let mut methods = String::new();
+    for method in metadata.methods {
+        let method_name = method.function.name;
+        let handler = handler_name(name, method_name);
+        if method.is_static {
+            methods.push_str(&format!(
+                "static {}(...args) {{ return funcs[\"{}\"](...args); }}\n",
+                method_name, handler
+            ));
+        } else {
+            methods.push_str(&format!(
+                "{}(...args) {{ return funcs[\"{}\"](idOf(this), ...args); }}\n",
+                method_name, handler
+            ));
+        }
+    }
+    format!(
+        "(() => {{
+    const funcs = ScriptIt.funcs;
+    const free = funcs[\"{free}\"];
+    const ID = Symbol(\"{name}\");
+    const registry =
+        typeof FinalizationRegistry === \"function\" ? new FinalizationRegistry(free) : null;
+    const idOf = (instance) => {{
+        if (!instance || instance[ID] === undefined) {{
+            throw new TypeError(\"Not a live {name} instance\");
+        }}
+        return instance[ID];
+    }};
+    class {name} {{
+        constructor(...args) {{
+            {constructor}
+        }}
+        {methods}
+        free() {{
+            const id = idOf(this);
+            this[ID] = undefined;
+            if (registry) registry.unregister(this);
+            free(id);
+        }}
+    }}
+    ScriptIt.classes = ScriptIt.classes || {{}};
+    ScriptIt.classes.{name} = {name};
+}})();",
+        free = handler_name(name, "free"),
+        name = name,
+        constructor = constructor,
+        methods = methods,
+    )
+}
+
+/// Instances of a class created from scripts, by id
+#[doc(hidden)]
+pub struct Instances<T> {
+    inner: Rc<RefCell<(u64, HashMap<u64, T>)>>,
+}
+
+impl<T> Default for Instances<T> {
+    fn default() -> Self {
+        Instances {
+            inner: Rc::new(RefCell::new((0, HashMap::new()))),
+        }
+    }
+}
+
+impl<T> Clone for Instances<T> {
+    fn clone(&self) -> Self {
+        Instances {
+            inner: Rc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T> Instances<T> {
+    pub fn insert(&self, instance: T) -> u64 {
+        let mut inner = self.inner.borrow_mut();
+        inner.0 += 1;
+        let id = inner.0;
+        inner.1.insert(id, instance);
+        id
+    }
+
+    pub fn remove(&self, id: u64) -> Option<T> {
+        self.inner.borrow_mut().1.remove(&id)
+    }
+
+    /// Calls `f` on the instance the method was called on, the first of `args`
+    pub fn call<F>(&self, args: &[ScriptValue], f: F) -> Result<ScriptValue, ScriptError>
+    where
+        F: FnOnce(&mut T, &[ScriptValue]) -> Result<ScriptValue, ScriptError>,
+    {
+        let (this, args) = match args.split_first() {
+            Some((this, args)) => (u64::from_script_value(this)?, args),
+            None => return Err(ScriptError::RuntimeError(String::from("Missing instance"))),
+        };
+        let mut inner = self.inner.borrow_mut();
+        let instance = inner
+            .1
+            .get_mut(&this)
+            .ok_or_else(|| ScriptError::RuntimeError(format!("Instance {} was freed", this)))?;
+        f(instance, args)
+    }
+}
+
+/// Result of a function exported to scripts: a value, or a `Result` with an error convertible
+/// into a `ScriptError`
+pub trait IntoScriptResult {
+    fn into_script_result(self) -> Result<ScriptValue, ScriptError>;
+}
+
+impl<T: IntoScriptValue> IntoScriptResult for T {
+    fn into_script_result(self) -> Result<ScriptValue, ScriptError> {
+        self.into_script_value()
+    }
+}
+
+impl<T: IntoScriptValue, E: Into<ScriptError>> IntoScriptResult for Result<T, E> {
+    fn into_script_result(self) -> Result<ScriptValue, ScriptError> {
+        self.map_err(Into::into)?.into_script_value()
+    }
+}
+
+/// Result of the `new` function of a class: the instance, or a `Result` holding it
+pub trait IntoInstance<T> {
+    fn into_instance(self) -> Result<T, ScriptError>;
+}
+
+impl<T> IntoInstance<T> for T {
+    fn into_instance(self) -> Result<T, ScriptError> {
+        Ok(self)
+    }
+}
+
+impl<T, E: Into<ScriptError>> IntoInstance<T> for Result<T, E> {
+    fn into_instance(self) -> Result<T, ScriptError> {
+        self.map_err(Into::into)
+    }
+}
+
+/// Builds TypeScript definitions (`.d.ts`) of exported functions and classes
+///
+/// Types the definitions can't describe, like `Serde` ones, are `unknown`.
+#[derive(Clone, Debug, Default)]
+pub struct TypeDefinitions {
+    functions: Vec<FunctionMetadata>,
+    classes: Vec<ClassMetadata>,
+}
+
+impl TypeDefinitions {
+    pub fn new() -> TypeDefinitions {
+        TypeDefinitions::default()
+    }
+
+    /// Adds a function registered with `register_function`
+    pub fn function<F: ScriptFunction>(mut self) -> TypeDefinitions {
+        self.functions.push(F::METADATA);
+        self
+    }
+
+    /// Adds a class registered with `register_class`
+    pub fn class<C: ScriptClass>(mut self) -> TypeDefinitions {
+        self.classes.push(C::METADATA);
+        self
+    }
+}
+
+fn write_docs(f: &mut fmt::Formatter<'_>, docs: &str, indent: &str) -> fmt::Result {
+    if docs.is_empty() {
+        return Ok(());
+    }
+    writeln!(f, "{}/**", indent)?;
+    for line in docs.lines() {
+        writeln!(f, "{} * {}", indent, line.replace("*/", "*\\/").trim_end())?;
+    }
+    writeln!(f, "{} */", indent)
+}
+
+/// Parameters only are optional when the ones after them are too
+fn write_params(f: &mut fmt::Formatter<'_>, params: &[ParamMetadata]) -> fmt::Result {
+    let required = params
+        .iter()
+        .rposition(|param| !param.optional)
+        .map_or(0, |position| position + 1);
+    for (i, param) in params.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        let optional = if i >= required { "?" } else { "" };
+        write!(f, "{}{}: {}", param.name, optional, param.ty)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for TypeDefinitions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "declare namespace ScriptIt {{")?;
+        if !self.functions.is_empty() {
+            writeln!(f, "    namespace funcs {{")?;
+            for function in &self.functions {
+                write_docs(f, function.docs, "        ")?;
+                write!(f, "        function {}(", function.name)?;
+                write_params(f, function.params)?;
+                writeln!(f, "): {};", function.returns)?;
+            }
+            writeln!(f, "    }}")?;
+        }
+        if !self.classes.is_empty() {
+            writeln!(f, "    namespace classes {{")?;
+            for class in &self.classes {
+                write_docs(f, class.docs, "        ")?;
+                writeln!(f, "        class {} {{", class.name)?;
+                match &class.constructor {
+                    Some(constructor) => {
+                        write_docs(f, constructor.docs, "            ")?;
+                        write!(f, "            constructor(")?;
+                        write_params(f, constructor.params)?;
+                        writeln!(f, ");")?;
+                    }
+                    None => writeln!(f, "            private constructor();")?,
+                }
+                for method in class.methods {
+                    let function = &method.function;
+                    write_docs(f, function.docs, "            ")?;
+                    let prefix = if method.is_static { "static " } else { "" };
+                    write!(f, "            {}{}(", prefix, function.name)?;
+                    write_params(f, function.params)?;
+                    writeln!(f, "): {};", function.returns)?;
+                }
+                write_docs(
+                    f,
+                    "Frees the Rust value behind the instance",
+                    "            ",
+                )?;
+                writeln!(f, "            free(): void;")?;
+                writeln!(f, "        }}")?;
+            }
+            writeln!(f, "    }}")?;
+        }
+        writeln!(f, "}}")
+    }
+}
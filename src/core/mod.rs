@@ -1,18 +1,82 @@
 //! Core constructs available on all platforms
 
+/// Contains the clocks timers are measured with
+pub mod clock;
+/// Contains the compiled script handle
+pub mod compiled;
+/// Contains the deterministic execution mode
+pub mod deterministic;
 /// Contains the main error type
 pub mod error;
+/// Contains the event loop running asynchronous work
+pub mod event_loop;
+/// Contains the traits behind `#[scriptit::function]` and `#[scriptit::class]`
+pub mod export;
+/// Contains the handlers behind `setTimeout` and `setInterval`
+pub mod timers;
 /// Contains the main value type
 pub mod value;
 
+use compiled::CompiledScript;
 use error::ScriptError;
+use event_loop::EventLoop;
+use std::{cell::RefCell, rc::Rc};
 use value::ScriptValue;
 
+/// Name given to scripts that were not run with an explicit name
+pub const ANONYMOUS_SCRIPT_NAME: &str = "<anonymous>";
+
 pub trait ScriptingEnvironment {
+    /// Evaluates a single JS expression, `script_name` (a file path or URL) will be used in errors and stack traces
+    fn eval_expression_named(
+        &mut self,
+        source: &str,
+        script_name: &str,
+    ) -> Result<ScriptValue, ScriptError>;
+    /// Runs JavaScript code, `script_name` (a file path or URL) will be used in errors and stack traces
+    fn run_named(&mut self, source: &str, script_name: &str) -> Result<(), ScriptError>;
     /// Evaluates a single JS expression
-    fn eval_expression(&mut self, source: &str) -> Result<ScriptValue, ScriptError>;
+    fn eval_expression(&mut self, source: &str) -> Result<ScriptValue, ScriptError> {
+        self.eval_expression_named(source, ANONYMOUS_SCRIPT_NAME)
+    }
     /// Runs JavaScript code
-    fn run(&mut self, source: &str) -> Result<(), ScriptError>;
+    fn run(&mut self, source: &str) -> Result<(), ScriptError> {
+        self.run_named(source, ANONYMOUS_SCRIPT_NAME)
+    }
+    /// Compiles JavaScript code once so it can be run many times with `run_compiled`
+    fn compile_named(
+        &mut self,
+        source: &str,
+        script_name: &str,
+    ) -> Result<CompiledScript, ScriptError>;
+    /// Compiles a single JS expression once so it can be evaluated many times with `run_compiled`
+    fn compile_expression_named(
+        &mut self,
+        source: &str,
+        script_name: &str,
+    ) -> Result<CompiledScript, ScriptError>;
+    /// Runs a compiled script: returns the value of compiled expressions and `ScriptValue::Null` otherwise
+    fn run_compiled(&mut self, script: &CompiledScript) -> Result<ScriptValue, ScriptError>;
+    /// Frees the compiled code behind a handle, it can't be run anymore
+    fn release_compiled(&mut self, script: CompiledScript);
+    /// Compiles JavaScript code once so it can be run many times with `run_compiled`
+    fn compile(&mut self, source: &str) -> Result<CompiledScript, ScriptError> {
+        self.compile_named(source, ANONYMOUS_SCRIPT_NAME)
+    }
+    /// Compiles a single JS expression once so it can be evaluated many times with `run_compiled`
+    fn compile_expression(&mut self, source: &str) -> Result<CompiledScript, ScriptError> {
+        self.compile_expression_named(source, ANONYMOUS_SCRIPT_NAME)
+    }
+    /// Columns added in front of the first line of an expression before it is evaluated, errors
+    /// report their columns in the wrapped code
+    fn expression_column_offset(&self) -> u32;
+    /// Event loop of the environment, handlers use it to queue tasks
+    fn event_loop(&self) -> Rc<RefCell<EventLoop>>;
+    /// Runs queued tasks until none is left and no pending operation can queue more
+    ///
+    /// On wasm, the host JS engine can't be blocked: the tasks that are ready get run, then an
+    /// error is returned if the event loop would have to wait for a timer or a pending operation.
+    fn run_event_loop(&mut self) -> Result<(), ScriptError>;
     /// Registers a low-level handler
     fn register_core_handler(
         &mut self,
@@ -23,25 +87,38 @@ pub trait ScriptingEnvironment {
     fn register_func(
         &mut self,
         func_name: &str,
-        mut handler_closure: Box<dyn FnMut(&Vec<ScriptValue>) -> Result<ScriptValue, ScriptError>>,
+        handler_closure: Box<dyn FnMut(&Vec<ScriptValue>) -> Result<ScriptValue, ScriptError>>,
     ) {
-        let core_handler_name = format!("func${}${}", func_name, uuid::Uuid::new_v4());
-        self.register_core_handler(
-            &core_handler_name,
-            Box::new(move |data_str: &str| {
-                let args: ScriptValue =
-                    serde_json::from_str(data_str).map_err(|err| err.to_string())?;
-                let args = args
-                    .as_array()
-                    .ok_or("Couldn't convert args to array of values to pass in rust")?;
-                let res = handler_closure(&args).map_err(|err| err.to_string())?;
-                return Ok(res.to_string());
-            }),
-        );
-        let src = format!(
-            "(ScriptIt.core.registerFunc('{}', '{}'), null)",
-            func_name, core_handler_name
-        );
-        self.eval_expression(&src).unwrap();
+        register_func_handler(self, func_name, handler_closure);
     }
 }
+
+/// Handler of a function call
+type FuncHandler = Box<dyn FnMut(&Vec<ScriptValue>) -> Result<ScriptValue, ScriptError>>;
+
+/// Registers a function call on `s_env`, returns the name of the core handler behind it
+pub(crate) fn register_func_handler<E: ScriptingEnvironment + ?Sized>(
+    s_env: &mut E,
+    func_name: &str,
+    mut handler_closure: FuncHandler,
+) -> String {
+    let core_handler_name = format!("func${}${}", func_name, uuid::Uuid::new_v4());
+    s_env.register_core_handler(
+        &core_handler_name,
+        Box::new(move |data_str: &str| {
+            let args: ScriptValue =
+                serde_json::from_str(data_str).map_err(|err| err.to_string())?;
+            let args = args
+                .as_array()
+                .ok_or("Couldn't convert args to array of values to pass in rust")?;
+            let res = handler_closure(args).map_err(|err| err.to_string())?;
+            Ok(res.to_string())
+        }),
+    );
+    let src = format!(
+        "(ScriptIt.core.registerFunc('{}', '{}'), null)",
+        func_name, core_handler_name
+    );
+    s_env.eval_expression(&src).unwrap();
+    core_handler_name
+}
@@ -0,0 +1,39 @@
+use super::ScriptingEnvironment;
+use serde::Deserialize;
+use std::time::Duration;
+
+#[derive(Deserialize)]
+struct SetTimerRequest {
+    id: u64,
+    delay: f64,
+}
+
+/// Registers the handlers behind `setTimeout` and `setInterval` on `s_env`
+///
+/// Timers are queued on the event loop of the environment: they fire while it runs.
+pub fn install(s_env: &mut dyn ScriptingEnvironment) {
+    let event_loop = s_env.event_loop();
+    s_env.register_core_handler(
+        "timers$set",
+        Box::new(move |data| {
+            let request: SetTimerRequest = serde_json::from_str(data).map_err(|e| e.to_string())?;
+            let delay =
+                Duration::from_secs_f64(request.delay.max(0.0).min(f64::from(i32::MAX)) / 1000.0);
+            let src = format!("ScriptIt.core.fireTimer({})", request.id);
+            let timer_id = event_loop
+                .borrow_mut()
+                .queue_timer(delay, Box::new(move |s_env| s_env.run(&src)));
+            Ok(timer_id.to_string())
+        }),
+    );
+
+    let event_loop = s_env.event_loop();
+    s_env.register_core_handler(
+        "timers$clear",
+        Box::new(move |data| {
+            let timer_id = data.parse::<u64>().map_err(|e| e.to_string())?;
+            event_loop.borrow_mut().cancel_timer(timer_id);
+            Ok(String::new())
+        }),
+    );
+}
@@ -1,2 +1,692 @@
-pub type ScriptValue = serde_json::Value;
+//! Values going between scripts and Rust
+//!
+//! A `ScriptValue` holds anything JSON can: scripts give values to Rust, and get them back, as
+//! JSON. `FromScriptValue` and `IntoScriptValue` convert it from and to Rust types, serde types
+//! included with `Serde`.
+//!
+//! ```
+//! use scriptit::core::value::{from_args, ScriptValue};
+//! use std::collections::HashMap;
+//!
+//! let args = vec![ScriptValue::from("retries"), ScriptValue::from(vec![1, 2])];
+//! let (name, counts): (String, Vec<u8>) = from_args(&args).unwrap();
+//! assert_eq!((name.as_str(), counts), ("retries", vec![1, 2]));
+//!
+//! let limits = ScriptValue::from(HashMap::from([("max", 300)]));
+//! assert_eq!(limits["max"].cast::<u16>().unwrap(), 300);
+//! assert!(limits["max"].cast::<u8>().is_err());
+//! ```
+
+use super::error::ScriptError;
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    any::type_name,
+    collections::{BTreeMap, HashMap},
+    fmt,
+    hash::BuildHasher,
+    iter::FromIterator,
+    ops::Index,
+};
+
 pub type ScriptNumber = serde_json::Number;
+/// Properties of a `ScriptValue::Object`, sorted by name
+pub type ScriptObject = BTreeMap<String, ScriptValue>;
+
+/// Value of a missing property or array element
+static NULL: ScriptValue = ScriptValue::Null;
+
+/// A value scripts can exchange with Rust: anything JSON can represent
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum ScriptValue {
+    #[default]
+    Null,
+    Bool(bool),
+    Number(ScriptNumber),
+    String(String),
+    Array(Vec<ScriptValue>),
+    Object(ScriptObject),
+}
+
+impl ScriptValue {
+    /// Name of the variant, as in cast errors
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ScriptValue::Null => "ScriptValue::Null",
+            ScriptValue::Bool(_) => "ScriptValue::Bool",
+            ScriptValue::Number(_) => "ScriptValue::Number",
+            ScriptValue::String(_) => "ScriptValue::String",
+            ScriptValue::Array(_) => "ScriptValue::Array",
+            ScriptValue::Object(_) => "ScriptValue::Object",
+        }
+    }
+
+    /// Converts the value into `T`, or fails with a `ScriptError::CastError`
+    pub fn cast<T: FromScriptValue>(&self) -> Result<T, ScriptError> {
+        T::from_script_value(self)
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, ScriptValue::Null)
+    }
+
+    pub fn is_boolean(&self) -> bool {
+        matches!(self, ScriptValue::Bool(_))
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, ScriptValue::Number(_))
+    }
+
+    pub fn is_string(&self) -> bool {
+        matches!(self, ScriptValue::String(_))
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self, ScriptValue::Array(_))
+    }
+
+    pub fn is_object(&self) -> bool {
+        matches!(self, ScriptValue::Object(_))
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            ScriptValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// The number as an `i64`, when it is an integer that fits
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            ScriptValue::Number(number) => number.as_i64(),
+            _ => None,
+        }
+    }
+
+    /// The number as a `u64`, when it is a positive integer that fits
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            ScriptValue::Number(number) => number.as_u64(),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            ScriptValue::Number(number) => number.as_f64(),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ScriptValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<ScriptValue>> {
+        match self {
+            ScriptValue::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<ScriptValue>> {
+        match self {
+            ScriptValue::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&ScriptObject> {
+        match self {
+            ScriptValue::Object(properties) => Some(properties),
+            _ => None,
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut ScriptObject> {
+        match self {
+            ScriptValue::Object(properties) => Some(properties),
+            _ => None,
+        }
+    }
+
+    /// Property of an object (`value.get("name")`) or element of an array (`value.get(0)`)
+    pub fn get<I: ValueIndex>(&self, index: I) -> Option<&ScriptValue> {
+        index.index_into(self)
+    }
+
+    pub fn get_mut<I: ValueIndex>(&mut self, index: I) -> Option<&mut ScriptValue> {
+        index.index_into_mut(self)
+    }
+
+    /// Takes the value out, leaving `ScriptValue::Null` in its place
+    pub fn take(&mut self) -> ScriptValue {
+        std::mem::take(self)
+    }
+}
+
+/// Values are displayed as JSON
+impl fmt::Display for ScriptValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string(self).map_err(|_| fmt::Error)?;
+        f.write_str(&json)
+    }
+}
+
+impl Serialize for ScriptValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ScriptValue::Null => serializer.serialize_unit(),
+            ScriptValue::Bool(value) => serializer.serialize_bool(*value),
+            ScriptValue::Number(number) => number.serialize(serializer),
+            ScriptValue::String(value) => serializer.serialize_str(value),
+            ScriptValue::Array(values) => values.serialize(serializer),
+            ScriptValue::Object(properties) => properties.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ScriptValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        serde_json::Value::deserialize(deserializer).map(ScriptValue::from)
+    }
+}
+
+/// Something a `ScriptValue` can be indexed with: a property name or an array index
+pub trait ValueIndex: private::Sealed {
+    #[doc(hidden)]
+    fn index_into<'v>(&self, value: &'v ScriptValue) -> Option<&'v ScriptValue>;
+    #[doc(hidden)]
+    fn index_into_mut<'v>(&self, value: &'v mut ScriptValue) -> Option<&'v mut ScriptValue>;
+}
+
+mod private {
+    pub trait Sealed {}
+    impl Sealed for usize {}
+    impl Sealed for str {}
+    impl Sealed for String {}
+    impl<T: Sealed + ?Sized> Sealed for &T {}
+}
+
+impl ValueIndex for usize {
+    fn index_into<'v>(&self, value: &'v ScriptValue) -> Option<&'v ScriptValue> {
+        value.as_array().and_then(|values| values.get(*self))
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut ScriptValue) -> Option<&'v mut ScriptValue> {
+        value
+            .as_array_mut()
+            .and_then(|values| values.get_mut(*self))
+    }
+}
+
+impl ValueIndex for str {
+    fn index_into<'v>(&self, value: &'v ScriptValue) -> Option<&'v ScriptValue> {
+        value
+            .as_object()
+            .and_then(|properties| properties.get(self))
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut ScriptValue) -> Option<&'v mut ScriptValue> {
+        value
+            .as_object_mut()
+            .and_then(|properties| properties.get_mut(self))
+    }
+}
+
+impl ValueIndex for String {
+    fn index_into<'v>(&self, value: &'v ScriptValue) -> Option<&'v ScriptValue> {
+        self.as_str().index_into(value)
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut ScriptValue) -> Option<&'v mut ScriptValue> {
+        self.as_str().index_into_mut(value)
+    }
+}
+
+impl<T: ValueIndex + ?Sized> ValueIndex for &T {
+    fn index_into<'v>(&self, value: &'v ScriptValue) -> Option<&'v ScriptValue> {
+        (**self).index_into(value)
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut ScriptValue) -> Option<&'v mut ScriptValue> {
+        (**self).index_into_mut(value)
+    }
+}
+
+/// Missing properties and elements index to `ScriptValue::Null`
+impl<I: ValueIndex> Index<I> for ScriptValue {
+    type Output = ScriptValue;
+
+    fn index(&self, index: I) -> &ScriptValue {
+        index.index_into(self).unwrap_or(&NULL)
+    }
+}
+
+impl From<serde_json::Value> for ScriptValue {
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => ScriptValue::Null,
+            serde_json::Value::Bool(value) => ScriptValue::Bool(value),
+            serde_json::Value::Number(number) => ScriptValue::Number(number),
+            serde_json::Value::String(value) => ScriptValue::String(value),
+            serde_json::Value::Array(values) => {
+                ScriptValue::Array(values.into_iter().map(ScriptValue::from).collect())
+            }
+            serde_json::Value::Object(properties) => ScriptValue::Object(
+                properties
+                    .into_iter()
+                    .map(|(name, value)| (name, ScriptValue::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl From<ScriptValue> for serde_json::Value {
+    fn from(value: ScriptValue) -> Self {
+        match value {
+            ScriptValue::Null => serde_json::Value::Null,
+            ScriptValue::Bool(value) => serde_json::Value::Bool(value),
+            ScriptValue::Number(number) => serde_json::Value::Number(number),
+            ScriptValue::String(value) => serde_json::Value::String(value),
+            ScriptValue::Array(values) => {
+                serde_json::Value::Array(values.into_iter().map(serde_json::Value::from).collect())
+            }
+            ScriptValue::Object(properties) => serde_json::Value::Object(
+                properties
+                    .into_iter()
+                    .map(|(name, value)| (name, serde_json::Value::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl PartialEq<serde_json::Value> for ScriptValue {
+    fn eq(&self, other: &serde_json::Value) -> bool {
+        match (self, other) {
+            (ScriptValue::Null, serde_json::Value::Null) => true,
+            (ScriptValue::Bool(a), serde_json::Value::Bool(b)) => a == b,
+            (ScriptValue::Number(a), serde_json::Value::Number(b)) => a == b,
+            (ScriptValue::String(a), serde_json::Value::String(b)) => a == b,
+            (ScriptValue::Array(a), serde_json::Value::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a == b)
+            }
+            (ScriptValue::Object(a), serde_json::Value::Object(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(name, a)| b.get(name).is_some_and(|b| a == b))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq<ScriptValue> for serde_json::Value {
+    fn eq(&self, other: &ScriptValue) -> bool {
+        other == self
+    }
+}
+
+impl From<bool> for ScriptValue {
+    fn from(value: bool) -> Self {
+        ScriptValue::Bool(value)
+    }
+}
+
+impl From<String> for ScriptValue {
+    fn from(value: String) -> Self {
+        ScriptValue::String(value)
+    }
+}
+
+impl From<&str> for ScriptValue {
+    fn from(value: &str) -> Self {
+        ScriptValue::String(value.to_string())
+    }
+}
+
+impl From<ScriptNumber> for ScriptValue {
+    fn from(number: ScriptNumber) -> Self {
+        ScriptValue::Number(number)
+    }
+}
+
+impl From<()> for ScriptValue {
+    fn from(_: ()) -> Self {
+        ScriptValue::Null
+    }
+}
+
+impl<T: Into<ScriptValue>> From<Option<T>> for ScriptValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(ScriptValue::Null, Into::into)
+    }
+}
+
+impl<T: Into<ScriptValue>> From<Vec<T>> for ScriptValue {
+    fn from(values: Vec<T>) -> Self {
+        ScriptValue::Array(values.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Clone + Into<ScriptValue>> From<&[T]> for ScriptValue {
+    fn from(values: &[T]) -> Self {
+        ScriptValue::Array(values.iter().cloned().map(Into::into).collect())
+    }
+}
+
+impl<K: Into<String>, T: Into<ScriptValue>, S: BuildHasher> From<HashMap<K, T, S>> for ScriptValue {
+    fn from(properties: HashMap<K, T, S>) -> Self {
+        object(properties)
+    }
+}
+
+impl<K: Into<String>, T: Into<ScriptValue>> From<BTreeMap<K, T>> for ScriptValue {
+    fn from(properties: BTreeMap<K, T>) -> Self {
+        object(properties)
+    }
+}
+
+fn object<K: Into<String>, T: Into<ScriptValue>>(
+    properties: impl IntoIterator<Item = (K, T)>,
+) -> ScriptValue {
+    ScriptValue::Object(
+        properties
+            .into_iter()
+            .map(|(name, value)| (name.into(), value.into()))
+            .collect(),
+    )
+}
+
+impl<T: Into<ScriptValue>> FromIterator<T> for ScriptValue {
+    fn from_iter<I: IntoIterator<Item = T>>(values: I) -> Self {
+        ScriptValue::Array(values.into_iter().map(Into::into).collect())
+    }
+}
+
+macro_rules! from_integers {
+    ($($integer:ty)*) => {
+        $(
+            impl From<$integer> for ScriptValue {
+                fn from(value: $integer) -> Self {
+                    ScriptValue::Number(ScriptNumber::from(value))
+                }
+            }
+        )*
+    };
+}
+
+from_integers!(i8 i16 i32 i64 isize u8 u16 u32 u64 usize);
+
+/// `NaN` and infinities are not JSON numbers: they become `ScriptValue::Null`, as in `JSON.stringify`
+impl From<f64> for ScriptValue {
+    fn from(value: f64) -> Self {
+        ScriptNumber::from_f64(value).map_or(ScriptValue::Null, ScriptValue::Number)
+    }
+}
+
+impl From<f32> for ScriptValue {
+    fn from(value: f32) -> Self {
+        ScriptValue::from(f64::from(value))
+    }
+}
+
+macro_rules! partial_eq {
+    ($($other:ty => $convert:expr;)*) => {
+        $(
+            impl PartialEq<$other> for ScriptValue {
+                fn eq(&self, other: &$other) -> bool {
+                    #[allow(clippy::redundant_closure_call)]
+                    ($convert)(self, other)
+                }
+            }
+
+            impl PartialEq<ScriptValue> for $other {
+                fn eq(&self, other: &ScriptValue) -> bool {
+                    other == self
+                }
+            }
+        )*
+    };
+}
+
+partial_eq! {
+    str => |value: &ScriptValue, other: &str| value.as_str() == Some(other);
+    &str => |value: &ScriptValue, other: &&str| value.as_str() == Some(*other);
+    String => |value: &ScriptValue, other: &String| value.as_str() == Some(other.as_str());
+    bool => |value: &ScriptValue, other: &bool| value.as_bool() == Some(*other);
+    i8 => |value: &ScriptValue, other: &i8| value.as_i64() == Some(i64::from(*other));
+    i16 => |value: &ScriptValue, other: &i16| value.as_i64() == Some(i64::from(*other));
+    i32 => |value: &ScriptValue, other: &i32| value.as_i64() == Some(i64::from(*other));
+    i64 => |value: &ScriptValue, other: &i64| value.as_i64() == Some(*other);
+    isize => |value: &ScriptValue, other: &isize| value.as_i64() == Some(*other as i64);
+    u8 => |value: &ScriptValue, other: &u8| value.as_u64() == Some(u64::from(*other));
+    u16 => |value: &ScriptValue, other: &u16| value.as_u64() == Some(u64::from(*other));
+    u32 => |value: &ScriptValue, other: &u32| value.as_u64() == Some(u64::from(*other));
+    u64 => |value: &ScriptValue, other: &u64| value.as_u64() == Some(*other);
+    usize => |value: &ScriptValue, other: &usize| value.as_u64() == Some(*other as u64);
+    f32 => |value: &ScriptValue, other: &f32| value.as_f64() == Some(f64::from(*other));
+    f64 => |value: &ScriptValue, other: &f64| value.as_f64() == Some(*other);
+}
+
+fn cast_error<T: ?Sized>(value: &ScriptValue) -> ScriptError {
+    ScriptError::CastError {
+        type_from: value.type_name(),
+        type_to: type_name::<T>(),
+    }
+}
+
+/// Conversion from a `ScriptValue`, failing with a `ScriptError::CastError` naming both types
+pub trait FromScriptValue: Sized {
+    fn from_script_value(value: &ScriptValue) -> Result<Self, ScriptError>;
+}
+
+/// Conversion into a `ScriptValue`
+///
+/// Everything that converts with `ScriptValue::from` does, serde types do through `Serde`.
+pub trait IntoScriptValue {
+    fn into_script_value(self) -> Result<ScriptValue, ScriptError>;
+}
+
+impl<T: Into<ScriptValue>> IntoScriptValue for T {
+    fn into_script_value(self) -> Result<ScriptValue, ScriptError> {
+        Ok(self.into())
+    }
+}
+
+impl FromScriptValue for ScriptValue {
+    fn from_script_value(value: &ScriptValue) -> Result<Self, ScriptError> {
+        Ok(value.clone())
+    }
+}
+
+/// `ScriptValue::Null` or an empty array, like the arguments of a function taking none
+impl FromScriptValue for () {
+    fn from_script_value(value: &ScriptValue) -> Result<Self, ScriptError> {
+        match value {
+            ScriptValue::Null => Ok(()),
+            ScriptValue::Array(values) if values.is_empty() => Ok(()),
+            _ => Err(cast_error::<()>(value)),
+        }
+    }
+}
+
+impl FromScriptValue for bool {
+    fn from_script_value(value: &ScriptValue) -> Result<Self, ScriptError> {
+        value.as_bool().ok_or_else(|| cast_error::<bool>(value))
+    }
+}
+
+impl FromScriptValue for String {
+    fn from_script_value(value: &ScriptValue) -> Result<Self, ScriptError> {
+        value
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| cast_error::<String>(value))
+    }
+}
+
+/// Integers have to be in range, numbers with a fractional part don't convert
+macro_rules! from_script_integers {
+    ($($integer:ty)*) => {
+        $(
+            impl FromScriptValue for $integer {
+                fn from_script_value(value: &ScriptValue) -> Result<Self, ScriptError> {
+                    use std::convert::TryFrom;
+                    let number = match value {
+                        ScriptValue::Number(number) => number,
+                        _ => return Err(cast_error::<$integer>(value)),
+                    };
+                    let integer = if let Some(integer) = number.as_i64() {
+                        <$integer>::try_from(integer).ok()
+                    } else if let Some(integer) = number.as_u64() {
+                        <$integer>::try_from(integer).ok()
+                    } else {
+                        // JSON numbers like `1e3` are floats, even when they hold an integer
+                        number
+                            .as_f64()
+                            .filter(|float| float.fract() == 0.0 && float.abs() < 2f64.powi(53))
+                            .and_then(|float| <$integer>::try_from(float as i64).ok())
+                    };
+                    integer.ok_or_else(|| cast_error::<$integer>(value))
+                }
+            }
+        )*
+    };
+}
+
+from_script_integers!(i8 i16 i32 i64 isize u8 u16 u32 u64 usize);
+
+impl FromScriptValue for f64 {
+    fn from_script_value(value: &ScriptValue) -> Result<Self, ScriptError> {
+        value.as_f64().ok_or_else(|| cast_error::<f64>(value))
+    }
+}
+
+impl FromScriptValue for f32 {
+    fn from_script_value(value: &ScriptValue) -> Result<Self, ScriptError> {
+        value
+            .as_f64()
+            .map(|float| float as f32)
+            .ok_or_else(|| cast_error::<f32>(value))
+    }
+}
+
+/// `ScriptValue::Null` converts to `None`
+impl<T: FromScriptValue> FromScriptValue for Option<T> {
+    fn from_script_value(value: &ScriptValue) -> Result<Self, ScriptError> {
+        match value {
+            ScriptValue::Null => Ok(None),
+            value => T::from_script_value(value).map(Some),
+        }
+    }
+}
+
+impl<T: FromScriptValue> FromScriptValue for Vec<T> {
+    fn from_script_value(value: &ScriptValue) -> Result<Self, ScriptError> {
+        value
+            .as_array()
+            .ok_or_else(|| cast_error::<Self>(value))?
+            .iter()
+            .map(T::from_script_value)
+            .collect()
+    }
+}
+
+impl<T: FromScriptValue, S: BuildHasher + Default> FromScriptValue for HashMap<String, T, S> {
+    fn from_script_value(value: &ScriptValue) -> Result<Self, ScriptError> {
+        value
+            .as_object()
+            .ok_or_else(|| cast_error::<Self>(value))?
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), T::from_script_value(value)?)))
+            .collect()
+    }
+}
+
+impl<T: FromScriptValue> FromScriptValue for BTreeMap<String, T> {
+    fn from_script_value(value: &ScriptValue) -> Result<Self, ScriptError> {
+        value
+            .as_object()
+            .ok_or_else(|| cast_error::<Self>(value))?
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), T::from_script_value(value)?)))
+            .collect()
+    }
+}
+
+/// Tuples are arrays, missing elements read as `ScriptValue::Null`: `Option`s can be left out
+macro_rules! tuples {
+    ($(($($element:ident $index:tt),+))*) => {
+        $(
+            impl<$($element: Into<ScriptValue>),+> From<($($element,)+)> for ScriptValue {
+                fn from(tuple: ($($element,)+)) -> Self {
+                    ScriptValue::Array(vec![$(tuple.$index.into()),+])
+                }
+            }
+
+            impl<$($element: FromScriptValue),+> FromScriptValue for ($($element,)+) {
+                fn from_script_value(value: &ScriptValue) -> Result<Self, ScriptError> {
+                    let values = value.as_array().ok_or_else(|| cast_error::<Self>(value))?;
+                    let len = [$($index),+].len();
+                    if values.len() > len {
+                        return Err(cast_error::<Self>(value));
+                    }
+                    Ok(($($element::from_script_value(values.get($index).unwrap_or(&NULL))?,)+))
+                }
+            }
+        )*
+    };
+}
+
+tuples! {
+    (A 0)
+    (A 0, B 1)
+    (A 0, B 1, C 2)
+    (A 0, B 1, C 2, D 3)
+    (A 0, B 1, C 2, D 3, E 4)
+    (A 0, B 1, C 2, D 3, E 4, F 5)
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6)
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7)
+}
+
+/// Converts the arguments of a function registered with `register_func`, as a tuple for instance
+pub fn from_args<T: FromScriptValue>(args: &[ScriptValue]) -> Result<T, ScriptError> {
+    T::from_script_value(&ScriptValue::Array(args.to_vec()))
+}
+
+/// Converts a serde type into a `ScriptValue`
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<ScriptValue, ScriptError> {
+    serde_json::to_value(value)
+        .map(ScriptValue::from)
+        .map_err(|e| ScriptError::SerializationError(e.to_string()))
+}
+
+/// Converts a `ScriptValue` into a serde type, failures tell which field didn't match
+pub fn from_value<T: DeserializeOwned>(value: &ScriptValue) -> Result<T, ScriptError> {
+    serde_json::from_value(serde_json::Value::from(value.clone())).map_err(|e| {
+        ScriptError::SerializationError(format!("Converting into `{}`: {}", type_name::<T>(), e))
+    })
+}
+
+/// Wraps serde types to convert them with `FromScriptValue` and `IntoScriptValue`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Serde<T>(pub T);
+
+impl<T: DeserializeOwned> FromScriptValue for Serde<T> {
+    fn from_script_value(value: &ScriptValue) -> Result<Self, ScriptError> {
+        from_value(value).map(Serde)
+    }
+}
+
+impl<T: Serialize> IntoScriptValue for Serde<T> {
+    fn into_script_value(self) -> Result<ScriptValue, ScriptError> {
+        to_value(&self.0)
+    }
+}
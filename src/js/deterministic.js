@@ -0,0 +1,297 @@
+// @ts-check
+
+// Deterministic mode: seeded `Math.random`, virtual UTC `Date` and fixed locale
+(() => {
+    /** @type {{ state: number[], locale: string }} */
+    const config = JSON.parse(ScriptIt.core.callToRust("deterministic$config", ""));
+    const { locale } = config;
+    const HostDate = Date;
+    // Engines built without internationalization support (QuickJS) have no `Intl`
+    const HostIntl = typeof Intl === "object" ? Intl : null;
+    const hostMath = Math;
+    // On wasm, the prototypes of builtins are shared with the host engine
+    const sharedWithHost = typeof ScriptIt.core.shadowGlobal === "function";
+
+    /**
+     * Replaces a global, including the ones passed through from the host engine
+     * @param {string} name
+     * @param {any} value
+     */
+    const setGlobal = (name, value) => {
+        if (sharedWithHost) {
+            ScriptIt.core.shadowGlobal(name);
+        }
+        Object.defineProperty(globalThis, name, {
+            value,
+            writable: true,
+            configurable: true,
+            enumerable: false,
+        });
+    };
+
+    // xoshiro128**, seeded from Rust
+    let [a, b, c, d] = config.state;
+    /**
+     * @param {number} x
+     * @param {number} k
+     */
+    const rotl = (x, k) => (x << k) | (x >>> (32 - k));
+    const random = () => {
+        const result = hostMath.imul(rotl(hostMath.imul(b, 5), 7), 9) >>> 0;
+        const t = b << 9;
+        c ^= a;
+        d ^= b;
+        b ^= c;
+        a ^= d;
+        c ^= t;
+        d = rotl(d, 11);
+        return result / 4294967296;
+    };
+
+    const math = Object.create(Object.getPrototypeOf(hostMath));
+    for (const key of Reflect.ownKeys(hostMath)) {
+        const descriptor = Object.getOwnPropertyDescriptor(hostMath, key);
+        Object.defineProperty(math, key, /** @type {PropertyDescriptor} */ (descriptor));
+    }
+    Object.defineProperty(math, "random", { value: random, writable: true, configurable: true });
+
+    const now = () => Number(ScriptIt.core.callToRust("deterministic$now", ""));
+
+    /**
+     * Makes date strings without a timezone use UTC instead of the host timezone
+     * @param {string} str
+     * @returns {string}
+     */
+    const asUtc = (str) => {
+        const hasTimezone = /(z|gmt|utc|[+-]\d{2}:?\d{2})\s*(\(.*\))?$/i.test(str);
+        // Date-only ISO strings already are UTC
+        if (hasTimezone || /^\d{4}(-\d{2}){0,2}$/.test(str)) {
+            return str;
+        }
+        if (/^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}(:\d{2}(\.\d+)?)?$/.test(str)) {
+            return `${str}Z`;
+        }
+        return `${str} GMT`;
+    };
+
+    const days = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+    const months = [
+        "Jan",
+        "Feb",
+        "Mar",
+        "Apr",
+        "May",
+        "Jun",
+        "Jul",
+        "Aug",
+        "Sep",
+        "Oct",
+        "Nov",
+        "Dec",
+    ];
+    /**
+     * @param {number} n
+     * @param {number} length
+     */
+    const pad = (n, length = 2) => String(n).padStart(length, "0");
+
+    class DeterministicDate extends HostDate {
+        /**
+         * @param {any[]} args
+         */
+        constructor(...args) {
+            if (args.length === 0) {
+                super(now());
+            } else if (args.length === 1) {
+                const [value] = args;
+                super(typeof value === "string" ? HostDate.parse(asUtc(value)) : value);
+            } else {
+                // @ts-ignore
+                super(HostDate.UTC(...args));
+            }
+        }
+
+        static now() {
+            return now();
+        }
+
+        /**
+         * @param {string} str
+         */
+        static parse(str) {
+            return HostDate.parse(asUtc(String(str)));
+        }
+
+        getTimezoneOffset() {
+            return isNaN(this.getTime()) ? NaN : 0;
+        }
+
+        toDateString() {
+            if (isNaN(this.getTime())) {
+                return "Invalid Date";
+            }
+            const year = this.getUTCFullYear();
+            const weekDay = days[this.getUTCDay()];
+            const month = months[this.getUTCMonth()];
+            const paddedYear = year < 0 ? `-${pad(-year, 6)}` : pad(year, 4);
+            return `${weekDay} ${month} ${pad(this.getUTCDate())} ${paddedYear}`;
+        }
+
+        toTimeString() {
+            if (isNaN(this.getTime())) {
+                return "Invalid Date";
+            }
+            return `${pad(this.getUTCHours())}:${pad(this.getUTCMinutes())}:${pad(
+                this.getUTCSeconds()
+            )} GMT+0000 (Coordinated Universal Time)`;
+        }
+
+        toString() {
+            if (isNaN(this.getTime())) {
+                return "Invalid Date";
+            }
+            return `${this.toDateString()} ${this.toTimeString()}`;
+        }
+
+        /**
+         * @param {string | string[]} locales
+         * @param {Intl.DateTimeFormatOptions} options
+         */
+        toLocaleString(locales = undefined, options = undefined) {
+            return HostDate.prototype.toLocaleString.call(this, locales || locale, {
+                timeZone: "UTC",
+                ...options,
+            });
+        }
+
+        /**
+         * @param {string | string[]} locales
+         * @param {Intl.DateTimeFormatOptions} options
+         */
+        toLocaleDateString(locales = undefined, options = undefined) {
+            return HostDate.prototype.toLocaleDateString.call(this, locales || locale, {
+                timeZone: "UTC",
+                ...options,
+            });
+        }
+
+        /**
+         * @param {string | string[]} locales
+         * @param {Intl.DateTimeFormatOptions} options
+         */
+        toLocaleTimeString(locales = undefined, options = undefined) {
+            return HostDate.prototype.toLocaleTimeString.call(this, locales || locale, {
+                timeZone: "UTC",
+                ...options,
+            });
+        }
+    }
+
+    // Local time is UTC
+    const parts = [
+        "FullYear",
+        "Month",
+        "Date",
+        "Day",
+        "Hours",
+        "Minutes",
+        "Seconds",
+        "Milliseconds",
+    ];
+    for (const part of parts) {
+        Object.defineProperty(DeterministicDate.prototype, `get${part}`, {
+            value: HostDate.prototype[`getUTC${part}`],
+            writable: true,
+            configurable: true,
+        });
+        if (part !== "Day") {
+            Object.defineProperty(DeterministicDate.prototype, `set${part}`, {
+                value: HostDate.prototype[`setUTC${part}`],
+                writable: true,
+                configurable: true,
+            });
+        }
+    }
+
+    // `Date()` called without `new` gives the current date as a string
+    const date = new Proxy(DeterministicDate, {
+        apply: () => new DeterministicDate().toString(),
+    });
+
+    if (HostIntl) {
+        const intl = Object.create(Object.getPrototypeOf(HostIntl));
+        for (const key of Reflect.ownKeys(HostIntl)) {
+            const descriptor = Object.getOwnPropertyDescriptor(HostIntl, key);
+            Object.defineProperty(intl, key, /** @type {PropertyDescriptor} */ (descriptor));
+        }
+        for (const name of [
+            "Collator",
+            "DateTimeFormat",
+            "DisplayNames",
+            "ListFormat",
+            "NumberFormat",
+            "PluralRules",
+            "RelativeTimeFormat",
+            "Segmenter",
+        ]) {
+            const HostFormat = HostIntl[name];
+            if (typeof HostFormat !== "function") {
+                continue;
+            }
+            const Format = class extends HostFormat {
+                /**
+                 * @param {string | string[]} locales
+                 * @param {any} options
+                 */
+                constructor(locales = undefined, options = undefined) {
+                    const isDateFormat = name === "DateTimeFormat";
+                    super(
+                        locales || locale,
+                        isDateFormat ? { timeZone: "UTC", ...options } : options
+                    );
+                }
+            };
+            Object.defineProperty(Format, "name", { value: name });
+            Object.defineProperty(intl, name, {
+                value: Format,
+                writable: true,
+                configurable: true,
+            });
+        }
+        setGlobal("Intl", intl);
+    }
+
+    if (!sharedWithHost) {
+        // Locale-dependent methods of other builtins
+        const hostLocaleCompare = String.prototype.localeCompare;
+        String.prototype.localeCompare = function (that, locales = undefined, options = undefined) {
+            return hostLocaleCompare.call(this, that, locales || locale, options);
+        };
+        const hostNumberToLocaleString = Number.prototype.toLocaleString;
+        Number.prototype.toLocaleString = function (locales = undefined, options = undefined) {
+            return hostNumberToLocaleString.call(this, locales || locale, options);
+        };
+        const hostBigIntToLocaleString = BigInt.prototype.toLocaleString;
+        BigInt.prototype.toLocaleString = function (locales = undefined, options = undefined) {
+            return hostBigIntToLocaleString.call(this, locales || locale, options);
+        };
+    }
+
+    setGlobal("Math", math);
+    setGlobal("Date", date);
+    // Their results depend on thread scheduling, the garbage collector or entropy
+    const removed = [
+        "Worker",
+        "WeakRef",
+        "FinalizationRegistry",
+        "SharedArrayBuffer",
+        "Atomics",
+        "crypto",
+    ];
+    for (const name of removed) {
+        if (sharedWithHost) {
+            ScriptIt.core.shadowGlobal(name);
+        }
+        delete globalThis[name];
+    }
+})();
@@ -17,16 +17,411 @@ function callToRust(handler, data) {
  * @param {string} handler Name of the `callToRust` handler
  */
 function registerFunc(funcName, handler) {
-    ScriptIt.funcs[funcName] = (...args) => {
-        const data = JSON.stringify(args);
-        const res = ScriptIt.core.callToRust(handler, data);
-        return JSON.parse(res);
+    // Computed key so the function gets `funcName` as its name in stacks and profiles
+    const { [funcName]: func } = {
+        [funcName]: (...args) => {
+            const data = JSON.stringify(args);
+            const res = ScriptIt.core.callToRust(handler, data);
+            return JSON.parse(res);
+        },
     };
+    ScriptIt.funcs[funcName] = func;
+}
+
+/**
+ * Serializes a value to a JSON string following the structured clone algorithm
+ * @param {any} value Value to serialize
+ * @returns {string} Serialized value, to pass to `deserialize`
+ */
+function serialize(value) {
+    /** @type {Map<object, number>} */
+    const seen = new Map();
+    /** @param {Uint8Array} bytes */
+    const bytesOf = (bytes) => Array.from(bytes);
+    /**
+     * @param {any} v
+     * @returns {any}
+     */
+    const encode = (v) => {
+        switch (typeof v) {
+            case "undefined":
+                return { $t: "undefined" };
+            case "boolean":
+            case "string":
+                return v;
+            case "number":
+                return Number.isFinite(v) && !Object.is(v, -0)
+                    ? v
+                    : { $t: "number", v: String(v) };
+            case "bigint":
+                return { $t: "bigint", v: v.toString() };
+            case "function":
+            case "symbol":
+                throw new TypeError(`${String(v)} could not be cloned`);
+        }
+        if (v === null) {
+            return null;
+        }
+        if (seen.has(v)) {
+            return { $t: "ref", id: seen.get(v) };
+        }
+        const id = seen.size;
+        seen.set(v, id);
+        if (Array.isArray(v)) {
+            return { $t: "array", id, v: v.map(encode) };
+        }
+        if (v instanceof Date) {
+            return { $t: "date", id, v: v.getTime() };
+        }
+        if (v instanceof RegExp) {
+            return { $t: "regexp", id, v: v.source, flags: v.flags };
+        }
+        if (v instanceof Map) {
+            return {
+                $t: "map",
+                id,
+                v: [...v].map(([key, val]) => [encode(key), encode(val)]),
+            };
+        }
+        if (v instanceof Set) {
+            return { $t: "set", id, v: [...v].map(encode) };
+        }
+        if (v instanceof ArrayBuffer) {
+            return { $t: "ArrayBuffer", id, v: bytesOf(new Uint8Array(v)) };
+        }
+        if (ArrayBuffer.isView(v)) {
+            const bytes = new Uint8Array(v.buffer, v.byteOffset, v.byteLength);
+            return { $t: v.constructor.name, id, v: bytesOf(bytes) };
+        }
+        if (v instanceof Error) {
+            return { $t: "error", id, name: v.name, v: v.message, stack: v.stack };
+        }
+        /** @type {Record<string, any>} */
+        const entries = {};
+        for (const key of Object.keys(v)) {
+            entries[key] = encode(v[key]);
+        }
+        return { $t: "object", id, v: entries };
+    };
+    return JSON.stringify(encode(value));
+}
+
+/**
+ * Deserializes a value serialized with `serialize`
+ * @param {string} data Serialized value
+ * @returns {any} A structured clone of the serialized value
+ */
+function deserialize(data) {
+    // Typed array constructors that can be cloned
+    const clonableViews = [
+        "Int8Array",
+        "Uint8Array",
+        "Uint8ClampedArray",
+        "Int16Array",
+        "Uint16Array",
+        "Int32Array",
+        "Uint32Array",
+        "Float32Array",
+        "Float64Array",
+        "BigInt64Array",
+        "BigUint64Array",
+        "DataView",
+    ];
+    // Error constructors that can be cloned, other names are deserialized as `Error`
+    const clonableErrors = [
+        "Error",
+        "TypeError",
+        "RangeError",
+        "SyntaxError",
+        "ReferenceError",
+        "EvalError",
+        "URIError",
+        "AggregateError",
+    ];
+    /** @type {Map<number, any>} */
+    const refs = new Map();
+    /**
+     * @param {any} v
+     * @returns {any}
+     */
+    const decode = (v) => {
+        if (v === null || typeof v !== "object") {
+            return v;
+        }
+        switch (v.$t) {
+            case "undefined":
+                return undefined;
+            case "number":
+                return Number(v.v);
+            case "bigint":
+                return BigInt(v.v);
+            case "ref":
+                return refs.get(v.id);
+            case "array": {
+                const arr = [];
+                refs.set(v.id, arr);
+                for (const item of v.v) {
+                    arr.push(decode(item));
+                }
+                return arr;
+            }
+            case "date":
+                return refs.set(v.id, new Date(v.v)).get(v.id);
+            case "regexp":
+                return refs.set(v.id, new RegExp(v.v, v.flags)).get(v.id);
+            case "map": {
+                const map = new Map();
+                refs.set(v.id, map);
+                for (const [key, val] of v.v) {
+                    map.set(decode(key), decode(val));
+                }
+                return map;
+            }
+            case "set": {
+                const set = new Set();
+                refs.set(v.id, set);
+                for (const item of v.v) {
+                    set.add(decode(item));
+                }
+                return set;
+            }
+            case "ArrayBuffer":
+                return refs.set(v.id, new Uint8Array(v.v).buffer).get(v.id);
+            case "error": {
+                const ErrorClass = (clonableErrors.includes(v.name) && globalThis[v.name]) || Error;
+                // `AggregateError` takes the aggregated errors first, they are not cloned
+                const err =
+                    ErrorClass.name === "AggregateError"
+                        ? new ErrorClass([], v.v)
+                        : new ErrorClass(v.v);
+                err.stack = v.stack;
+                return refs.set(v.id, err).get(v.id);
+            }
+            case "object": {
+                /** @type {Record<string, any>} */
+                const obj = {};
+                refs.set(v.id, obj);
+                for (const key of Object.keys(v.v)) {
+                    obj[key] = decode(v.v[key]);
+                }
+                return obj;
+            }
+        }
+        if (clonableViews.includes(v.$t)) {
+            const buffer = new Uint8Array(v.v).buffer;
+            const View = globalThis[v.$t];
+            const view =
+                v.$t === "DataView"
+                    ? new View(buffer)
+                    : new View(buffer, 0, buffer.byteLength / View.BYTES_PER_ELEMENT);
+            return refs.set(v.id, view).get(v.id);
+        }
+        throw new TypeError(`Can't deserialize a value tagged ${v.$t}`);
+    };
+    return decode(JSON.parse(data));
+}
+
+/**
+ * Views the bytes of a buffer or of a buffer view, without copying them
+ * @param {any} source
+ * @returns {Uint8Array}
+ */
+function toBytes(source) {
+    if (source instanceof ArrayBuffer) {
+        return new Uint8Array(source);
+    }
+    if (ArrayBuffer.isView(source)) {
+        return new Uint8Array(source.buffer, source.byteOffset, source.byteLength);
+    }
+    throw new TypeError("The provided value is not an ArrayBuffer or an ArrayBufferView");
+}
+
+/**
+ * Encodes a string to UTF-8, lone surrogates are replaced by U+FFFD
+ * @param {string} str
+ * @returns {Uint8Array}
+ */
+function utf8Encode(str) {
+    /** @type {number[]} */
+    const bytes = [];
+    for (const char of str) {
+        let code = /** @type {number} */ (char.codePointAt(0));
+        if (code >= 0xd800 && code <= 0xdfff) {
+            code = 0xfffd;
+        }
+        if (code < 0x80) {
+            bytes.push(code);
+        } else if (code < 0x800) {
+            bytes.push(0xc0 | (code >> 6), 0x80 | (code & 0x3f));
+        } else if (code < 0x10000) {
+            bytes.push(0xe0 | (code >> 12), 0x80 | ((code >> 6) & 0x3f), 0x80 | (code & 0x3f));
+        } else {
+            bytes.push(
+                0xf0 | (code >> 18),
+                0x80 | ((code >> 12) & 0x3f),
+                0x80 | ((code >> 6) & 0x3f),
+                0x80 | (code & 0x3f)
+            );
+        }
+    }
+    return new Uint8Array(bytes);
+}
+
+/**
+ * Decodes UTF-8, invalid sequences are replaced by U+FFFD unless `fatal` is set
+ * @param {Uint8Array} bytes
+ * @param {{ fatal?: boolean, ignoreBOM?: boolean }} options
+ * @returns {string}
+ */
+function utf8Decode(bytes, options = {}) {
+    let out = "";
+    let i = 0;
+    if (!options.ignoreBOM && bytes[0] === 0xef && bytes[1] === 0xbb && bytes[2] === 0xbf) {
+        i = 3;
+    }
+    const invalid = () => {
+        if (options.fatal) {
+            throw new TypeError("The encoded data was not valid utf-8");
+        }
+        return "\ufffd";
+    };
+    while (i < bytes.length) {
+        const byte = bytes[i];
+        let needed = 0;
+        let code = 0;
+        let lower = 0x80;
+        let upper = 0xbf;
+        if (byte < 0x80) {
+            out += String.fromCharCode(byte);
+            i++;
+            continue;
+        } else if (byte >= 0xc2 && byte <= 0xdf) {
+            needed = 1;
+            code = byte & 0x1f;
+        } else if (byte >= 0xe0 && byte <= 0xef) {
+            needed = 2;
+            code = byte & 0x0f;
+            lower = byte === 0xe0 ? 0xa0 : 0x80;
+            upper = byte === 0xed ? 0x9f : 0xbf;
+        } else if (byte >= 0xf0 && byte <= 0xf4) {
+            needed = 3;
+            code = byte & 0x07;
+            lower = byte === 0xf0 ? 0x90 : 0x80;
+            upper = byte === 0xf4 ? 0x8f : 0xbf;
+        } else {
+            out += invalid();
+            i++;
+            continue;
+        }
+        let j = 1;
+        for (; j <= needed; j++) {
+            const next = bytes[i + j];
+            if (next === undefined || next < lower || next > upper) {
+                break;
+            }
+            code = (code << 6) | (next & 0x3f);
+            lower = 0x80;
+            upper = 0xbf;
+        }
+        if (j <= needed) {
+            out += invalid();
+            i += j;
+            continue;
+        }
+        out += String.fromCodePoint(code);
+        i += needed + 1;
+    }
+    return out;
 }
 
 ScriptIt.core = {
     callToRust,
     registerFunc,
+    serialize,
+    deserialize,
+    toBytes,
+    utf8Encode,
+    utf8Decode,
 };
 
 ScriptIt.funcs = {};
+
+// Timers, fired by the event loop of the environment
+(() => {
+    /**
+     * Timers set from scripts, by id
+     * @type {Map<number, { callback: Function, args: any[], delay: number, repeat: boolean, timerId: string }>}
+     */
+    const timers = new Map();
+    let nextTimerId = 1;
+
+    /**
+     * Queues a timer on the event loop of the environment
+     * @param {number} id Id of the timer
+     * @param {number} delay Delay in milliseconds
+     * @returns {string} Id of the timer on the event loop
+     */
+    function queueTimer(id, delay) {
+        return ScriptIt.core.callToRust("timers$set", JSON.stringify({ id, delay }));
+    }
+
+    /**
+     * Sets a timer
+     * @param {Function} callback Function to call when the timer fires
+     * @param {any} delay Delay in milliseconds
+     * @param {any[]} args Arguments passed to `callback`
+     * @param {boolean} repeat Whether the timer fires again after each `delay`
+     * @returns {number} Id of the timer
+     */
+    function setTimer(callback, delay, args, repeat) {
+        if (typeof callback !== "function") {
+            throw new TypeError("The timer callback must be a function");
+        }
+        const id = nextTimerId++;
+        delay = Math.min(Math.max(Number(delay) || 0, 0), 2147483647);
+        timers.set(id, { callback, args, delay, repeat, timerId: queueTimer(id, delay) });
+        return id;
+    }
+
+    /**
+     * Cancels a timer
+     * @param {any} id Id of the timer
+     */
+    function clearTimer(id) {
+        const timer = timers.get(id);
+        if (timer) {
+            timers.delete(id);
+            ScriptIt.core.callToRust("timers$clear", timer.timerId);
+        }
+    }
+
+    /**
+     * Calls the callback of a timer, called by the event loop
+     * @param {number} id Id of the timer
+     */
+    function fireTimer(id) {
+        const timer = timers.get(id);
+        if (!timer) {
+            return;
+        }
+        if (timer.repeat) {
+            timer.timerId = queueTimer(id, timer.delay);
+        } else {
+            timers.delete(id);
+        }
+        timer.callback(...timer.args);
+    }
+
+    globalThis.setTimeout = (callback, delay, ...args) => setTimer(callback, delay, args, false);
+    globalThis.setInterval = (callback, delay, ...args) => setTimer(callback, delay, args, true);
+    globalThis.clearTimeout = clearTimer;
+    globalThis.clearInterval = clearTimer;
+    globalThis.queueMicrotask = (callback) => {
+        if (typeof callback !== "function") {
+            throw new TypeError("The microtask callback must be a function");
+        }
+        Promise.resolve().then(() => callback());
+    };
+
+    ScriptIt.core.fireTimer = fireTimer;
+})();
@@ -0,0 +1,267 @@
+use crate::core::{
+    compiled::CompiledScript,
+    error::ScriptError,
+    event_loop::{self, EventLoop},
+    timers,
+    value::ScriptValue,
+    ScriptingEnvironment,
+};
+use boa_engine::{
+    js_string, object::FunctionObjectBuilder, Context, JsError, JsNativeError, JsResult, JsValue,
+    NativeFunction, Script, Source,
+};
+use std::{cell::RefCell, collections::HashMap, path::Path, rc::Rc};
+
+type Handlers = Rc<RefCell<HashMap<String, Box<dyn FnMut(&str) -> Result<String, String>>>>>;
+
+fn jserror_to_scripterror(
+    context: &mut Context,
+    error: JsError,
+    script_name: &str,
+    is_compile_step: bool,
+) -> ScriptError {
+    // Thrown `Error` objects are described as `Name: message`, other thrown values as is
+    let description = match error.try_native(context) {
+        Ok(native) => native.to_string(),
+        Err(_) => format!("Uncaught {}", error),
+    };
+    // Boa doesn't report the script location: prefix the description with the script name
+    let description = format!("{}: {}", script_name, description);
+    if is_compile_step {
+        ScriptError::CompileError(description)
+    } else {
+        ScriptError::RuntimeError(description)
+    }
+}
+
+fn compile_script(
+    context: &mut Context,
+    source: &str,
+    script_name: &str,
+) -> Result<Script, ScriptError> {
+    let source = Source::from_reader(source.as_bytes(), Some(Path::new(script_name)));
+    let script = Script::parse(source, None, context)
+        .map_err(|e| jserror_to_scripterror(context, e, script_name, true))?;
+    // Early errors (redeclarations...) are reported when generating the bytecode
+    script
+        .codeblock(context)
+        .map_err(|e| jserror_to_scripterror(context, e, script_name, true))?;
+    Ok(script)
+}
+
+fn run_script(
+    context: &mut Context,
+    script: &Script,
+    script_name: &str,
+    is_expression: bool,
+) -> Result<ScriptValue, ScriptError> {
+    let result = script.evaluate(context);
+    // Promise jobs run right after the script, like on V8
+    context.run_jobs();
+    match result {
+        Ok(value) if is_expression => val_to_scriptvalue(context, &value),
+        Ok(_) => Ok(ScriptValue::Null),
+        Err(e) => Err(jserror_to_scripterror(context, e, script_name, false)),
+    }
+}
+
+/// Expressions are run wrapped so that they evaluate to JSON
+const EXPRESSION_PREFIX: &str = "JSON.stringify(";
+
+fn expression_source(source: &str) -> String {
+    format!("{}{})", EXPRESSION_PREFIX, source)
+}
+
+fn val_to_scriptvalue(context: &mut Context, value: &JsValue) -> Result<ScriptValue, ScriptError> {
+    let json_str = value
+        .to_string(context)
+        .map_err(|_| ScriptError::CastError {
+            type_from: "boa_engine::JsValue",
+            type_to: "boa_engine::JsString",
+        })?
+        .to_std_string_escaped();
+    serde_json::from_str(&json_str).map_err(|e| ScriptError::SerializationError(e.to_string()))
+}
+
+fn call_handler(handlers: &Handlers, handler_name: &str, data: &str) -> Result<String, String> {
+    let mut handlers = handlers.borrow_mut();
+    let handler_closure = handlers
+        .get_mut(handler_name)
+        .ok_or(format!("Can't get unregistered handler: {}", handler_name))?;
+    handler_closure(data)
+}
+
+fn core_call_to_rust_receiver(
+    handlers: &Handlers,
+    args: &[JsValue],
+    context: &mut Context,
+) -> JsResult<JsValue> {
+    let mut string_arg = |index: usize| -> JsResult<String> {
+        Ok(args
+            .get(index)
+            .cloned()
+            .unwrap_or_default()
+            .to_string(context)?
+            .to_std_string_escaped())
+    };
+    let handler_name = string_arg(0)?;
+    let handler_data = string_arg(1)?;
+    let handler_result = call_handler(handlers, &handler_name, &handler_data)
+        .map_err(|err_str| JsNativeError::error().with_message(err_str))?;
+    Ok(js_string!(handler_result).into())
+}
+
+/// A Boa scripting environment, written in Rust: it runs wherever Rust does (`wasm32-wasi`...)
+pub struct BoaScriptingEnvironment {
+    compiled_scripts: HashMap<String, Script>,
+    context: Context,
+    handlers: Handlers,
+    event_loop: Rc<RefCell<EventLoop>>,
+}
+
+impl BoaScriptingEnvironment {
+    pub fn new() -> BoaScriptingEnvironment {
+        let mut context = Context::default();
+        let handlers: Handlers = Rc::new(RefCell::new(HashMap::new()));
+
+        // Run the bootstrap scripts
+        let bs_src = format!(
+            "{}\n{}",
+            include_str!("../js_v8/v8_bootstrap.js"),
+            include_str!("../js/shared_bootstrap.js")
+        );
+        let bs_script = compile_script(&mut context, &bs_src, "scriptit:bootstrap.js").unwrap();
+        run_script(&mut context, &bs_script, "scriptit:bootstrap.js", false).unwrap();
+
+        // Go set ScriptIt.core.callToRust to core_call_to_rust_receiver
+        let closure_handlers = Rc::clone(&handlers);
+        // SAFETY: the closure only captures the handlers, they hold no garbage-collected value
+        let call_to_rust_fn = unsafe {
+            NativeFunction::from_closure(move |_, args, context| {
+                core_call_to_rust_receiver(&closure_handlers, args, context)
+            })
+        };
+        let call_to_rust_fn = FunctionObjectBuilder::new(context.realm(), call_to_rust_fn)
+            .name("callToRust")
+            .length(2)
+            .build();
+        let core = context
+            .global_object()
+            .get(js_string!("ScriptIt"), &mut context)
+            .unwrap()
+            .as_object()
+            .unwrap()
+            .get(js_string!("core"), &mut context)
+            .unwrap();
+        core.as_object()
+            .unwrap()
+            .set(
+                js_string!("callToRust"),
+                call_to_rust_fn,
+                false,
+                &mut context,
+            )
+            .unwrap();
+
+        let mut s_env = BoaScriptingEnvironment {
+            compiled_scripts: HashMap::new(),
+            context,
+            handlers,
+            event_loop: Rc::new(RefCell::new(EventLoop::new())),
+        };
+        timers::install(&mut s_env);
+        s_env
+    }
+
+    fn internal_compile(
+        &mut self,
+        source: &str,
+        script_name: &str,
+        is_expression: bool,
+    ) -> Result<CompiledScript, ScriptError> {
+        let script = compile_script(&mut self.context, source, script_name)?;
+        let compiled = CompiledScript::new(script_name, is_expression);
+        self.compiled_scripts
+            .insert(compiled.id().to_string(), script);
+        Ok(compiled)
+    }
+}
+
+impl Default for BoaScriptingEnvironment {
+    fn default() -> Self {
+        BoaScriptingEnvironment::new()
+    }
+}
+
+impl ScriptingEnvironment for BoaScriptingEnvironment {
+    fn eval_expression_named(
+        &mut self,
+        source: &str,
+        script_name: &str,
+    ) -> Result<ScriptValue, ScriptError> {
+        let script = compile_script(&mut self.context, &expression_source(source), script_name)?;
+        run_script(&mut self.context, &script, script_name, true)
+    }
+
+    fn run_named(&mut self, source: &str, script_name: &str) -> Result<(), ScriptError> {
+        let script = compile_script(&mut self.context, source, script_name)?;
+        run_script(&mut self.context, &script, script_name, false)?;
+        Ok(())
+    }
+
+    fn compile_named(
+        &mut self,
+        source: &str,
+        script_name: &str,
+    ) -> Result<CompiledScript, ScriptError> {
+        self.internal_compile(source, script_name, false)
+    }
+
+    fn compile_expression_named(
+        &mut self,
+        source: &str,
+        script_name: &str,
+    ) -> Result<CompiledScript, ScriptError> {
+        self.internal_compile(&expression_source(source), script_name, true)
+    }
+
+    fn run_compiled(&mut self, script: &CompiledScript) -> Result<ScriptValue, ScriptError> {
+        let compiled_script = self.compiled_scripts.get(script.id()).ok_or_else(|| {
+            ScriptError::RuntimeError(format!("Unknown compiled script: {}", script.id()))
+        })?;
+        run_script(
+            &mut self.context,
+            compiled_script,
+            script.script_name(),
+            script.is_expression(),
+        )
+    }
+
+    fn release_compiled(&mut self, script: CompiledScript) {
+        self.compiled_scripts.remove(script.id());
+    }
+
+    fn expression_column_offset(&self) -> u32 {
+        EXPRESSION_PREFIX.len() as u32
+    }
+
+    fn event_loop(&self) -> Rc<RefCell<EventLoop>> {
+        Rc::clone(&self.event_loop)
+    }
+
+    fn run_event_loop(&mut self) -> Result<(), ScriptError> {
+        event_loop::run(self)
+    }
+
+    fn register_core_handler(
+        &mut self,
+        handler_name: &str,
+        handler_closure: Box<dyn FnMut(&str) -> Result<String, String>>,
+    ) {
+        self.handlers
+            .borrow_mut()
+            .insert(handler_name.to_string(), handler_closure);
+    }
+}
+
+pub type PlatformScriptingEnvironment = BoaScriptingEnvironment;
@@ -0,0 +1,309 @@
+use crate::core::{
+    compiled::CompiledScript,
+    error::ScriptError,
+    event_loop::{self, EventLoop},
+    timers,
+    value::ScriptValue,
+    ScriptingEnvironment,
+};
+use rquickjs::{
+    convert::Coerced, qjs, CaughtError, Context, Ctx, Exception, Function, Object, Persistent,
+    Runtime, Value,
+};
+use std::{cell::RefCell, collections::HashMap, ffi::CString, rc::Rc};
+
+type Handlers = Rc<RefCell<HashMap<String, Box<dyn FnMut(&str) -> Result<String, String>>>>>;
+
+fn exception_to_scripterror(ctx: &Ctx, is_compile_step: bool) -> ScriptError {
+    let description = match CaughtError::from_error(ctx, rquickjs::Error::Exception) {
+        CaughtError::Exception(exception) => {
+            let name = exception
+                .get::<_, Option<Coerced<String>>>("name")
+                .ok()
+                .flatten()
+                .map_or_else(|| String::from("Error"), |name| name.0);
+            let mut description = format!("{}: {}", name, exception.message().unwrap_or_default());
+            // The stack also holds the script location: `name:line:column`
+            if let Some(stack) = exception.stack().filter(|stack| !stack.is_empty()) {
+                description = format!("{}\n{}", description, stack.trim_end());
+            }
+            description
+        }
+        CaughtError::Value(value) => format!(
+            "Uncaught {}",
+            value
+                .get::<Coerced<String>>()
+                .map(|value| value.0)
+                .unwrap_or_default()
+        ),
+        CaughtError::Error(error) => error.to_string(),
+    };
+    if is_compile_step {
+        ScriptError::CompileError(description)
+    } else {
+        ScriptError::RuntimeError(description)
+    }
+}
+
+fn compile_script<'js>(
+    ctx: &Ctx<'js>,
+    source: &str,
+    script_name: &str,
+) -> Result<Value<'js>, ScriptError> {
+    let cast_error = |_| ScriptError::CastError {
+        type_from: "&str",
+        type_to: "CString",
+    };
+    let source = CString::new(source).map_err(cast_error)?;
+    let script_name = CString::new(script_name).map_err(cast_error)?;
+    // `Ctx::eval` can't name scripts: QuickJS is called directly
+    unsafe {
+        let compiled = qjs::JS_Eval(
+            ctx.as_raw().as_ptr(),
+            source.as_ptr(),
+            source.as_bytes().len() as _,
+            script_name.as_ptr(),
+            (qjs::JS_EVAL_TYPE_GLOBAL | qjs::JS_EVAL_FLAG_COMPILE_ONLY) as _,
+        );
+        if qjs::JS_IsException(compiled) {
+            return Err(exception_to_scripterror(ctx, true));
+        }
+        Ok(Value::from_raw(ctx.clone(), compiled))
+    }
+}
+
+fn run_script<'js>(
+    ctx: &Ctx<'js>,
+    compiled: &Value<'js>,
+    is_expression: bool,
+) -> Result<ScriptValue, ScriptError> {
+    let value = unsafe {
+        // `JS_EvalFunction` takes ownership of the compiled script, it can be run again later on
+        let compiled = qjs::JS_DupValue(ctx.as_raw().as_ptr(), compiled.as_raw());
+        let value = qjs::JS_EvalFunction(ctx.as_raw().as_ptr(), compiled);
+        if qjs::JS_IsException(value) {
+            return Err(exception_to_scripterror(ctx, false));
+        }
+        Value::from_raw(ctx.clone(), value)
+    };
+    if is_expression {
+        val_to_scriptvalue(&value)
+    } else {
+        Ok(ScriptValue::Null)
+    }
+}
+
+/// Expressions are run wrapped so that they evaluate to JSON
+const EXPRESSION_PREFIX: &str = "JSON.stringify(";
+
+fn expression_source(source: &str) -> String {
+    format!("{}{})", EXPRESSION_PREFIX, source)
+}
+
+fn val_to_scriptvalue(value: &Value) -> Result<ScriptValue, ScriptError> {
+    let json_str = value
+        .get::<Coerced<String>>()
+        .map_err(|_| ScriptError::CastError {
+            type_from: "rquickjs::Value",
+            type_to: "String",
+        })?
+        .0;
+    serde_json::from_str(&json_str).map_err(|e| ScriptError::SerializationError(e.to_string()))
+}
+
+fn call_handler(handlers: &Handlers, handler_name: &str, data: &str) -> Result<String, String> {
+    let mut handlers = handlers.borrow_mut();
+    let handler_closure = handlers
+        .get_mut(handler_name)
+        .ok_or(format!("Can't get unregistered handler: {}", handler_name))?;
+    handler_closure(data)
+}
+
+fn core_call_to_rust_function<'js>(
+    ctx: &Ctx<'js>,
+    handlers: Handlers,
+) -> rquickjs::Result<Function<'js>> {
+    Function::new(
+        ctx.clone(),
+        move |ctx: Ctx<'js>, handler_name: Coerced<String>, data: Coerced<String>| {
+            call_handler(&handlers, &handler_name.0, &data.0)
+                .map_err(|err_str| Exception::throw_message(&ctx, &err_str))
+        },
+    )
+}
+
+/// A QuickJS scripting environment, a lighter alternative to V8 on native targets
+pub struct QuickJsScriptingEnvironment {
+    // Compiled scripts are values of the runtime: they have to be dropped before it
+    compiled_scripts: HashMap<String, Persistent<Value<'static>>>,
+    context: Context,
+    runtime: Runtime,
+    handlers: Handlers,
+    event_loop: Rc<RefCell<EventLoop>>,
+}
+
+impl QuickJsScriptingEnvironment {
+    pub fn new() -> QuickJsScriptingEnvironment {
+        let runtime = Runtime::new().unwrap();
+        let context = Context::full(&runtime).unwrap();
+        let handlers: Handlers = Rc::new(RefCell::new(HashMap::new()));
+        context.with(|ctx| {
+            // Run the bootstrap scripts
+            let bs_src = format!(
+                "{}\n{}",
+                include_str!("../js_v8/v8_bootstrap.js"),
+                include_str!("../js/shared_bootstrap.js")
+            );
+            let bs_script = compile_script(&ctx, &bs_src, "scriptit:bootstrap.js").unwrap();
+            run_script(&ctx, &bs_script, false).unwrap();
+
+            // Go set ScriptIt.core.callToRust to the handler dispatcher
+            let call_to_rust_fn = core_call_to_rust_function(&ctx, Rc::clone(&handlers)).unwrap();
+            ctx.globals()
+                .get::<_, Object>("ScriptIt")
+                .unwrap()
+                .get::<_, Object>("core")
+                .unwrap()
+                .set("callToRust", call_to_rust_fn)
+                .unwrap();
+        });
+
+        let mut s_env = QuickJsScriptingEnvironment {
+            compiled_scripts: HashMap::new(),
+            context,
+            runtime,
+            handlers,
+            event_loop: Rc::new(RefCell::new(EventLoop::new())),
+        };
+        timers::install(&mut s_env);
+        s_env
+    }
+
+    /// Limits the memory of the runtime: allocations past `limit` bytes throw an out of memory error
+    pub fn set_memory_limit(&mut self, limit: usize) {
+        self.runtime.set_memory_limit(limit);
+    }
+
+    /// Runs the promise jobs queued by scripts, V8 does it after each script on its own
+    fn run_microtasks(&self) {
+        loop {
+            match self.runtime.execute_pending_job() {
+                Ok(true) => {}
+                Ok(false) => break,
+                // Exceptions thrown in jobs reject their promises: they are not reported here
+                Err(_) => {}
+            }
+        }
+    }
+
+    fn internal_run(
+        &mut self,
+        source: &str,
+        script_name: &str,
+        is_expression: bool,
+    ) -> Result<ScriptValue, ScriptError> {
+        let result = self.context.with(|ctx| {
+            let compiled = compile_script(&ctx, source, script_name)?;
+            run_script(&ctx, &compiled, is_expression)
+        });
+        self.run_microtasks();
+        result
+    }
+
+    fn internal_compile(
+        &mut self,
+        source: &str,
+        script_name: &str,
+        is_expression: bool,
+    ) -> Result<CompiledScript, ScriptError> {
+        let compiled_script = self.context.with(|ctx| {
+            let compiled = compile_script(&ctx, source, script_name)?;
+            Ok::<_, ScriptError>(Persistent::save(&ctx, compiled))
+        })?;
+        let compiled = CompiledScript::new(script_name, is_expression);
+        self.compiled_scripts
+            .insert(compiled.id().to_string(), compiled_script);
+        Ok(compiled)
+    }
+}
+
+impl Default for QuickJsScriptingEnvironment {
+    fn default() -> Self {
+        QuickJsScriptingEnvironment::new()
+    }
+}
+
+impl ScriptingEnvironment for QuickJsScriptingEnvironment {
+    fn eval_expression_named(
+        &mut self,
+        source: &str,
+        script_name: &str,
+    ) -> Result<ScriptValue, ScriptError> {
+        self.internal_run(&expression_source(source), script_name, true)
+    }
+
+    fn run_named(&mut self, source: &str, script_name: &str) -> Result<(), ScriptError> {
+        self.internal_run(source, script_name, false)?;
+        Ok(())
+    }
+
+    fn compile_named(
+        &mut self,
+        source: &str,
+        script_name: &str,
+    ) -> Result<CompiledScript, ScriptError> {
+        self.internal_compile(source, script_name, false)
+    }
+
+    fn compile_expression_named(
+        &mut self,
+        source: &str,
+        script_name: &str,
+    ) -> Result<CompiledScript, ScriptError> {
+        self.internal_compile(&expression_source(source), script_name, true)
+    }
+
+    fn run_compiled(&mut self, script: &CompiledScript) -> Result<ScriptValue, ScriptError> {
+        let compiled_script = self.compiled_scripts.get(script.id()).ok_or_else(|| {
+            ScriptError::RuntimeError(format!("Unknown compiled script: {}", script.id()))
+        })?;
+        let result = self.context.with(|ctx| {
+            let compiled = compiled_script
+                .clone()
+                .restore(&ctx)
+                .map_err(|e| ScriptError::RuntimeError(e.to_string()))?;
+            run_script(&ctx, &compiled, script.is_expression())
+        });
+        self.run_microtasks();
+        result
+    }
+
+    fn release_compiled(&mut self, script: CompiledScript) {
+        self.compiled_scripts.remove(script.id());
+    }
+
+    fn expression_column_offset(&self) -> u32 {
+        // QuickJS counts the columns of the first line from 0, and from 1 on the next lines
+        EXPRESSION_PREFIX.len() as u32 - 1
+    }
+
+    fn event_loop(&self) -> Rc<RefCell<EventLoop>> {
+        Rc::clone(&self.event_loop)
+    }
+
+    fn run_event_loop(&mut self) -> Result<(), ScriptError> {
+        event_loop::run(self)
+    }
+
+    fn register_core_handler(
+        &mut self,
+        handler_name: &str,
+        handler_closure: Box<dyn FnMut(&str) -> Result<String, String>>,
+    ) {
+        self.handlers
+            .borrow_mut()
+            .insert(handler_name.to_string(), handler_closure);
+    }
+}
+
+pub type PlatformScriptingEnvironment = QuickJsScriptingEnvironment;
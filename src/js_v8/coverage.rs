@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A range of source code and how many times it was executed
+///
+/// Offsets are expressed in UTF-16 code units, like in V8 coverage JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverageRange {
+    pub start_offset: usize,
+    pub end_offset: usize,
+    pub count: u64,
+}
+
+/// Coverage of a single function, the first range spans the whole function
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionCoverage {
+    pub function_name: String,
+    pub ranges: Vec<CoverageRange>,
+    pub is_block_coverage: bool,
+}
+
+/// Coverage of a script, identified by the name it was run with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScriptCoverage {
+    #[serde(rename = "scriptId")]
+    pub script_id: String,
+    #[serde(rename = "url")]
+    pub script_name: String,
+    pub functions: Vec<FunctionCoverage>,
+    /// Source of the script, used to map offsets to lines
+    #[serde(skip)]
+    pub source: Option<String>,
+}
+
+/// Source of a script as it was given to the environment
+pub(crate) struct CoverageSource {
+    pub(crate) source: String,
+    /// Length of the code wrapped in front of the source before running it, in UTF-16 code units
+    pub(crate) prefix_len: usize,
+}
+
+/// Coverage collected by `V8ScriptingEnvironment::take_coverage`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoverageReport {
+    pub scripts: Vec<ScriptCoverage>,
+}
+
+/// UTF-16 offsets at which each line starts, followed by the total length
+fn line_starts(source: &str) -> Vec<usize> {
+    let mut starts = vec![0];
+    let mut offset = 0;
+    for c in source.chars() {
+        offset += c.len_utf16();
+        if c == '\n' {
+            starts.push(offset);
+        }
+    }
+    starts.push(offset);
+    starts
+}
+
+impl ScriptCoverage {
+    /// Maps ranges of the code V8 ran back to `source`, dropping what only covers the wrapper
+    fn attach_source(&mut self, source: &CoverageSource) {
+        let source_len = source.source.encode_utf16().count();
+        let shift = |offset: usize| offset.saturating_sub(source.prefix_len).min(source_len);
+        for function in &mut self.functions {
+            for range in &mut function.ranges {
+                range.start_offset = shift(range.start_offset);
+                range.end_offset = shift(range.end_offset);
+            }
+            function
+                .ranges
+                .retain(|range| range.start_offset < range.end_offset);
+        }
+        self.functions
+            .retain(|function| !function.ranges.is_empty());
+        self.source = Some(source.source.clone());
+    }
+
+    /// Execution count of the innermost range containing `offset`
+    fn count_at(&self, offset: usize) -> Option<u64> {
+        self.functions
+            .iter()
+            .flat_map(|function| function.ranges.iter())
+            .filter(|range| range.start_offset <= offset && offset < range.end_offset)
+            .min_by_key(|range| range.end_offset - range.start_offset)
+            .map(|range| range.count)
+    }
+
+    fn write_lcov(&self, lcov: &mut String) {
+        lcov.push_str(&format!("TN:\nSF:{}\n", self.script_name));
+        let source = match &self.source {
+            Some(source) => source,
+            None => {
+                lcov.push_str("end_of_record\n");
+                return;
+            }
+        };
+        let starts = line_starts(source);
+        let line_of = |offset: usize| match starts.binary_search(&offset) {
+            Ok(line) => line + 1,
+            Err(line) => line,
+        };
+
+        // Functions, the top-level script code is reported by V8 as an unnamed function
+        let mut functions_hit = 0;
+        let mut functions_found = 0;
+        for (index, function) in self.functions.iter().enumerate() {
+            let range = match function.ranges.first() {
+                Some(range) => range,
+                None => continue,
+            };
+            let name = if function.function_name.is_empty() {
+                format!("(anonymous_{})", index)
+            } else {
+                function.function_name.clone()
+            };
+            lcov.push_str(&format!("FN:{},{}\n", line_of(range.start_offset), name));
+            lcov.push_str(&format!("FNDA:{},{}\n", range.count, name));
+            functions_found += 1;
+            if range.count > 0 {
+                functions_hit += 1;
+            }
+        }
+        lcov.push_str(&format!("FNF:{}\nFNH:{}\n", functions_found, functions_hit));
+
+        // Lines, counted at their first non-whitespace character
+        let mut lines_hit = 0;
+        let mut lines_found = 0;
+        let utf16_source: Vec<u16> = source.encode_utf16().collect();
+        for line in 0..starts.len() - 1 {
+            let first_char = (starts[line]..starts[line + 1]).find(|offset| {
+                !char::from_u32(utf16_source[*offset] as u32)
+                    .map(char::is_whitespace)
+                    .unwrap_or(false)
+            });
+            let count = match first_char.and_then(|offset| self.count_at(offset)) {
+                Some(count) => count,
+                None => continue,
+            };
+            lcov.push_str(&format!("DA:{},{}\n", line + 1, count));
+            lines_found += 1;
+            if count > 0 {
+                lines_hit += 1;
+            }
+        }
+        lcov.push_str(&format!("LF:{}\nLH:{}\n", lines_found, lines_hit));
+        lcov.push_str("end_of_record\n");
+    }
+}
+
+impl CoverageReport {
+    pub(crate) fn from_precise_coverage(
+        result: &serde_json::Value,
+        sources: &HashMap<String, CoverageSource>,
+    ) -> Result<CoverageReport, serde_json::Error> {
+        let scripts: Vec<ScriptCoverage> = serde_json::from_value(result["result"].clone())?;
+        let scripts = scripts
+            .into_iter()
+            .filter_map(|mut script| {
+                script.attach_source(sources.get(&script.script_name)?);
+                Some(script)
+            })
+            .collect();
+        Ok(CoverageReport { scripts })
+    }
+
+    /// Coverage of the script run with `script_name`
+    pub fn script(&self, script_name: &str) -> Option<&ScriptCoverage> {
+        self.scripts
+            .iter()
+            .find(|script| script.script_name == script_name)
+    }
+
+    /// Exports the coverage as V8 coverage JSON (as written by `NODE_V8_COVERAGE`)
+    pub fn to_v8_json(&self) -> String {
+        serde_json::json!({ "result": self.scripts }).to_string()
+    }
+
+    /// Exports the coverage as LCOV tracefile
+    pub fn to_lcov(&self) -> String {
+        let mut lcov = String::new();
+        for script in &self.scripts {
+            script.write_lcov(&mut lcov);
+        }
+        lcov
+    }
+}
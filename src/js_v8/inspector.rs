@@ -0,0 +1,291 @@
+use crate::core::error::ScriptError;
+use rusty_v8 as v8;
+use std::{
+    collections::HashMap,
+    mem::MaybeUninit,
+    ptr,
+    sync::mpsc::{channel, Receiver, Sender},
+};
+
+const CONTEXT_GROUP_ID: i32 = 1;
+
+/// Allocates a `T` on the heap, giving its final address to the constructor: V8 keeps raw
+/// pointers to inspector clients and channels so those must never move.
+fn new_box_with<T>(new_fn: impl FnOnce(*mut T) -> T) -> Box<T> {
+    let b = Box::new(MaybeUninit::<T>::uninit());
+    let p = Box::into_raw(b) as *mut T;
+    unsafe { ptr::write(p, new_fn(p)) };
+    unsafe { Box::from_raw(p) }
+}
+
+fn string_buffer_to_string(message: v8::UniquePtr<v8::inspector::StringBuffer>) -> String {
+    message.unwrap().string().to_string()
+}
+
+enum FrontendMessage {
+    Message(usize, String),
+    Disconnect(usize),
+}
+
+/// Frontend side of an inspector session speaking the Chrome DevTools Protocol
+///
+/// Messages are CDP JSON strings. The frontend can be moved to another thread, for instance to
+/// bridge it to a DevTools WebSocket or to drive a debugger while the script is paused.
+pub struct InspectorFrontend {
+    session_id: usize,
+    to_inspector: Sender<FrontendMessage>,
+    from_session: Receiver<String>,
+}
+
+impl InspectorFrontend {
+    /// Sends a CDP message to the environment, it is dispatched on the next run or when the script is paused
+    pub fn send(&self, message: &str) -> Result<(), ScriptError> {
+        self.to_inspector
+            .send(FrontendMessage::Message(
+                self.session_id,
+                message.to_string(),
+            ))
+            .map_err(|_| ScriptError::RuntimeError(String::from("Inspector session closed")))
+    }
+
+    /// Waits for the next CDP response or notification, `None` once the environment is gone
+    pub fn recv(&self) -> Option<String> {
+        self.from_session.recv().ok()
+    }
+
+    /// Gets the next CDP response or notification if there is one already
+    pub fn try_recv(&self) -> Option<String> {
+        self.from_session.try_recv().ok()
+    }
+}
+
+impl Drop for InspectorFrontend {
+    fn drop(&mut self) {
+        let _ = self
+            .to_inspector
+            .send(FrontendMessage::Disconnect(self.session_id));
+    }
+}
+
+pub(crate) struct InspectorSession {
+    v8_channel: v8::inspector::ChannelBase,
+    v8_session: v8::UniqueRef<v8::inspector::V8InspectorSession>,
+    outgoing: Sender<String>,
+}
+
+impl v8::inspector::ChannelImpl for InspectorSession {
+    fn base(&self) -> &v8::inspector::ChannelBase {
+        &self.v8_channel
+    }
+
+    fn base_mut(&mut self) -> &mut v8::inspector::ChannelBase {
+        &mut self.v8_channel
+    }
+
+    fn send_response(
+        &mut self,
+        _call_id: i32,
+        message: v8::UniquePtr<v8::inspector::StringBuffer>,
+    ) {
+        let _ = self.outgoing.send(string_buffer_to_string(message));
+    }
+
+    fn send_notification(&mut self, message: v8::UniquePtr<v8::inspector::StringBuffer>) {
+        let _ = self.outgoing.send(string_buffer_to_string(message));
+    }
+
+    fn flush_protocol_notifications(&mut self) {}
+}
+
+impl InspectorSession {
+    fn new(
+        v8_inspector: &mut v8::inspector::V8Inspector,
+        outgoing: Sender<String>,
+    ) -> Box<InspectorSession> {
+        new_box_with(move |self_ptr| {
+            let v8_channel = v8::inspector::ChannelBase::new::<Self>();
+            let empty_view = v8::inspector::StringView::empty();
+            let v8_session =
+                v8_inspector.connect(CONTEXT_GROUP_ID, unsafe { &mut *self_ptr }, empty_view);
+            InspectorSession {
+                v8_channel,
+                v8_session,
+                outgoing,
+            }
+        })
+    }
+
+    pub(crate) fn dispatch(&mut self, message: &str) {
+        let message = v8::inspector::StringView::from(message.as_bytes());
+        self.v8_session.dispatch_protocol_message(message);
+    }
+}
+
+/// Inspector attached to the global context of a `V8ScriptingEnvironment`
+pub(crate) struct Inspector {
+    v8_inspector_client: v8::inspector::V8InspectorClientBase,
+    v8_inspector: v8::UniqueRef<v8::inspector::V8Inspector>,
+    sessions: HashMap<usize, Box<InspectorSession>>,
+    next_session_id: usize,
+    incoming_sender: Sender<FrontendMessage>,
+    incoming: Receiver<FrontendMessage>,
+    paused: bool,
+}
+
+impl v8::inspector::V8InspectorClientImpl for Inspector {
+    fn base(&self) -> &v8::inspector::V8InspectorClientBase {
+        &self.v8_inspector_client
+    }
+
+    fn base_mut(&mut self) -> &mut v8::inspector::V8InspectorClientBase {
+        &mut self.v8_inspector_client
+    }
+
+    fn run_message_loop_on_pause(&mut self, context_group_id: i32) {
+        assert_eq!(context_group_id, CONTEXT_GROUP_ID);
+        // The script is paused (breakpoint, stepping...): block on frontend messages until one
+        // of them resumes execution
+        self.paused = true;
+        while self.paused {
+            match self.incoming.recv() {
+                Ok(message) => self.handle_message(message),
+                Err(_) => break,
+            }
+        }
+    }
+
+    fn quit_message_loop_on_pause(&mut self) {
+        self.paused = false;
+    }
+
+    fn run_if_waiting_for_debugger(&mut self, context_group_id: i32) {
+        assert_eq!(context_group_id, CONTEXT_GROUP_ID);
+    }
+}
+
+impl Inspector {
+    pub(crate) fn new(
+        scope: &mut v8::HandleScope,
+        context: v8::Local<v8::Context>,
+    ) -> Box<Inspector> {
+        let (incoming_sender, incoming) = channel();
+        let mut inspector = new_box_with(|self_ptr| {
+            let v8_inspector_client = v8::inspector::V8InspectorClientBase::new::<Self>();
+            let v8_inspector = v8::inspector::V8Inspector::create(scope, unsafe { &mut *self_ptr });
+            Inspector {
+                v8_inspector_client,
+                v8_inspector,
+                sessions: HashMap::new(),
+                next_session_id: 0,
+                incoming_sender,
+                incoming,
+                paused: false,
+            }
+        });
+
+        // Tell the inspector about the global context
+        let context_name = v8::inspector::StringView::from(&b"scriptit"[..]);
+        inspector
+            .v8_inspector
+            .context_created(context, CONTEXT_GROUP_ID, context_name);
+
+        inspector
+    }
+
+    /// Connects a new frontend
+    pub(crate) fn connect(&mut self) -> InspectorFrontend {
+        let (outgoing, from_session) = channel();
+        let session_id = self.next_session_id;
+        self.next_session_id += 1;
+        let session = self.create_session(outgoing);
+        self.sessions.insert(session_id, session);
+        InspectorFrontend {
+            session_id,
+            to_inspector: self.incoming_sender.clone(),
+            from_session,
+        }
+    }
+
+    /// Creates a session that is not attached to a frontend, the caller dispatches messages itself
+    pub(crate) fn create_session(&mut self, outgoing: Sender<String>) -> Box<InspectorSession> {
+        InspectorSession::new(&mut self.v8_inspector, outgoing)
+    }
+
+    /// Dispatches all pending frontend messages without blocking
+    pub(crate) fn poll_sessions(&mut self) {
+        while let Ok(message) = self.incoming.try_recv() {
+            self.handle_message(message);
+        }
+    }
+
+    fn handle_message(&mut self, message: FrontendMessage) {
+        match message {
+            FrontendMessage::Message(session_id, message) => {
+                if let Some(session) = self.sessions.get_mut(&session_id) {
+                    session.dispatch(&message);
+                }
+            }
+            FrontendMessage::Disconnect(session_id) => {
+                self.sessions.remove(&session_id);
+                if self.sessions.is_empty() {
+                    self.paused = false;
+                }
+            }
+        }
+    }
+}
+
+/// Session used by the environment itself to call CDP methods synchronously
+pub(crate) struct ProtocolSession {
+    session: Box<InspectorSession>,
+    from_session: Receiver<String>,
+    next_call_id: u64,
+}
+
+impl ProtocolSession {
+    pub(crate) fn new(inspector: &mut Inspector) -> ProtocolSession {
+        let (outgoing, from_session) = channel();
+        ProtocolSession {
+            session: inspector.create_session(outgoing),
+            from_session,
+            next_call_id: 1,
+        }
+    }
+
+    /// Calls a CDP method and returns its result, notifications sent meanwhile go to `on_notification`
+    pub(crate) fn call(
+        &mut self,
+        method: &str,
+        params: serde_json::Value,
+        on_notification: &mut dyn FnMut(&serde_json::Value),
+    ) -> Result<serde_json::Value, ScriptError> {
+        let call_id = self.next_call_id;
+        self.next_call_id += 1;
+        let message = serde_json::json!({
+            "id": call_id,
+            "method": method,
+            "params": params,
+        });
+        self.session.dispatch(&message.to_string());
+
+        // V8 responds synchronously while dispatching
+        while let Ok(message) = self.from_session.try_recv() {
+            let message: serde_json::Value = serde_json::from_str(&message)
+                .map_err(|e| ScriptError::SerializationError(e.to_string()))?;
+            if message["id"] != call_id {
+                on_notification(&message);
+            } else if let Some(error) = message.get("error") {
+                return Err(ScriptError::RuntimeError(format!(
+                    "Inspector call {} failed: {}",
+                    method, error["message"]
+                )));
+            } else {
+                return Ok(message["result"].clone());
+            }
+        }
+        Err(ScriptError::RuntimeError(format!(
+            "Inspector call {} got no response",
+            method
+        )))
+    }
+}
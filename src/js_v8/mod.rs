@@ -1,6 +1,32 @@
-use crate::core::{error::ScriptError, value::ScriptValue, ScriptingEnvironment};
+/// Precise code coverage reports
+pub mod coverage;
+/// Chrome DevTools Protocol inspector
+pub mod inspector;
+/// Web Workers spawned by scripts
+pub mod worker;
+
+use crate::core::{
+    compiled::CompiledScript,
+    error::ScriptError,
+    event_loop::{self, EventLoop},
+    register_func_handler, timers,
+    value::ScriptValue,
+    ScriptingEnvironment, ANONYMOUS_SCRIPT_NAME,
+};
+use coverage::{CoverageReport, CoverageSource};
+use inspector::{Inspector, InspectorFrontend, ProtocolSession};
 use rusty_v8 as v8;
-use std::{collections::HashMap, sync::Once};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    rc::Rc,
+    sync::Once,
+    time::Duration,
+};
+use worker::{WorkerFuncs, WorkerHost};
 
 static PLATFORM_INIT: Once = Once::new();
 
@@ -26,17 +52,97 @@ fn trycatch_scope_to_scripterror(
         }
     };
     let msg = v8::Exception::create_message(tc_scope, exception);
+    let mut description = msg.get(tc_scope).to_rust_string_lossy(tc_scope);
+
+    // Prefix the description with the script location: `name:line:column`
+    let resource_name = msg
+        .get_script_resource_name(tc_scope)
+        .and_then(|name| name.to_string(tc_scope))
+        .map(|name| name.to_rust_string_lossy(tc_scope));
+    if let (Some(resource_name), Some(line_number)) = (resource_name, msg.get_line_number(tc_scope))
+    {
+        description = format!(
+            "{}:{}:{}: {}",
+            resource_name,
+            line_number,
+            msg.get_start_column() + 1,
+            description
+        );
+    }
+
     if is_compile_step {
-        ScriptError::CompileError(String::from(
-            msg.get(tc_scope).to_rust_string_lossy(tc_scope),
-        ))
+        ScriptError::CompileError(description)
     } else {
-        ScriptError::RuntimeError(String::from(
-            msg.get(tc_scope).to_rust_string_lossy(tc_scope),
-        ))
+        // Runtime errors also carry the JS stack trace when there is one
+        let stack = tc_scope
+            .stack_trace()
+            .and_then(|stack| stack.to_string(tc_scope))
+            .map(|stack| stack.to_rust_string_lossy(tc_scope));
+        if let Some(stack) = stack {
+            description = format!("{}\n{}", description, stack);
+        }
+        ScriptError::RuntimeError(description)
+    }
+}
+
+fn script_origin<'s>(scope: &mut v8::HandleScope<'s>, script_name: &str) -> v8::ScriptOrigin<'s> {
+    let resource_name = v8::String::new(scope, script_name).unwrap();
+    let resource_line_offset = v8::Integer::new(scope, 0);
+    let resource_column_offset = v8::Integer::new(scope, 0);
+    let resource_is_shared_cross_origin = v8::Boolean::new(scope, false);
+    let script_id = v8::Integer::new(scope, 0);
+    let source_map_url = v8::String::new(scope, "").unwrap();
+    let resource_is_opaque = v8::Boolean::new(scope, false);
+    let is_wasm = v8::Boolean::new(scope, false);
+    let is_module = v8::Boolean::new(scope, false);
+    v8::ScriptOrigin::new(
+        resource_name.into(),
+        resource_line_offset,
+        resource_column_offset,
+        resource_is_shared_cross_origin,
+        script_id,
+        source_map_url.into(),
+        resource_is_opaque,
+        is_wasm,
+        is_module,
+    )
+}
+
+fn compile_script<'s>(
+    tc_scope: &mut v8::TryCatch<v8::HandleScope<'s>>,
+    source: &str,
+    script_name: &str,
+) -> Result<v8::Local<'s, v8::Script>, ScriptError> {
+    let source = v8::String::new(tc_scope, source).ok_or(ScriptError::CastError {
+        type_from: "&str",
+        type_to: "v8::String",
+    })?;
+    let origin = script_origin(tc_scope, script_name);
+    match v8::Script::compile(tc_scope, source, Some(&origin)) {
+        Some(script) => Ok(script),
+        None => Err(trycatch_scope_to_scripterror(tc_scope, true)),
+    }
+}
+
+fn run_script(
+    tc_scope: &mut v8::TryCatch<v8::HandleScope>,
+    script: v8::Local<v8::Script>,
+    is_expression: bool,
+) -> Result<ScriptValue, ScriptError> {
+    match script.run(tc_scope) {
+        Some(value) if is_expression => val_to_scriptvalue(tc_scope, &value),
+        Some(_) => Ok(ScriptValue::Null),
+        None => Err(trycatch_scope_to_scripterror(tc_scope, false)),
     }
 }
 
+/// Expressions are run wrapped so that they evaluate to JSON
+const EXPRESSION_PREFIX: &str = "JSON.stringify(";
+
+fn expression_source(source: &str) -> String {
+    format!("{}{})", EXPRESSION_PREFIX, source)
+}
+
 fn val_to_scriptvalue(
     scope: &mut v8::HandleScope,
     value: &v8::Local<v8::Value>,
@@ -101,10 +207,35 @@ fn core_call_to_rust_receiver(
     }
 }
 
+/// Memory usage of a `V8ScriptingEnvironment`, sizes are in bytes
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeapStatistics {
+    /// Memory used by JS objects
+    pub used_heap_size: usize,
+    /// Memory reserved by the JS heap
+    pub total_heap_size: usize,
+    /// Size the JS heap can't grow past
+    pub heap_size_limit: usize,
+    /// Memory held outside of the JS heap by JS objects (array buffers...)
+    pub external_memory: usize,
+    /// Number of low-level handlers registered, each `register_func` adds one
+    pub handler_count: usize,
+}
+
 /// A V8 scripting environment. This API also exists on WASM but JS will execute insecurely there.
 pub struct V8ScriptingEnvironment {
+    // Sessions keep pointers into the inspector which keeps pointers into the isolate: drop order matters
+    protocol_session: Option<ProtocolSession>,
+    inspector: Option<Box<Inspector>>,
     isolate: v8::OwnedIsolate,
     global_context: v8::Global<v8::Context>,
+    // Compiled scripts are bound to the global context, the only context of the environment
+    compiled_scripts: HashMap<String, v8::Global<v8::Script>>,
+    coverage_sources: Option<HashMap<String, CoverageSource>>,
+    // Profiling and coverage share the inspector profiler, it gets disabled once both are stopped
+    profiling: bool,
+    event_loop: Rc<RefCell<EventLoop>>,
+    workers: Rc<RefCell<WorkerHost>>,
 }
 
 impl V8ScriptingEnvironment {
@@ -121,12 +252,14 @@ impl V8ScriptingEnvironment {
 
             // Run the bootstrap scripts
             let bs_src = format!(
-                "{}\n{}",
+                "{}\n{}\n{}",
                 include_str!("./v8_bootstrap.js"),
-                include_str!("../js/shared_bootstrap.js")
+                include_str!("../js/shared_bootstrap.js"),
+                include_str!("./worker_bootstrap.js")
             );
             let bs_src = v8::String::new(scope, &bs_src).unwrap();
-            let bs_script = v8::Script::compile(scope, bs_src, None).unwrap();
+            let bs_origin = script_origin(scope, "scriptit:bootstrap.js");
+            let bs_script = v8::Script::compile(scope, bs_src, Some(&bs_origin)).unwrap();
             bs_script.run(scope).unwrap();
 
             // Go set ScriptIt.core.callToRust to core_call_to_rust_receiver
@@ -154,61 +287,312 @@ impl V8ScriptingEnvironment {
             handlers: HashMap::new(),
         });
 
-        V8ScriptingEnvironment {
+        let mut s_env = V8ScriptingEnvironment {
+            protocol_session: None,
+            inspector: None,
             isolate,
             global_context,
+            compiled_scripts: HashMap::new(),
+            coverage_sources: None,
+            profiling: false,
+            event_loop: Rc::new(RefCell::new(EventLoop::new())),
+            workers: Rc::new(RefCell::new(WorkerHost::new())),
+        };
+        timers::install(&mut s_env);
+        worker::install(&mut s_env);
+        s_env
+    }
+
+    /// Limits the V8 heap of the environments created afterwards, V8 aborts the process when a
+    /// script goes past `megabytes`
+    ///
+    /// V8 reads its flags once: this has to be called before the first environment gets created.
+    pub fn set_heap_limit(megabytes: usize) {
+        v8::V8::set_flags_from_command_line(vec![
+            String::from("scriptit"),
+            format!("--max-old-space-size={}", megabytes),
+        ]);
+    }
+
+    /// Restricts the registered functions that workers spawned by scripts can call
+    ///
+    /// Only the functions allowed when a worker gets spawned are available to it.
+    pub fn set_worker_funcs(&mut self, funcs: WorkerFuncs) {
+        self.workers.borrow_mut().set_funcs(funcs);
+    }
+
+    /// Sets how long workers spawned from now on wait for this environment to answer their calls
+    /// to registered functions (30 seconds by default)
+    ///
+    /// Calls are answered while this environment runs its event loop: a worker calling a function
+    /// is blocked until then, and the call fails with an error once `timeout` has elapsed.
+    pub fn set_worker_call_timeout(&mut self, timeout: Duration) {
+        self.workers.borrow_mut().set_call_timeout(timeout);
+    }
+
+    /// Connects a Chrome DevTools Protocol frontend to this environment
+    ///
+    /// Messages sent by the frontend are dispatched before each run, when calling
+    /// `poll_inspector` and, while a script is paused (breakpoint, stepping...), as they arrive.
+    pub fn connect_inspector(&mut self) -> InspectorFrontend {
+        if self.inspector.is_none() {
+            let scope = &mut v8::HandleScope::with_context(&mut self.isolate, &self.global_context);
+            let context = v8::Local::new(scope, &self.global_context);
+            self.inspector = Some(Inspector::new(scope, context));
         }
+        self.inspector.as_mut().unwrap().connect()
     }
-}
 
-impl ScriptingEnvironment for V8ScriptingEnvironment {
-    fn eval_expression(&mut self, source: &str) -> Result<ScriptValue, ScriptError> {
-        let source = format!("JSON.stringify({})", source);
-        let scope = &mut v8::HandleScope::with_context(&mut self.isolate, &self.global_context);
-        let source = v8::String::new(scope, &source).ok_or(ScriptError::CastError {
-            type_from: "&str",
-            type_to: "v8::String",
+    /// Starts recording a CPU profile of script execution
+    ///
+    /// Host functions registered with `register_func` show up in the profile under their own name.
+    pub fn start_profiling(&mut self) -> Result<(), ScriptError> {
+        self.inspector_call("Profiler.enable", serde_json::json!({}))?;
+        self.inspector_call("Profiler.start", serde_json::json!({}))?;
+        self.profiling = true;
+        Ok(())
+    }
+
+    /// Stops recording the CPU profile and returns it as `.cpuprofile` JSON, loadable in Chrome DevTools
+    pub fn stop_profiling(&mut self) -> Result<String, ScriptError> {
+        let result = self.inspector_call("Profiler.stop", serde_json::json!({}))?;
+        self.profiling = false;
+        self.disable_unused_profiler()?;
+        Ok(result["profile"].to_string())
+    }
+
+    /// Disables the inspector profiler unless profiling or coverage collection still runs
+    fn disable_unused_profiler(&mut self) -> Result<(), ScriptError> {
+        if self.profiling || self.coverage_sources.is_some() {
+            return Ok(());
+        }
+        self.inspector_call("Profiler.disable", serde_json::json!({}))?;
+        Ok(())
+    }
+
+    /// Returns the current memory usage of the environment
+    pub fn heap_statistics(&mut self) -> HeapStatistics {
+        let mut stats = v8::HeapStatistics::default();
+        self.isolate.get_heap_statistics(&mut stats);
+        let handler_count = self
+            .isolate
+            .get_slot::<V8ScriptingState>()
+            .map(|state| state.handlers.len())
+            .unwrap_or(0);
+        HeapStatistics {
+            used_heap_size: stats.used_heap_size(),
+            total_heap_size: stats.total_heap_size(),
+            heap_size_limit: stats.heap_size_limit(),
+            external_memory: stats.external_memory(),
+            handler_count,
+        }
+    }
+
+    /// Writes a snapshot of the JS heap to `path`, as a `.heapsnapshot` loadable in Chrome DevTools
+    pub fn write_heap_snapshot<P: AsRef<Path>>(&mut self, path: P) -> Result<(), ScriptError> {
+        let file = File::create(path).map_err(|e| ScriptError::RuntimeError(e.to_string()))?;
+        let mut writer = BufWriter::new(file);
+        let mut write_result = Ok(());
+
+        // The snapshot is streamed in chunks, as notifications, before the call returns
+        self.inspector_call_with_notifications(
+            "HeapProfiler.takeHeapSnapshot",
+            serde_json::json!({ "reportProgress": false }),
+            &mut |notification| {
+                if notification["method"] != "HeapProfiler.addHeapSnapshotChunk" {
+                    return;
+                }
+                if let Some(chunk) = notification["params"]["chunk"].as_str() {
+                    if write_result.is_ok() {
+                        write_result = writer.write_all(chunk.as_bytes());
+                    }
+                }
+            },
+        )?;
+
+        write_result
+            .and_then(|_| writer.flush())
+            .map_err(|e| ScriptError::RuntimeError(e.to_string()))
+    }
+
+    /// Starts collecting precise (block-level) code coverage of the scripts run from now on
+    pub fn start_coverage(&mut self) -> Result<(), ScriptError> {
+        self.inspector_call("Profiler.enable", serde_json::json!({}))?;
+        self.inspector_call(
+            "Profiler.startPreciseCoverage",
+            serde_json::json!({ "callCount": true, "detailed": true }),
+        )?;
+        self.coverage_sources = Some(HashMap::new());
+        Ok(())
+    }
+
+    /// Returns the coverage collected so far, execution counts are reset afterwards
+    pub fn take_coverage(&mut self) -> Result<CoverageReport, ScriptError> {
+        let result = self.inspector_call("Profiler.takePreciseCoverage", serde_json::json!({}))?;
+        let sources = self.coverage_sources.as_ref().ok_or_else(|| {
+            ScriptError::RuntimeError(String::from("Coverage collection was not started"))
         })?;
+        CoverageReport::from_precise_coverage(&result, sources)
+            .map_err(|e| ScriptError::SerializationError(e.to_string()))
+    }
+
+    /// Stops collecting code coverage
+    pub fn stop_coverage(&mut self) -> Result<(), ScriptError> {
+        self.inspector_call("Profiler.stopPreciseCoverage", serde_json::json!({}))?;
+        self.coverage_sources = None;
+        self.disable_unused_profiler()
+    }
 
+    fn record_coverage_source(&mut self, source: &str, script_name: &str, is_expression: bool) {
+        // Only named scripts are reported: anonymous ones can't be told apart
+        if script_name == ANONYMOUS_SCRIPT_NAME {
+            return;
+        }
+        if let Some(sources) = self.coverage_sources.as_mut() {
+            let coverage_source = CoverageSource {
+                source: source.to_string(),
+                prefix_len: if is_expression {
+                    EXPRESSION_PREFIX.len()
+                } else {
+                    0
+                },
+            };
+            sources.insert(script_name.to_string(), coverage_source);
+        }
+    }
+
+    fn inspector(&mut self) -> &mut Inspector {
+        if self.inspector.is_none() {
+            let scope = &mut v8::HandleScope::with_context(&mut self.isolate, &self.global_context);
+            let context = v8::Local::new(scope, &self.global_context);
+            self.inspector = Some(Inspector::new(scope, context));
+        }
+        self.inspector.as_mut().unwrap()
+    }
+
+    fn inspector_call(
+        &mut self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value, ScriptError> {
+        self.inspector_call_with_notifications(method, params, &mut |_| {})
+    }
+
+    fn inspector_call_with_notifications(
+        &mut self,
+        method: &str,
+        params: serde_json::Value,
+        on_notification: &mut dyn FnMut(&serde_json::Value),
+    ) -> Result<serde_json::Value, ScriptError> {
+        if self.protocol_session.is_none() {
+            let session = ProtocolSession::new(self.inspector());
+            self.protocol_session = Some(session);
+        }
+        self.protocol_session
+            .as_mut()
+            .unwrap()
+            .call(method, params, on_notification)
+    }
+
+    /// Dispatches the pending messages of connected inspector frontends
+    pub fn poll_inspector(&mut self) {
+        if let Some(inspector) = self.inspector.as_mut() {
+            inspector.poll_sessions();
+        }
+    }
+
+    fn internal_compile(
+        &mut self,
+        source: &str,
+        script_name: &str,
+        is_expression: bool,
+    ) -> Result<CompiledScript, ScriptError> {
+        self.poll_inspector();
+        self.record_coverage_source(source, script_name, is_expression);
+        let scope = &mut v8::HandleScope::with_context(&mut self.isolate, &self.global_context);
         let tc_scope = &mut v8::TryCatch::new(scope);
 
-        let script = match v8::Script::compile(tc_scope, source, None) {
-            Some(script) => script,
-            None => {
-                return Err(trycatch_scope_to_scripterror(tc_scope, true));
-            }
+        let script = if is_expression {
+            compile_script(tc_scope, &expression_source(source), script_name)?
+        } else {
+            compile_script(tc_scope, source, script_name)?
         };
 
-        match script.run(tc_scope) {
-            Some(value) => val_to_scriptvalue(tc_scope, &value),
-            None => {
-                return Err(trycatch_scope_to_scripterror(tc_scope, false));
-            }
-        }
+        let compiled = CompiledScript::new(script_name, is_expression);
+        self.compiled_scripts
+            .insert(compiled.id().to_string(), v8::Global::new(tc_scope, script));
+        Ok(compiled)
     }
+}
 
-    fn run(&mut self, source: &str) -> Result<(), ScriptError> {
+impl ScriptingEnvironment for V8ScriptingEnvironment {
+    fn eval_expression_named(
+        &mut self,
+        source: &str,
+        script_name: &str,
+    ) -> Result<ScriptValue, ScriptError> {
+        self.poll_inspector();
+        self.record_coverage_source(source, script_name, true);
         let scope = &mut v8::HandleScope::with_context(&mut self.isolate, &self.global_context);
-        let source = v8::String::new(scope, &source).ok_or(ScriptError::CastError {
-            type_from: "&str",
-            type_to: "v8::String",
-        })?;
+        let tc_scope = &mut v8::TryCatch::new(scope);
 
+        let script = compile_script(tc_scope, &expression_source(source), script_name)?;
+        run_script(tc_scope, script, true)
+    }
+
+    fn run_named(&mut self, source: &str, script_name: &str) -> Result<(), ScriptError> {
+        self.poll_inspector();
+        self.record_coverage_source(source, script_name, false);
+        let scope = &mut v8::HandleScope::with_context(&mut self.isolate, &self.global_context);
         let tc_scope = &mut v8::TryCatch::new(scope);
 
-        let script = match v8::Script::compile(tc_scope, source, None) {
-            Some(script) => script,
-            None => {
-                return Err(trycatch_scope_to_scripterror(tc_scope, true));
-            }
-        };
+        let script = compile_script(tc_scope, source, script_name)?;
+        run_script(tc_scope, script, false)?;
+        Ok(())
+    }
 
-        match script.run(tc_scope) {
-            Some(_) => Ok(()),
-            None => {
-                return Err(trycatch_scope_to_scripterror(tc_scope, false));
-            }
-        }
+    fn compile_named(
+        &mut self,
+        source: &str,
+        script_name: &str,
+    ) -> Result<CompiledScript, ScriptError> {
+        self.internal_compile(source, script_name, false)
+    }
+
+    fn compile_expression_named(
+        &mut self,
+        source: &str,
+        script_name: &str,
+    ) -> Result<CompiledScript, ScriptError> {
+        self.internal_compile(source, script_name, true)
+    }
+
+    fn run_compiled(&mut self, script: &CompiledScript) -> Result<ScriptValue, ScriptError> {
+        self.poll_inspector();
+        let compiled_script = self.compiled_scripts.get(script.id()).ok_or_else(|| {
+            ScriptError::RuntimeError(format!("Unknown compiled script: {}", script.id()))
+        })?;
+        let scope = &mut v8::HandleScope::with_context(&mut self.isolate, &self.global_context);
+        let tc_scope = &mut v8::TryCatch::new(scope);
+
+        let compiled_script = v8::Local::new(tc_scope, compiled_script);
+        run_script(tc_scope, compiled_script, script.is_expression())
+    }
+
+    fn release_compiled(&mut self, script: CompiledScript) {
+        self.compiled_scripts.remove(script.id());
+    }
+
+    fn expression_column_offset(&self) -> u32 {
+        EXPRESSION_PREFIX.len() as u32
+    }
+
+    fn event_loop(&self) -> Rc<RefCell<EventLoop>> {
+        Rc::clone(&self.event_loop)
+    }
+
+    fn run_event_loop(&mut self) -> Result<(), ScriptError> {
+        event_loop::run(self)
     }
 
     fn register_core_handler(
@@ -223,6 +607,18 @@ impl ScriptingEnvironment for V8ScriptingEnvironment {
             .handlers
             .insert(handler_name.to_string(), handler_closure);
     }
+
+    fn register_func(
+        &mut self,
+        func_name: &str,
+        handler_closure: Box<dyn FnMut(&Vec<ScriptValue>) -> Result<ScriptValue, ScriptError>>,
+    ) {
+        let core_handler_name = register_func_handler(self, func_name, handler_closure);
+        // Kept on the Rust side so scripts can't change the handlers their workers call
+        self.workers
+            .borrow_mut()
+            .add_func_handler(func_name, core_handler_name);
+    }
 }
 
 pub type PlatformScriptingEnvironment = V8ScriptingEnvironment;
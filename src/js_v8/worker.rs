@@ -0,0 +1,363 @@
+//! Web Workers: scripts spawn `new Worker(source)` to run code on another thread
+//!
+//! Each worker gets its own `V8ScriptingEnvironment`, and event loop, on a dedicated thread.
+//! Messages are exchanged with `postMessage`/`onmessage` and are structured clones. Functions
+//! registered with `register_func` are inherited by workers: calls are forwarded to the parent
+//! environment, which only answers them while running its event loop. The worker is blocked
+//! meanwhile: calls fail after 30 seconds without an answer, see
+//! `V8ScriptingEnvironment::set_worker_call_timeout`.
+//!
+//! ```
+//! use scriptit::{
+//!     core::{value::ScriptValue, ScriptingEnvironment},
+//!     platform::PlatformScriptingEnvironment,
+//! };
+//!
+//! let mut s_env = PlatformScriptingEnvironment::new();
+//! s_env.run(r#"
+//!     globalThis.result = null;
+//!     const worker = new Worker(`
+//!         onmessage = (event) => {
+//!             postMessage(event.data.reduce((sum, n) => sum + n, 0));
+//!             close();
+//!         };
+//!     `);
+//!     worker.onmessage = (event) => { globalThis.result = event.data; };
+//!     worker.postMessage([1, 2, 3]);
+//! "#).unwrap();
+//! s_env.run_event_loop().unwrap();
+//!
+//! assert_eq!(s_env.eval_expression("result").unwrap(), ScriptValue::from(6));
+//! ```
+
+use super::V8ScriptingEnvironment;
+use crate::core::{
+    error::ScriptError, event_loop::RemoteTaskSender, value::ScriptValue, ScriptingEnvironment,
+};
+use rusty_v8 as v8;
+use serde::Deserialize;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::Rc,
+    sync::mpsc::{channel, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+/// Pending operation keeping a worker alive until it calls `close` or gets terminated
+const PARENT_OP: &str = "parent";
+/// How long a worker waits for the parent environment to answer a call, by default
+const DEFAULT_CALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Functions registered on an environment that its workers can call
+pub enum WorkerFuncs {
+    /// Every registered function (the default)
+    All,
+    /// Only the functions with these names
+    Only(Vec<String>),
+    /// No function
+    None,
+}
+
+impl WorkerFuncs {
+    fn allows(&self, func_name: &str) -> bool {
+        match self {
+            WorkerFuncs::All => true,
+            WorkerFuncs::Only(names) => names.iter().any(|name| name == func_name),
+            WorkerFuncs::None => false,
+        }
+    }
+}
+
+struct WorkerHandle {
+    tasks: RemoteTaskSender,
+    isolate: v8::IsolateHandle,
+}
+
+impl WorkerHandle {
+    fn terminate(&self) {
+        self.isolate.terminate_execution();
+        self.tasks.send(Box::new(|s_env| {
+            s_env
+                .event_loop()
+                .borrow_mut()
+                .complete_pending_op(PARENT_OP);
+            Ok(())
+        }));
+    }
+}
+
+/// Workers spawned from an environment, they are terminated when it gets dropped
+pub(crate) struct WorkerHost {
+    workers: HashMap<String, WorkerHandle>,
+    // Core handler behind each registered function, workers only get the ones `funcs` allows
+    func_handlers: HashMap<String, String>,
+    funcs: WorkerFuncs,
+    call_timeout: Duration,
+}
+
+impl WorkerHost {
+    pub(crate) fn new() -> WorkerHost {
+        WorkerHost {
+            workers: HashMap::new(),
+            func_handlers: HashMap::new(),
+            funcs: WorkerFuncs::All,
+            call_timeout: DEFAULT_CALL_TIMEOUT,
+        }
+    }
+
+    pub(crate) fn set_funcs(&mut self, funcs: WorkerFuncs) {
+        self.funcs = funcs;
+    }
+
+    pub(crate) fn add_func_handler(&mut self, func_name: &str, core_handler_name: String) {
+        self.func_handlers
+            .insert(func_name.to_string(), core_handler_name);
+    }
+
+    /// Registered functions that workers can call, with the core handler behind them
+    fn allowed_func_handlers(&self) -> Vec<(String, String)> {
+        self.func_handlers
+            .iter()
+            .filter(|(func_name, _)| self.funcs.allows(func_name))
+            .map(|(func_name, handler)| (func_name.clone(), handler.clone()))
+            .collect()
+    }
+
+    pub(crate) fn set_call_timeout(&mut self, call_timeout: Duration) {
+        self.call_timeout = call_timeout;
+    }
+}
+
+impl Drop for WorkerHost {
+    fn drop(&mut self) {
+        for worker in self.workers.values() {
+            worker.terminate();
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SpawnRequest {
+    source: String,
+}
+
+#[derive(Deserialize)]
+struct MessageRequest {
+    id: String,
+    data: String,
+}
+
+fn js_string(value: &str) -> String {
+    ScriptValue::String(value.to_string()).to_string()
+}
+
+/// Tells the parent environment that a worker stopped, even if its thread panicked
+struct WorkerExit {
+    id: String,
+    parent: RemoteTaskSender,
+    result: Result<(), ScriptError>,
+}
+
+impl Drop for WorkerExit {
+    fn drop(&mut self) {
+        let id = self.id.clone();
+        let error = match &self.result {
+            Ok(()) if !thread::panicking() => None,
+            Ok(()) => Some(String::from("The worker thread panicked")),
+            Err(error) => Some(error.to_string()),
+        };
+        self.parent.send(Box::new(move |s_env| {
+            s_env
+                .event_loop()
+                .borrow_mut()
+                .complete_pending_op(&format!("worker${}", id));
+            match error {
+                Some(error) => s_env.run(&format!(
+                    "ScriptIt.core.dispatchWorkerError({}, {})",
+                    js_string(&id),
+                    js_string(&error)
+                )),
+                None => Ok(()),
+            }
+        }));
+    }
+}
+
+/// Calls a function registered on the parent environment and waits for its result
+///
+/// The parent only answers while running its event loop, the call fails after `timeout`.
+fn call_parent(
+    parent: &RemoteTaskSender,
+    handler: &str,
+    args: &[ScriptValue],
+    timeout: Duration,
+) -> Result<ScriptValue, ScriptError> {
+    let parent_stopped =
+        || ScriptError::RuntimeError(String::from("The parent environment has stopped"));
+    let src = format!(
+        "JSON.parse(ScriptIt.core.callToRust({}, {}))",
+        js_string(handler),
+        js_string(&ScriptValue::from(args.to_vec()).to_string())
+    );
+    let (result_sender, result) = channel();
+    let sent = parent.send(Box::new(move |s_env| {
+        let _ = result_sender.send(s_env.eval_expression(&src));
+        Ok(())
+    }));
+    if !sent {
+        return Err(parent_stopped());
+    }
+    match result.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => Err(ScriptError::RuntimeError(format!(
+            "The parent environment did not answer within {} ms, it answers while running its event loop",
+            timeout.as_millis()
+        ))),
+        Err(RecvTimeoutError::Disconnected) => Err(parent_stopped()),
+    }
+}
+
+fn run_worker(
+    s_env: &mut V8ScriptingEnvironment,
+    id: &str,
+    source: &str,
+    funcs: Vec<(String, String)>,
+    call_timeout: Duration,
+    parent: &RemoteTaskSender,
+) -> Result<(), ScriptError> {
+    for (func_name, handler) in funcs {
+        let parent = parent.clone();
+        s_env.register_func(
+            &func_name,
+            Box::new(move |args| call_parent(&parent, &handler, args, call_timeout)),
+        );
+    }
+
+    let message_parent = parent.clone();
+    let message_id = id.to_string();
+    s_env.register_core_handler(
+        "worker$postToParent",
+        Box::new(move |data| {
+            let src = format!(
+                "ScriptIt.core.dispatchWorkerMessage({}, {})",
+                js_string(&message_id),
+                js_string(data)
+            );
+            message_parent.send(Box::new(move |s_env| s_env.run(&src)));
+            Ok(String::new())
+        }),
+    );
+
+    let event_loop = s_env.event_loop();
+    event_loop.borrow_mut().add_pending_op(PARENT_OP);
+    s_env.register_core_handler(
+        "worker$close",
+        Box::new(move |_| {
+            event_loop.borrow_mut().complete_pending_op(PARENT_OP);
+            Ok(String::new())
+        }),
+    );
+
+    s_env.run_named(
+        include_str!("./worker_scope.js"),
+        "scriptit:worker_scope.js",
+    )?;
+    s_env.run_named(source, &format!("worker:{}", id))?;
+    s_env.run_event_loop()
+}
+
+fn spawn(
+    host: &Rc<RefCell<WorkerHost>>,
+    parent: RemoteTaskSender,
+    request: SpawnRequest,
+) -> Result<String, String> {
+    let SpawnRequest { source } = request;
+    let id = uuid::Uuid::new_v4().to_string();
+    let funcs = host.borrow().allowed_func_handlers();
+    let call_timeout = host.borrow().call_timeout;
+
+    let (handle_sender, handle) = channel();
+    let worker_id = id.clone();
+    thread::Builder::new()
+        .name(format!("worker:{}", id))
+        .spawn(move || {
+            let mut exit = WorkerExit {
+                id: worker_id.clone(),
+                parent: parent.clone(),
+                result: Ok(()),
+            };
+            let mut s_env = V8ScriptingEnvironment::new();
+            let _ = handle_sender.send(WorkerHandle {
+                tasks: s_env.event_loop().borrow().remote(),
+                isolate: s_env.isolate.thread_safe_handle(),
+            });
+            exit.result = run_worker(
+                &mut s_env,
+                &worker_id,
+                &source,
+                funcs,
+                call_timeout,
+                &parent,
+            );
+        })
+        .map_err(|e| e.to_string())?;
+
+    let handle = handle
+        .recv()
+        .map_err(|_| String::from("The worker failed to start"))?;
+    host.borrow_mut().workers.insert(id.clone(), handle);
+    Ok(id)
+}
+
+/// Registers the handlers behind the `Worker` class on `s_env`
+pub(crate) fn install(s_env: &mut V8ScriptingEnvironment) {
+    let event_loop = s_env.event_loop();
+    let host = Rc::clone(&s_env.workers);
+    s_env.register_core_handler(
+        "worker$spawn",
+        Box::new(move |data| {
+            let request: SpawnRequest = serde_json::from_str(data).map_err(|e| e.to_string())?;
+            let parent = event_loop.borrow().remote();
+            let id = spawn(&host, parent, request)?;
+            event_loop
+                .borrow_mut()
+                .add_pending_op(&format!("worker${}", id));
+            Ok(id)
+        }),
+    );
+
+    let host = Rc::clone(&s_env.workers);
+    s_env.register_core_handler(
+        "worker$postMessage",
+        Box::new(move |data| {
+            let request: MessageRequest = serde_json::from_str(data).map_err(|e| e.to_string())?;
+            let src = format!(
+                "ScriptIt.core.dispatchParentMessage({})",
+                js_string(&request.data)
+            );
+            let mut host = host.borrow_mut();
+            let sent = match host.workers.get(&request.id) {
+                Some(worker) => worker.tasks.send(Box::new(move |s_env| s_env.run(&src))),
+                None => false,
+            };
+            // Messages sent to stopped workers are dropped
+            if !sent {
+                host.workers.remove(&request.id);
+            }
+            Ok(String::new())
+        }),
+    );
+
+    let host = Rc::clone(&s_env.workers);
+    s_env.register_core_handler(
+        "worker$terminate",
+        Box::new(move |id| {
+            if let Some(worker) = host.borrow_mut().workers.remove(id) {
+                worker.terminate();
+            }
+            Ok(String::new())
+        }),
+    );
+}
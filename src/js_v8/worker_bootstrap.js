@@ -0,0 +1,71 @@
+// @ts-check
+
+(() => {
+    /** @type {Map<string, Worker>} Workers spawned from this environment, by id */
+    const workers = new Map();
+
+    /**
+     * Runs `source` on its own thread and environment, messages are structured clones
+     */
+    class Worker {
+        /**
+         * @param {string} source Code run by the worker
+         */
+        constructor(source) {
+            /** @type {((event: { data: any }) => void) | null} */
+            this.onmessage = null;
+            /** @type {((event: { message: string }) => void) | null} */
+            this.onerror = null;
+            const spawn = { source: String(source) };
+            /** @type {string} */
+            this.id = ScriptIt.core.callToRust("worker$spawn", JSON.stringify(spawn));
+            workers.set(this.id, this);
+        }
+
+        /**
+         * Sends a structured clone of `message` to the worker
+         * @param {any} message
+         */
+        postMessage(message) {
+            const data = ScriptIt.core.serialize(message);
+            ScriptIt.core.callToRust("worker$postMessage", JSON.stringify({ id: this.id, data }));
+        }
+
+        /**
+         * Stops the worker right away
+         */
+        terminate() {
+            workers.delete(this.id);
+            ScriptIt.core.callToRust("worker$terminate", this.id);
+        }
+    }
+
+    /**
+     * Dispatches a message sent by a worker
+     * @param {string} id Id of the worker
+     * @param {string} data Serialized message
+     */
+    function dispatchWorkerMessage(id, data) {
+        const worker = workers.get(id);
+        if (worker && typeof worker.onmessage === "function") {
+            worker.onmessage({ data: ScriptIt.core.deserialize(data) });
+        }
+    }
+
+    /**
+     * Dispatches an uncaught error of a worker, the worker is stopped
+     * @param {string} id Id of the worker
+     * @param {string} message Description of the error
+     */
+    function dispatchWorkerError(id, message) {
+        const worker = workers.get(id);
+        workers.delete(id);
+        if (worker && typeof worker.onerror === "function") {
+            worker.onerror({ message });
+        }
+    }
+
+    globalThis.Worker = Worker;
+    ScriptIt.core.dispatchWorkerMessage = dispatchWorkerMessage;
+    ScriptIt.core.dispatchWorkerError = dispatchWorkerError;
+})();
@@ -0,0 +1,31 @@
+// @ts-check
+
+// Global scope of the code run by a worker
+globalThis.self = globalThis;
+/** @type {((event: { data: any }) => void) | null} */
+globalThis.onmessage = null;
+
+/**
+ * Sends a structured clone of `message` to the parent environment
+ * @param {any} message
+ */
+globalThis.postMessage = (message) => {
+    ScriptIt.core.callToRust("worker$postToParent", ScriptIt.core.serialize(message));
+};
+
+/**
+ * Stops the worker once the tasks already queued have run
+ */
+globalThis.close = () => {
+    ScriptIt.core.callToRust("worker$close", "");
+};
+
+/**
+ * Dispatches a message sent by the parent environment
+ * @param {string} data Serialized message
+ */
+ScriptIt.core.dispatchParentMessage = (data) => {
+    if (typeof globalThis.onmessage === "function") {
+        globalThis.onmessage({ data: ScriptIt.core.deserialize(data) });
+    }
+};
@@ -1,4 +1,11 @@
-use crate::core::{error::ScriptError, value::ScriptValue, ScriptingEnvironment};
+use crate::core::{
+    compiled::CompiledScript,
+    error::ScriptError,
+    event_loop::{self, EventLoop},
+    timers,
+    value::ScriptValue,
+    ScriptingEnvironment,
+};
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 use wasm_bindgen::prelude::*;
 
@@ -11,14 +18,24 @@ extern "C" {
     #[wasm_bindgen(constructor)]
     fn new(message: &str) -> Error;
 
+    #[wasm_bindgen(method, getter)]
+    fn name(this: &Error) -> String;
+
     #[wasm_bindgen(method, getter)]
     fn message(this: &Error) -> String;
 
+    #[wasm_bindgen(method, getter)]
+    fn stack(this: &Error) -> Option<String>;
+
     type BootstrapResult;
     type CompiledFunction;
 
     #[wasm_bindgen(method, catch)]
-    fn compile(this: &BootstrapResult, s: &str) -> Result<CompiledFunction, Error>;
+    fn compile(
+        this: &BootstrapResult,
+        s: &str,
+        script_name: &str,
+    ) -> Result<CompiledFunction, Error>;
 
     #[wasm_bindgen(method, catch)]
     fn run(this: &BootstrapResult, fun: &CompiledFunction) -> Result<JsValue, Error>;
@@ -32,7 +49,10 @@ fn js_bootstrap() -> BootstrapResult {
         .map_err(|e| e.message())
         .unwrap();
     let shared_bootstrap_src = wasm_bootstrap_res
-        .compile(include_str!("../js/shared_bootstrap.js"))
+        .compile(
+            include_str!("../js/shared_bootstrap.js"),
+            "scriptit:bootstrap.js",
+        )
         .map_err(|e| e.message())
         .unwrap();
     wasm_bootstrap_res
@@ -53,20 +73,30 @@ fn jsvalue_to_script_compile_error(error: Error) -> ScriptError {
 }
 
 fn jsvalue_to_script_runtime_error(error: Error) -> ScriptError {
-    ScriptError::RuntimeError(error.message())
+    let mut description = format!("{}: {}", error.name(), error.message());
+    // The stack refers to the `//# sourceURL` given at compile time, outside of V8 it doesn't
+    // include the message
+    if let Some(stack) = error.stack().filter(|stack| !stack.is_empty()) {
+        description = format!("{}\n{}", description, stack.trim_end());
+    }
+    ScriptError::RuntimeError(description)
 }
 
 pub struct WASMScriptingEnvironment {
     bootstrapped: BootstrapResult,
     handlers: Rc<RefCell<HashMap<String, Box<dyn FnMut(&str) -> Result<String, String>>>>>,
+    compiled_functions: HashMap<String, CompiledFunction>,
+    event_loop: Rc<RefCell<EventLoop>>,
 }
 
 impl WASMScriptingEnvironment {
     pub fn new() -> WASMScriptingEnvironment {
         let handlers = Rc::new(RefCell::new(HashMap::new()));
-        let wse = WASMScriptingEnvironment {
+        let mut wse = WASMScriptingEnvironment {
             bootstrapped: js_bootstrap(),
             handlers: Rc::clone(&handlers),
+            compiled_functions: HashMap::new(),
+            event_loop: Rc::new(RefCell::new(EventLoop::new())),
         };
 
         let closure_handlers = Rc::clone(&handlers);
@@ -90,34 +120,108 @@ impl WASMScriptingEnvironment {
             as Box<dyn FnMut(JsValue, JsValue) -> Result<JsValue, JsValue>>);
 
         wse.bootstrapped.set_call_to_rust(closure.into_js_value());
+        timers::install(&mut wse);
 
         wse
     }
 
-    fn internal_eval(&mut self, source: &str) -> Result<ScriptValue, ScriptError> {
-        let func = self
-            .bootstrapped
-            .compile(source)
-            .map_err(|e| jsvalue_to_script_compile_error(e))?;
-        match self.bootstrapped.run(&func) {
-            Ok(value) => jsvalue_to_scriptvalue(value),
+    fn internal_compile(
+        &self,
+        source: &str,
+        script_name: &str,
+    ) -> Result<CompiledFunction, ScriptError> {
+        self.bootstrapped
+            .compile(source, script_name)
+            .map_err(|e| jsvalue_to_script_compile_error(e))
+    }
+
+    fn internal_run(
+        &self,
+        func: &CompiledFunction,
+        is_expression: bool,
+    ) -> Result<ScriptValue, ScriptError> {
+        match self.bootstrapped.run(func) {
+            Ok(value) if is_expression => jsvalue_to_scriptvalue(value),
+            Ok(_) => Ok(ScriptValue::Null),
             Err(value) => Err(jsvalue_to_script_runtime_error(value)),
         }
     }
 }
 
+/// Expressions are run as the body of a function returning them
+const EXPRESSION_PREFIX: &str = "return ";
+
+fn expression_source(source: &str) -> String {
+    format!("{}{}", EXPRESSION_PREFIX, source)
+}
+
 impl ScriptingEnvironment for WASMScriptingEnvironment {
     /// Evaluates a single JS expression
-    fn eval_expression(&mut self, source: &str) -> Result<ScriptValue, ScriptError> {
-        self.internal_eval(&format!("return {}", source))
+    fn eval_expression_named(
+        &mut self,
+        source: &str,
+        script_name: &str,
+    ) -> Result<ScriptValue, ScriptError> {
+        let func = self.internal_compile(&expression_source(source), script_name)?;
+        self.internal_run(&func, true)
     }
 
     /// Runs JavaScript code
-    fn run(&mut self, source: &str) -> Result<(), ScriptError> {
-        self.internal_eval(source)?;
+    fn run_named(&mut self, source: &str, script_name: &str) -> Result<(), ScriptError> {
+        let func = self.internal_compile(source, script_name)?;
+        self.internal_run(&func, false)?;
         Ok(())
     }
 
+    /// Compiles JavaScript code into a reusable `Function`
+    fn compile_named(
+        &mut self,
+        source: &str,
+        script_name: &str,
+    ) -> Result<CompiledScript, ScriptError> {
+        let func = self.internal_compile(source, script_name)?;
+        let compiled = CompiledScript::new(script_name, false);
+        self.compiled_functions
+            .insert(compiled.id().to_string(), func);
+        Ok(compiled)
+    }
+
+    /// Compiles a single JS expression into a reusable `Function`
+    fn compile_expression_named(
+        &mut self,
+        source: &str,
+        script_name: &str,
+    ) -> Result<CompiledScript, ScriptError> {
+        let func = self.internal_compile(&expression_source(source), script_name)?;
+        let compiled = CompiledScript::new(script_name, true);
+        self.compiled_functions
+            .insert(compiled.id().to_string(), func);
+        Ok(compiled)
+    }
+
+    fn run_compiled(&mut self, script: &CompiledScript) -> Result<ScriptValue, ScriptError> {
+        let func = self.compiled_functions.get(script.id()).ok_or_else(|| {
+            ScriptError::RuntimeError(format!("Unknown compiled script: {}", script.id()))
+        })?;
+        self.internal_run(func, script.is_expression())
+    }
+
+    fn release_compiled(&mut self, script: CompiledScript) {
+        self.compiled_functions.remove(script.id());
+    }
+
+    fn expression_column_offset(&self) -> u32 {
+        EXPRESSION_PREFIX.len() as u32
+    }
+
+    fn event_loop(&self) -> Rc<RefCell<EventLoop>> {
+        Rc::clone(&self.event_loop)
+    }
+
+    fn run_event_loop(&mut self) -> Result<(), ScriptError> {
+        event_loop::run(self)
+    }
+
     fn register_core_handler(
         &mut self,
         handler_name: &str,
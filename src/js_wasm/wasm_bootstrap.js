@@ -91,10 +91,11 @@
 
     /**
      * @param {string} stringSrc
+     * @param {string} scriptName Name reported in stack traces
      * @returns {(sbx: typeof sandbox) => any}
      */
-    function compile(stringSrc) {
-        const wrappedSource = `with (globalThis) { ${stringSrc} }`;
+    function compile(stringSrc, scriptName) {
+        const wrappedSource = `with (globalThis) { ${stringSrc}\n}\n//# sourceURL=${scriptName}`;
         /** @type {any} */
         const compiledFunction = new Function("globalThis", wrappedSource);
         return compiledFunction;
@@ -108,11 +109,40 @@
         return compiledFunction(sandboxProxy);
     }
 
+    /**
+     * Lets scripts access a global of the host engine
+     * @param {string} name
+     * @returns {boolean} Whether the host engine has this global
+     */
+    function passthroughGlobal(name) {
+        if (!(name in globalThis)) {
+            return false;
+        }
+        if (!passthroughGlobals.includes(name)) {
+            passthroughGlobals.push(name);
+        }
+        return true;
+    }
+
+    /**
+     * Stops passing a global of the host engine through, scripts get the sandbox one instead
+     * @param {string} name
+     */
+    function shadowGlobal(name) {
+        const index = passthroughGlobals.indexOf(name);
+        if (index !== -1) {
+            passthroughGlobals.splice(index, 1);
+        }
+    }
+
     /**
      * @param {(handler: string, data: string) => string} callToRust
      */
     function setCallToRust(callToRust) {
         sandbox.ScriptIt.core.callToRust = callToRust;
+        // Optional APIs installed from Rust use the host globals when there are some
+        sandbox.ScriptIt.core.passthroughGlobal = passthroughGlobal;
+        sandbox.ScriptIt.core.shadowGlobal = shadowGlobal;
     }
 
     return {
@@ -3,7 +3,13 @@
 //! scriptit will run your JS differently depending on your platform:
 //!
 //! - Run in a V8 interpreter for "native" targets
-//! - Run in the WASM host interpreter for "wasm32" targets
+//! - Run in a QuickJS interpreter for "native" targets built without the default `v8` feature
+//!   and with the `quickjs` feature
+//! - Run in a Boa interpreter with the `boa` feature, for "wasm32-wasi" targets and for "native"
+//!   targets built without the `v8` and `quickjs` features
+//! - Run in the WASM host interpreter for the other "wasm32" targets
+//!
+//! `platform` is the engine picked when building, `backend` picks one at runtime instead.
 //!
 //! ## Example
 //!
@@ -30,12 +36,64 @@
 //! assert_eq!(res, ScriptValue::String("Hello Rust! (from JS...)".to_string()));
 //! ```
 
+pub mod api;
+pub mod backend;
 pub mod core;
 
+#[cfg(feature = "derive")]
+pub use scriptit_derive::{class, function};
+
 #[cfg(not(target_arch = "wasm32"))]
+pub mod pool;
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "repl"))]
+pub mod repl;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod threaded;
+
+#[cfg(feature = "typescript")]
+pub mod typescript;
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "v8"))]
 #[path = "js_v8/mod.rs"]
 pub mod platform;
 
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(not(target_arch = "wasm32"), feature = "quickjs"))]
+#[path = "js_quickjs/mod.rs"]
+pub mod quickjs;
+
+#[cfg(all(not(target_arch = "wasm32"), not(feature = "v8"), feature = "quickjs"))]
+pub use quickjs as platform;
+
+#[cfg(feature = "boa")]
+#[path = "js_boa/mod.rs"]
+pub mod boa;
+
+#[cfg(all(
+    feature = "boa",
+    any(
+        target_os = "wasi",
+        all(
+            not(target_arch = "wasm32"),
+            not(feature = "v8"),
+            not(feature = "quickjs")
+        )
+    )
+))]
+pub use boa as platform;
+
+#[cfg(all(
+    not(target_arch = "wasm32"),
+    not(feature = "v8"),
+    not(feature = "quickjs"),
+    not(feature = "boa")
+))]
+compile_error!("scriptit needs the `v8`, `quickjs` or `boa` feature on native targets");
+
+#[cfg(all(target_os = "wasi", not(feature = "boa")))]
+compile_error!("scriptit needs the `boa` feature on wasm32-wasi");
+
+#[cfg(all(target_arch = "wasm32", not(target_os = "wasi")))]
 #[path = "js_wasm/mod.rs"]
 pub mod platform;
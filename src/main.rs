@@ -0,0 +1,296 @@
+//! `scriptit`, runs scripts from the command line in a `PlatformScriptingEnvironment`
+//!
+//! Capabilities are not installed unless asked for, like in applications embedding scriptit: this
+//! is a way to try scripts out before deploying them. See `scriptit --help`.
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    std::process::exit(cli::run(&args));
+}
+
+// Scripts run from the host engine on wasm: there is no command line to parse
+#[cfg(target_arch = "wasm32")]
+fn main() {}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod cli {
+    use scriptit::{
+        api::{
+            console::{self, ConsoleLevel},
+            crypto,
+            fs::{self, FsMode},
+            web,
+        },
+        core::{error::ScriptError, ScriptingEnvironment},
+        platform::PlatformScriptingEnvironment,
+    };
+    use std::{
+        io::{self, Read},
+        thread,
+        time::Duration,
+    };
+
+    const USAGE: &str = "\
+Usage: scriptit [OPTIONS] [FILE]
+
+Runs FILE (`-` for stdin) as a script, then prints the value of the --eval expression as JSON.
+With --interactive, a REPL starts afterwards in the same environment.
+ES module syntax (`import`, `export`) is not supported: FILE runs as a classic script.
+
+Options:
+  -e, --eval <EXPR>      Evaluates EXPR after FILE and prints its value as JSON
+  -i, --interactive      Starts a REPL once done (needs the `repl` feature)
+      --console          Installs `console`, writing to stdout (and stderr for warnings and errors)
+      --fs-root <DIR>    Installs `ScriptIt.fs` confined to DIR, read-only
+      --fs-write         Lets `ScriptIt.fs` write in the --fs-root directory
+      --web              Installs the web-platform globals (`URL`, `TextEncoder`...)
+      --crypto           Installs `crypto`
+      --timers           Runs the event loop after FILE: timers fire and promises settle
+      --timeout <MS>     Stops when running takes longer than MS milliseconds
+      --heap-limit <MB>  Limits the heap of the engine to MB megabytes (V8 and QuickJS only)
+  -h, --help             Prints this help
+
+Exit codes:
+  0  Success
+  1  Runtime error
+  2  Usage error
+  3  Compile error
+  4  Serialization error (the --eval value is not JSON-serializable)
+  5  Cast error
+  6  Timeout
+  7  I/O error (FILE or --fs-root could not be read)
+";
+
+    const EXIT_SUCCESS: i32 = 0;
+    const EXIT_RUNTIME_ERROR: i32 = 1;
+    const EXIT_USAGE: i32 = 2;
+    const EXIT_COMPILE_ERROR: i32 = 3;
+    const EXIT_SERIALIZATION_ERROR: i32 = 4;
+    const EXIT_CAST_ERROR: i32 = 5;
+    const EXIT_TIMEOUT: i32 = 6;
+    const EXIT_IO_ERROR: i32 = 7;
+
+    #[derive(Default)]
+    struct Options {
+        file: Option<String>,
+        eval: Option<String>,
+        interactive: bool,
+        console: bool,
+        fs_root: Option<String>,
+        fs_write: bool,
+        web: bool,
+        crypto: bool,
+        timers: bool,
+        timeout: Option<u64>,
+        heap_limit: Option<usize>,
+        help: bool,
+    }
+
+    fn parse_args(args: &[String]) -> Result<Options, String> {
+        let mut options = Options::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            // `--flag=value` and `--flag value` are both accepted
+            let (flag, inline_value) = match arg.find('=') {
+                Some(index) if arg.starts_with("--") => (&arg[..index], Some(&arg[index + 1..])),
+                _ => (arg.as_str(), None),
+            };
+            let mut value = |name: &str| -> Result<String, String> {
+                match inline_value {
+                    Some(value) => Ok(value.to_string()),
+                    None => args
+                        .next()
+                        .cloned()
+                        .ok_or_else(|| format!("{} expects a value", name)),
+                }
+            };
+            let number = |name: &str, value: String| -> Result<u64, String> {
+                value
+                    .parse()
+                    .map_err(|_| format!("{} expects a number, got `{}`", name, value))
+            };
+            match flag {
+                "-e" | "--eval" => options.eval = Some(value(flag)?),
+                "-i" | "--interactive" => options.interactive = true,
+                "--console" => options.console = true,
+                "--fs-root" => options.fs_root = Some(value(flag)?),
+                "--fs-write" => options.fs_write = true,
+                "--web" => options.web = true,
+                "--crypto" => options.crypto = true,
+                "--timers" => options.timers = true,
+                "--timeout" => options.timeout = Some(number(flag, value(flag)?)?),
+                "--heap-limit" => options.heap_limit = Some(number(flag, value(flag)?)? as usize),
+                "-h" | "--help" => options.help = true,
+                _ if flag.starts_with('-') && flag != "-" => {
+                    return Err(format!("Unknown option: {}", flag))
+                }
+                _ if options.file.is_some() => return Err(format!("Unexpected argument: {}", arg)),
+                _ => options.file = Some(arg.clone()),
+            }
+        }
+        if options.fs_write && options.fs_root.is_none() {
+            return Err(String::from("--fs-write needs --fs-root"));
+        }
+        if options.interactive && cfg!(not(feature = "repl")) {
+            return Err(String::from("--interactive needs the `repl` feature"));
+        }
+        let has_input = options.file.is_some() || options.eval.is_some() || options.interactive;
+        if !options.help && !has_input {
+            return Err(String::from(
+                "Nothing to run: give a FILE, an --eval expression or --interactive",
+            ));
+        }
+        Ok(options)
+    }
+
+    fn exit_code(error: &ScriptError) -> i32 {
+        match error {
+            ScriptError::CastError { .. } => EXIT_CAST_ERROR,
+            ScriptError::SerializationError(_) => EXIT_SERIALIZATION_ERROR,
+            ScriptError::CompileError(_) => EXIT_COMPILE_ERROR,
+            ScriptError::RuntimeError(_) => EXIT_RUNTIME_ERROR,
+        }
+    }
+
+    #[cfg(feature = "v8")]
+    fn new_environment(heap_limit: Option<usize>) -> Result<PlatformScriptingEnvironment, String> {
+        if let Some(megabytes) = heap_limit {
+            PlatformScriptingEnvironment::set_heap_limit(megabytes);
+        }
+        Ok(PlatformScriptingEnvironment::new())
+    }
+
+    #[cfg(all(not(feature = "v8"), feature = "quickjs"))]
+    fn new_environment(heap_limit: Option<usize>) -> Result<PlatformScriptingEnvironment, String> {
+        let mut s_env = PlatformScriptingEnvironment::new();
+        if let Some(megabytes) = heap_limit {
+            let bytes = megabytes
+                .checked_mul(1024 * 1024)
+                .ok_or_else(|| format!("--heap-limit {} is too large", megabytes))?;
+            s_env.set_memory_limit(bytes);
+        }
+        Ok(s_env)
+    }
+
+    #[cfg(all(not(feature = "v8"), not(feature = "quickjs")))]
+    fn new_environment(heap_limit: Option<usize>) -> Result<PlatformScriptingEnvironment, String> {
+        match heap_limit {
+            Some(_) => Err(String::from("--heap-limit is not supported by this engine")),
+            None => Ok(PlatformScriptingEnvironment::new()),
+        }
+    }
+
+    fn read_source(file: &str) -> io::Result<String> {
+        if file == "-" {
+            let mut source = String::new();
+            io::stdin().read_to_string(&mut source)?;
+            Ok(source)
+        } else {
+            std::fs::read_to_string(file)
+        }
+    }
+
+    fn install(s_env: &mut PlatformScriptingEnvironment, options: &Options) -> Result<(), i32> {
+        if options.console {
+            console::install(s_env, |level: ConsoleLevel, message: &str| match level {
+                ConsoleLevel::Warn | ConsoleLevel::Error => eprintln!("{}", message),
+                _ => println!("{}", message),
+            })
+            .map_err(|e| report(&e))?;
+        }
+        if let Some(root) = &options.fs_root {
+            let mode = if options.fs_write {
+                FsMode::ReadWrite
+            } else {
+                FsMode::ReadOnly
+            };
+            fs::install(s_env, root, mode).map_err(|e| {
+                eprintln!("scriptit: --fs-root {}: {}", root, e);
+                EXIT_IO_ERROR
+            })?;
+        }
+        if options.web {
+            web::install(s_env).map_err(|e| report(&e))?;
+        }
+        if options.crypto {
+            crypto::install(s_env).map_err(|e| report(&e))?;
+        }
+        Ok(())
+    }
+
+    fn report(error: &ScriptError) -> i32 {
+        eprintln!("{}", error);
+        exit_code(error)
+    }
+
+    fn run_options(options: &Options) -> Result<(), i32> {
+        let source = match &options.file {
+            Some(file) => Some(read_source(file).map_err(|e| {
+                eprintln!("scriptit: {}: {}", file, e);
+                EXIT_IO_ERROR
+            })?),
+            None => None,
+        };
+
+        if let Some(timeout) = options.timeout {
+            // Scripts can't be interrupted: the whole process stops
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(timeout));
+                eprintln!("scriptit: timed out after {}ms", timeout);
+                std::process::exit(EXIT_TIMEOUT);
+            });
+        }
+
+        let mut s_env = new_environment(options.heap_limit).map_err(|e| {
+            eprintln!("scriptit: {}", e);
+            EXIT_USAGE
+        })?;
+        install(&mut s_env, options)?;
+
+        if let (Some(file), Some(source)) = (&options.file, &source) {
+            let script_name = if file == "-" { "<stdin>" } else { file };
+            s_env
+                .run_named(source, script_name)
+                .map_err(|e| report(&e))?;
+        }
+        if options.timers {
+            s_env.run_event_loop().map_err(|e| report(&e))?;
+        }
+        if let Some(expression) = &options.eval {
+            let value = s_env
+                .eval_expression_named(expression, "<eval>")
+                .map_err(|e| report(&e))?;
+            println!("{}", value);
+        }
+        #[cfg(feature = "repl")]
+        {
+            if options.interactive {
+                scriptit::repl::Repl::new().run(&mut s_env).map_err(|e| {
+                    eprintln!("scriptit: {}", e);
+                    EXIT_IO_ERROR
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the command line `args`, returns the exit code
+    pub fn run(args: &[String]) -> i32 {
+        let options = match parse_args(args) {
+            Ok(options) => options,
+            Err(message) => {
+                eprintln!("scriptit: {}\n\n{}", message, USAGE);
+                return EXIT_USAGE;
+            }
+        };
+        if options.help {
+            print!("{}", USAGE);
+            return EXIT_SUCCESS;
+        }
+        match run_options(&options) {
+            Ok(()) => EXIT_SUCCESS,
+            Err(code) => code,
+        }
+    }
+}
@@ -0,0 +1,275 @@
+//! A pool of scripting environments to run scripts concurrently
+//!
+//! ```
+//! use scriptit::{
+//!     core::{value::ScriptValue, ScriptingEnvironment},
+//!     pool::ScriptingPool,
+//! };
+//!
+//! let pool = ScriptingPool::builder()
+//!     .size(2)
+//!     .setup(|s_env| {
+//!         s_env.register_func("answer", Box::new(|_| Ok(ScriptValue::from(42))));
+//!     })
+//!     .build();
+//!
+//! let s_env = pool.checkout();
+//! let res = s_env.eval_expression("ScriptIt.funcs.answer()").unwrap();
+//! assert_eq!(res, ScriptValue::from(42));
+//! ```
+
+use crate::{
+    core::{error::ScriptError, value::ScriptValue},
+    platform::PlatformScriptingEnvironment,
+    threaded::{JobFuture, ThreadedScriptingEnvironment},
+};
+use std::{
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    task::{Context, Poll, Waker},
+};
+
+type Setup = Arc<dyn Fn(&mut PlatformScriptingEnvironment) + Send + Sync>;
+
+struct PoolState {
+    idle: Vec<ThreadedScriptingEnvironment>,
+    waiters: VecDeque<Waker>,
+}
+
+struct PoolInner {
+    setup: Setup,
+    reset_on_return: bool,
+    state: Mutex<PoolState>,
+    available: Condvar,
+}
+
+impl PoolInner {
+    fn new_environment(&self) -> ThreadedScriptingEnvironment {
+        let setup = Arc::clone(&self.setup);
+        ThreadedScriptingEnvironment::with_setup(move |s_env| setup(s_env))
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, PoolState> {
+        // A poisoned state is still consistent: it only holds idle environments and wakers
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn checkin(&self, s_env: ThreadedScriptingEnvironment) {
+        let s_env = if self.reset_on_return {
+            self.new_environment()
+        } else {
+            s_env
+        };
+        let mut state = self.lock_state();
+        state.idle.push(s_env);
+        // Waiting checkouts may have been dropped: wake them all, the first one polled wins
+        for waker in state.waiters.drain(..) {
+            waker.wake();
+        }
+        self.available.notify_one();
+    }
+}
+
+/// Builds a `ScriptingPool`
+pub struct ScriptingPoolBuilder {
+    size: usize,
+    reset_on_return: bool,
+    setup: Setup,
+}
+
+impl ScriptingPoolBuilder {
+    /// Number of environments, and threads, in the pool (defaults to 1)
+    ///
+    /// `build` panics if the size is 0: checkouts would wait forever.
+    pub fn size(mut self, size: usize) -> ScriptingPoolBuilder {
+        self.size = size;
+        self
+    }
+
+    /// Replaces environments by fresh ones when they are returned to the pool (defaults to false)
+    ///
+    /// Fresh environments are pre-warmed in the background so checkouts don't wait on them.
+    pub fn reset_on_return(mut self, reset_on_return: bool) -> ScriptingPoolBuilder {
+        self.reset_on_return = reset_on_return;
+        self
+    }
+
+    /// Registration spec applied to every environment of the pool, from its own thread
+    pub fn setup<F>(mut self, setup: F) -> ScriptingPoolBuilder
+    where
+        F: Fn(&mut PlatformScriptingEnvironment) + Send + Sync + 'static,
+    {
+        self.setup = Arc::new(setup);
+        self
+    }
+
+    pub fn build(self) -> ScriptingPool {
+        assert!(
+            self.size > 0,
+            "A scripting pool needs at least one environment"
+        );
+        let inner = Arc::new(PoolInner {
+            setup: self.setup,
+            reset_on_return: self.reset_on_return,
+            state: Mutex::new(PoolState {
+                idle: Vec::with_capacity(self.size),
+                waiters: VecDeque::new(),
+            }),
+            available: Condvar::new(),
+        });
+        let environments: Vec<_> = (0..self.size).map(|_| inner.new_environment()).collect();
+        inner.lock_state().idle = environments;
+        ScriptingPool { inner }
+    }
+}
+
+/// A pool of pre-warmed environments, each living on its own thread
+///
+/// Cloning the pool gives another handle to the same environments.
+#[derive(Clone)]
+pub struct ScriptingPool {
+    inner: Arc<PoolInner>,
+}
+
+/// An environment checked out from a `ScriptingPool`, it is returned to the pool when dropped
+pub struct PooledEnvironment {
+    s_env: Option<ThreadedScriptingEnvironment>,
+    pool: Arc<PoolInner>,
+}
+
+impl PooledEnvironment {
+    fn s_env(&self) -> &ThreadedScriptingEnvironment {
+        self.s_env.as_ref().unwrap()
+    }
+
+    /// Runs `job` on the scripting thread and waits for its result
+    pub fn execute<F, R>(&self, job: F) -> Result<R, ScriptError>
+    where
+        F: FnOnce(&mut PlatformScriptingEnvironment) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        self.s_env().execute(job)
+    }
+
+    /// Runs `job` on the scripting thread without blocking, the returned future resolves to its result
+    pub fn execute_async<F, R>(&self, job: F) -> JobFuture<R>
+    where
+        F: FnOnce(&mut PlatformScriptingEnvironment) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        self.s_env().execute_async(job)
+    }
+
+    /// Evaluates a single JS expression
+    pub fn eval_expression(&self, source: &str) -> Result<ScriptValue, ScriptError> {
+        self.s_env().eval_expression(source)
+    }
+
+    /// Evaluates a single JS expression, `script_name` will be used in errors and stack traces
+    pub fn eval_expression_named(
+        &self,
+        source: &str,
+        script_name: &str,
+    ) -> Result<ScriptValue, ScriptError> {
+        self.s_env().eval_expression_named(source, script_name)
+    }
+
+    /// Runs JavaScript code
+    pub fn run(&self, source: &str) -> Result<(), ScriptError> {
+        self.s_env().run(source)
+    }
+
+    /// Runs JavaScript code, `script_name` will be used in errors and stack traces
+    pub fn run_named(&self, source: &str, script_name: &str) -> Result<(), ScriptError> {
+        self.s_env().run_named(source, script_name)
+    }
+
+    /// Calls the JS function found at `function_path` (`myFunc`, `ScriptIt.funcs.greet`...) with `args`
+    pub fn call_function(
+        &self,
+        function_path: &str,
+        args: Vec<ScriptValue>,
+    ) -> Result<ScriptValue, ScriptError> {
+        self.s_env().call_function(function_path, args)
+    }
+}
+
+impl Drop for PooledEnvironment {
+    fn drop(&mut self) {
+        if let Some(s_env) = self.s_env.take() {
+            self.pool.checkin(s_env);
+        }
+    }
+}
+
+/// Waits for an environment of the pool to become available
+pub struct Checkout {
+    pool: Arc<PoolInner>,
+}
+
+impl Future for Checkout {
+    type Output = PooledEnvironment;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<PooledEnvironment> {
+        let mut state = self.pool.lock_state();
+        match state.idle.pop() {
+            Some(s_env) => Poll::Ready(PooledEnvironment {
+                s_env: Some(s_env),
+                pool: Arc::clone(&self.pool),
+            }),
+            None => {
+                state.waiters.push_back(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl ScriptingPool {
+    pub fn builder() -> ScriptingPoolBuilder {
+        ScriptingPoolBuilder {
+            size: 1,
+            reset_on_return: false,
+            setup: Arc::new(|_| {}),
+        }
+    }
+
+    /// Checks out an environment, blocking until one is available
+    pub fn checkout(&self) -> PooledEnvironment {
+        let mut state = self.inner.lock_state();
+        loop {
+            if let Some(s_env) = state.idle.pop() {
+                return PooledEnvironment {
+                    s_env: Some(s_env),
+                    pool: Arc::clone(&self.inner),
+                };
+            }
+            state = self
+                .inner
+                .available
+                .wait(state)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    /// Checks out an environment without blocking the async executor
+    pub fn checkout_async(&self) -> Checkout {
+        Checkout {
+            pool: Arc::clone(&self.inner),
+        }
+    }
+
+    /// Runs `job` on the first available environment, then returns it to the pool
+    pub async fn execute<F, R>(&self, job: F) -> Result<R, ScriptError>
+    where
+        F: FnOnce(&mut PlatformScriptingEnvironment) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let s_env = self.checkout_async().await;
+        s_env.execute_async(job).await
+    }
+}
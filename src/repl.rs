@@ -0,0 +1,395 @@
+//! A read-eval-print loop hosts can embed in their command-line tools
+//!
+//! `Repl` edits lines with rustyline: history, multi-line input and tab completion from the
+//! globals of the environment (`ScriptIt.funcs.` completes registered functions). The evaluation
+//! itself is done by a `ReplSession`, usable on its own with another line editor.
+//!
+//! ```no_run
+//! use scriptit::{
+//!     core::{value::ScriptValue, ScriptingEnvironment},
+//!     platform::PlatformScriptingEnvironment,
+//!     repl::Repl,
+//! };
+//!
+//! let mut s_env = PlatformScriptingEnvironment::new();
+//! s_env.register_func("answer", Box::new(|_| Ok(ScriptValue::from(42))));
+//! Repl::new().prompt("app> ").run(&mut s_env).unwrap();
+//! ```
+
+use crate::core::{error::ScriptError, value::ScriptValue, ScriptingEnvironment};
+use rustyline::{
+    completion::Completer,
+    error::ReadlineError,
+    highlight::Highlighter,
+    hint::Hinter,
+    history::DefaultHistory,
+    validate::{ValidationContext, ValidationResult, Validator},
+    Editor, Helper,
+};
+use std::{
+    cell::RefCell,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Compile error messages of V8, QuickJS and Boa meaning that the input stopped too early
+const END_OF_INPUT_ERRORS: [&str; 5] = [
+    "end of input",
+    "unexpected end",
+    "abrupt end",
+    "unterminated",
+    // QuickJS names the token it got: there is none at the end of the input
+    "unexpected token in expression: ''",
+];
+
+/// Describes the value of an expression in JSON, for the ones JSON can't hold as well
+const DESCRIBE_SOURCE: &str = r#"(($value) => {
+    switch (typeof $value) {
+        case "undefined":
+            return { repr: "undefined" };
+        case "function":
+            return { repr: `[Function: ${$value.name || "(anonymous)"}]` };
+        case "symbol":
+            return { repr: $value.toString() };
+        case "bigint":
+            return { repr: `${$value}n` };
+    }
+    if ($value instanceof Error) {
+        return { repr: String($value) };
+    }
+    try {
+        JSON.stringify($value);
+        return { value: $value };
+    } catch (e) {
+        return { repr: String($value) };
+    }
+})"#;
+
+/// Describes the property names `PATH` has, its prototypes included
+const PROPERTIES_SOURCE: &str = r#"(() => {
+    try {
+        const names = new Set();
+        for (let obj = PATH; obj !== null && obj !== undefined; obj = Object.getPrototypeOf(obj)) {
+            Object.getOwnPropertyNames(obj).forEach((name) => names.add(name));
+        }
+        return Array.from(names);
+    } catch (e) {
+        return [];
+    }
+})()"#;
+
+/// What became of some input
+#[derive(Debug, PartialEq)]
+pub enum Evaluation {
+    /// The input stops too early, like in the middle of a block: it needs more lines
+    Incomplete,
+    /// The input was an expression, here is its pretty-printed value
+    Value(String),
+    /// The input was made of statements, they ran
+    Done,
+}
+
+/// Evaluates REPL input in a scripting environment, without any line editing
+pub struct ReplSession<'a> {
+    s_env: &'a mut dyn ScriptingEnvironment,
+}
+
+impl<'a> ReplSession<'a> {
+    pub fn new(s_env: &'a mut dyn ScriptingEnvironment) -> ReplSession<'a> {
+        ReplSession { s_env }
+    }
+
+    /// Evaluates `input` as an expression, or runs it as statements when it isn't one
+    pub fn eval(&mut self, input: &str) -> Result<Evaluation, ScriptError> {
+        if !is_declaration(input) {
+            // The describer takes a single line so that errors point at the right line of `input`,
+            // the line break keeps a trailing `//` comment from swallowing the closing parenthesis
+            let describe: Vec<&str> = DESCRIBE_SOURCE.lines().map(str::trim).collect();
+            let described = format!("{}(({}\n))", describe.join(" "), input);
+            if let Ok(script) = self.s_env.compile_expression_named(&described, "<repl>") {
+                let description = self.s_env.run_compiled(&script);
+                self.s_env.release_compiled(script);
+                return Ok(Evaluation::Value(pretty_print(&description?)));
+            }
+        }
+        match self.s_env.compile_named(input, "<repl>") {
+            Ok(script) => {
+                let result = self.s_env.run_compiled(&script);
+                self.s_env.release_compiled(script);
+                result.map(|_| Evaluation::Done)
+            }
+            Err(ScriptError::CompileError(message)) if is_incomplete(input, &message) => {
+                Ok(Evaluation::Incomplete)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Whether `input` stops too early to be compiled
+    pub fn is_incomplete(&mut self, input: &str) -> bool {
+        match self.s_env.compile_named(input, "<repl>") {
+            Ok(script) => {
+                self.s_env.release_compiled(script);
+                false
+            }
+            Err(ScriptError::CompileError(message)) => is_incomplete(input, &message),
+            Err(_) => false,
+        }
+    }
+
+    /// Completes the property path ending at `pos` in `line`
+    ///
+    /// Returns where the completed word starts and the candidates, sorted. Only paths made of
+    /// identifiers (`ScriptIt.funcs.ans`) are completed: nothing else gets evaluated.
+    pub fn complete(&mut self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let before = &line[..pos];
+        let start = before
+            .rfind(|c: char| !(is_identifier_char(c) || c == '.'))
+            .map_or(0, |index| {
+                index + before[index..].chars().next().unwrap().len_utf8()
+            });
+        let path = &before[start..];
+        let (object, prefix) = match path.rfind('.') {
+            Some(index) => (&path[..index], &path[index + 1..]),
+            None => ("globalThis", path),
+        };
+        let is_identifier = |name: &str| {
+            !name.is_empty()
+                && !name.starts_with(|c: char| c.is_ascii_digit())
+                && name.chars().all(is_identifier_char)
+        };
+        if !object.split('.').all(is_identifier) {
+            return (pos, Vec::new());
+        }
+        let source = PROPERTIES_SOURCE.replace("PATH", object);
+        let names = match self.s_env.eval_expression_named(&source, "<repl>") {
+            Ok(ScriptValue::Array(names)) => names,
+            _ => return (pos, Vec::new()),
+        };
+        let mut candidates: Vec<String> = names
+            .iter()
+            .filter_map(|name| name.as_str())
+            .filter(|name| name.starts_with(prefix) && is_identifier(name))
+            .map(String::from)
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        (pos - prefix.len(), candidates)
+    }
+}
+
+/// Whether `input` declares a function or a class, which would be an expression in parentheses
+fn is_declaration(input: &str) -> bool {
+    let input = input.trim_start();
+    let input = input
+        .strip_prefix("async")
+        .filter(|rest| rest.starts_with(char::is_whitespace))
+        .map_or(input, str::trim_start);
+    ["function", "class"].iter().any(|keyword| {
+        input.starts_with(keyword)
+            && !input[keyword.len()..].starts_with(|c: char| is_identifier_char(c))
+    })
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+fn pretty_print(description: &ScriptValue) -> String {
+    match (description.get("repr"), description.get("value")) {
+        (Some(ScriptValue::String(repr)), _) => repr.clone(),
+        (_, Some(value)) => {
+            serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+        }
+        _ => description.to_string(),
+    }
+}
+
+fn is_incomplete(input: &str, compile_error: &str) -> bool {
+    let compile_error = compile_error.to_lowercase();
+    END_OF_INPUT_ERRORS
+        .iter()
+        .any(|message| compile_error.contains(message))
+        || has_unclosed_delimiters(input)
+}
+
+/// Whether brackets, template literals or comments are left open at the end of `source`
+///
+/// Quotes don't count: strings can't span several lines. Regular expressions are not told apart
+/// from divisions, which can only make an invalid input look incomplete.
+fn has_unclosed_delimiters(source: &str) -> bool {
+    // `` ` `` for template literals, `$` for the expressions in them
+    let mut open: Vec<char> = Vec::new();
+    let mut chars = source.chars().peekable();
+    let mut quote: Option<char> = None;
+    let mut in_line_comment = false;
+    let mut in_block_comment = false;
+    while let Some(c) = chars.next() {
+        if in_line_comment {
+            in_line_comment = c != '\n';
+        } else if in_block_comment {
+            if c == '*' && chars.peek() == Some(&'/') {
+                chars.next();
+                in_block_comment = false;
+            }
+        } else if let Some(q) = quote {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '\n' => quote = None,
+                _ if c == q => quote = None,
+                _ => {}
+            }
+        } else if open.last() == Some(&'`') {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '`' => {
+                    open.pop();
+                }
+                '$' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    open.push('$');
+                }
+                _ => {}
+            }
+        } else {
+            match c {
+                '/' if chars.peek() == Some(&'/') => in_line_comment = true,
+                '/' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    in_block_comment = true;
+                }
+                '\'' | '"' => quote = Some(c),
+                '`' | '(' | '[' | '{' => open.push(c),
+                ')' | ']' | '}' => {
+                    let opener = match c {
+                        ')' => '(',
+                        ']' => '[',
+                        _ if open.last() == Some(&'$') => '$',
+                        _ => '{',
+                    };
+                    if open.last() == Some(&opener) {
+                        open.pop();
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    in_block_comment || !open.is_empty()
+}
+
+struct ReplHelper<'s, 'a> {
+    session: &'s RefCell<ReplSession<'a>>,
+}
+
+impl Completer for ReplHelper<'_, '_> {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.session.borrow_mut().complete(line, pos))
+    }
+}
+
+impl Validator for ReplHelper<'_, '_> {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        if self.session.borrow_mut().is_incomplete(ctx.input()) {
+            Ok(ValidationResult::Incomplete)
+        } else {
+            Ok(ValidationResult::Valid(None))
+        }
+    }
+}
+
+impl Hinter for ReplHelper<'_, '_> {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper<'_, '_> {}
+
+impl Helper for ReplHelper<'_, '_> {}
+
+fn readline_to_io_error(error: ReadlineError) -> io::Error {
+    match error {
+        ReadlineError::Io(error) => error,
+        error => io::Error::other(error),
+    }
+}
+
+/// An interactive loop on the terminal: values get printed on stdout, errors on stderr
+pub struct Repl {
+    prompt: String,
+    history_file: Option<PathBuf>,
+}
+
+impl Repl {
+    pub fn new() -> Repl {
+        Repl {
+            prompt: String::from("> "),
+            history_file: None,
+        }
+    }
+
+    /// Sets the prompt, `> ` by default
+    pub fn prompt(mut self, prompt: &str) -> Repl {
+        self.prompt = String::from(prompt);
+        self
+    }
+
+    /// Loads the history from `path` when starting, and saves it there when done
+    pub fn history_file<P: AsRef<Path>>(mut self, path: P) -> Repl {
+        self.history_file = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Reads and evaluates input until Ctrl-D, Ctrl-C drops the input being edited
+    pub fn run(&self, s_env: &mut dyn ScriptingEnvironment) -> io::Result<()> {
+        let session = RefCell::new(ReplSession::new(s_env));
+        let mut editor: Editor<ReplHelper, DefaultHistory> =
+            Editor::new().map_err(readline_to_io_error)?;
+        editor.set_helper(Some(ReplHelper { session: &session }));
+        if let Some(path) = &self.history_file {
+            // There is no history the first time
+            let _ = editor.load_history(path);
+        }
+        loop {
+            let input = match editor.readline(&self.prompt) {
+                Ok(input) => input,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(error) => return Err(readline_to_io_error(error)),
+            };
+            if input.trim().is_empty() {
+                continue;
+            }
+            editor
+                .add_history_entry(input.as_str())
+                .map_err(readline_to_io_error)?;
+            match session.borrow_mut().eval(&input) {
+                Ok(Evaluation::Value(value)) => println!("{}", value),
+                Ok(Evaluation::Done) => {}
+                // Validation only lets complete input through, unless the engines disagree
+                Ok(Evaluation::Incomplete) => eprintln!("Unexpected end of input"),
+                Err(error) => eprintln!("{}", error),
+            }
+        }
+        if let Some(path) = &self.history_file {
+            editor.save_history(path).map_err(readline_to_io_error)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Repl::new()
+    }
+}
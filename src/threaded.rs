@@ -0,0 +1,229 @@
+//! A scripting environment that can be shared between threads
+//!
+//! Scripting environments and their handlers are not `Send`: `ThreadedScriptingEnvironment` owns a
+//! `PlatformScriptingEnvironment` on a dedicated thread and forwards calls to it over channels.
+//!
+//! ```
+//! use scriptit::{
+//!     core::value::ScriptValue,
+//!     threaded::ThreadedScriptingEnvironment,
+//! };
+//!
+//! let s_env = ThreadedScriptingEnvironment::new();
+//! s_env.register_func("greet", Box::new(|args| {
+//!     let name = args.get(0).unwrap().as_str().unwrap();
+//!     Ok(ScriptValue::String(format!("Hello {}!", name)))
+//! })).unwrap();
+//!
+//! let handle = s_env.clone();
+//! let res = std::thread::spawn(move || {
+//!     handle.eval_expression("ScriptIt.funcs.greet('thread')")
+//! }).join().unwrap().unwrap();
+//!
+//! assert_eq!(res, ScriptValue::String("Hello thread!".to_string()));
+//! ```
+
+use crate::{
+    core::{error::ScriptError, value::ScriptValue, ScriptingEnvironment},
+    platform::PlatformScriptingEnvironment,
+};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        mpsc::{channel, Sender},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+    thread,
+};
+
+type Job = Box<dyn FnOnce(&mut PlatformScriptingEnvironment) + Send>;
+/// A function handler that can be sent to the scripting thread
+pub type SendFuncHandler =
+    Box<dyn FnMut(&Vec<ScriptValue>) -> Result<ScriptValue, ScriptError> + Send>;
+
+/// A `Send + Sync + Clone` handle to a `PlatformScriptingEnvironment` living on its own thread
+///
+/// Every call blocks the calling thread until the scripting thread is done with it. The scripting
+/// thread stops once every handle is dropped.
+#[derive(Clone)]
+pub struct ThreadedScriptingEnvironment {
+    jobs: Arc<Mutex<Sender<Job>>>,
+}
+
+fn thread_stopped() -> ScriptError {
+    ScriptError::RuntimeError(String::from("The scripting thread has stopped"))
+}
+
+struct JobState<R> {
+    result: Option<Result<R, ScriptError>>,
+    waker: Option<Waker>,
+}
+
+/// Completes a `JobFuture`, with an error if dropped before completing (the job was never run)
+struct JobCompletion<R> {
+    state: Arc<Mutex<JobState<R>>>,
+}
+
+impl<R> JobCompletion<R> {
+    fn complete(&self, result: Result<R, ScriptError>) {
+        if let Ok(mut state) = self.state.lock() {
+            if state.result.is_none() {
+                state.result = Some(result);
+                if let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
+            }
+        }
+    }
+}
+
+impl<R> Drop for JobCompletion<R> {
+    fn drop(&mut self) {
+        self.complete(Err(thread_stopped()));
+    }
+}
+
+/// Result of a job sent to the scripting thread with `execute_async`
+pub struct JobFuture<R> {
+    state: Arc<Mutex<JobState<R>>>,
+}
+
+impl<R> Future for JobFuture<R> {
+    type Output = Result<R, ScriptError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(_) => return Poll::Ready(Err(thread_stopped())),
+        };
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl ThreadedScriptingEnvironment {
+    pub fn new() -> ThreadedScriptingEnvironment {
+        ThreadedScriptingEnvironment::with_setup(|_| {})
+    }
+
+    /// Creates the environment and runs `setup` on it, from the scripting thread
+    ///
+    /// This is where handlers that are not `Send` can get registered.
+    pub fn with_setup<F>(setup: F) -> ThreadedScriptingEnvironment
+    where
+        F: FnOnce(&mut PlatformScriptingEnvironment) + Send + 'static,
+    {
+        let (jobs, incoming_jobs) = channel::<Job>();
+        thread::spawn(move || {
+            let mut s_env = PlatformScriptingEnvironment::new();
+            setup(&mut s_env);
+            for job in incoming_jobs {
+                job(&mut s_env);
+            }
+        });
+        ThreadedScriptingEnvironment {
+            jobs: Arc::new(Mutex::new(jobs)),
+        }
+    }
+
+    /// Runs `job` on the scripting thread and waits for its result
+    pub fn execute<F, R>(&self, job: F) -> Result<R, ScriptError>
+    where
+        F: FnOnce(&mut PlatformScriptingEnvironment) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (result_sender, result) = channel();
+        self.jobs
+            .lock()
+            .map_err(|_| thread_stopped())?
+            .send(Box::new(move |s_env| {
+                let _ = result_sender.send(job(s_env));
+            }))
+            .map_err(|_| thread_stopped())?;
+        result.recv().map_err(|_| thread_stopped())
+    }
+
+    /// Runs `job` on the scripting thread without blocking, the returned future resolves to its result
+    pub fn execute_async<F, R>(&self, job: F) -> JobFuture<R>
+    where
+        F: FnOnce(&mut PlatformScriptingEnvironment) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let state = Arc::new(Mutex::new(JobState {
+            result: None,
+            waker: None,
+        }));
+        let completion = JobCompletion {
+            state: Arc::clone(&state),
+        };
+        // If sending fails, the job and its completion get dropped right away: the future errors
+        if let Ok(jobs) = self.jobs.lock() {
+            let _ = jobs.send(Box::new(move |s_env| completion.complete(Ok(job(s_env)))));
+        }
+        JobFuture { state }
+    }
+
+    /// Evaluates a single JS expression
+    pub fn eval_expression(&self, source: &str) -> Result<ScriptValue, ScriptError> {
+        let source = source.to_string();
+        self.execute(move |s_env| s_env.eval_expression(&source))?
+    }
+
+    /// Evaluates a single JS expression, `script_name` will be used in errors and stack traces
+    pub fn eval_expression_named(
+        &self,
+        source: &str,
+        script_name: &str,
+    ) -> Result<ScriptValue, ScriptError> {
+        let source = source.to_string();
+        let script_name = script_name.to_string();
+        self.execute(move |s_env| s_env.eval_expression_named(&source, &script_name))?
+    }
+
+    /// Runs JavaScript code
+    pub fn run(&self, source: &str) -> Result<(), ScriptError> {
+        let source = source.to_string();
+        self.execute(move |s_env| s_env.run(&source))?
+    }
+
+    /// Runs JavaScript code, `script_name` will be used in errors and stack traces
+    pub fn run_named(&self, source: &str, script_name: &str) -> Result<(), ScriptError> {
+        let source = source.to_string();
+        let script_name = script_name.to_string();
+        self.execute(move |s_env| s_env.run_named(&source, &script_name))?
+    }
+
+    /// Calls the JS function found at `function_path` (`myFunc`, `ScriptIt.funcs.greet`...) with `args`
+    pub fn call_function(
+        &self,
+        function_path: &str,
+        args: Vec<ScriptValue>,
+    ) -> Result<ScriptValue, ScriptError> {
+        let args = serde_json::to_string(&args)
+            .map_err(|e| ScriptError::SerializationError(e.to_string()))?;
+        self.eval_expression(&format!("({})(...{})", function_path, args))
+    }
+
+    /// Registers a function call, the handler runs on the scripting thread
+    pub fn register_func(
+        &self,
+        func_name: &str,
+        handler_closure: SendFuncHandler,
+    ) -> Result<(), ScriptError> {
+        let func_name = func_name.to_string();
+        self.execute(move |s_env| s_env.register_func(&func_name, handler_closure))
+    }
+}
+
+impl Default for ThreadedScriptingEnvironment {
+    fn default() -> Self {
+        ThreadedScriptingEnvironment::new()
+    }
+}
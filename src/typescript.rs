@@ -0,0 +1,324 @@
+//! TypeScript support: types are stripped in Rust before the code reaches the engine
+//!
+//! Wrapping an environment in a `TypeScriptEnvironment` makes `run`, `eval_expression` and
+//! `compile` accept TypeScript. Errors and stack traces are mapped back to the TypeScript source
+//! using the source map produced while stripping types. Scripts are run as classic scripts:
+//! scriptit has no ES module loader, `import` and `export` are rejected by the engines.
+//!
+//! ```
+//! use scriptit::{
+//!     core::{value::ScriptValue, ScriptingEnvironment},
+//!     platform::PlatformScriptingEnvironment,
+//!     typescript::TypeScriptEnvironment,
+//! };
+//!
+//! let mut s_env = TypeScriptEnvironment::new(PlatformScriptingEnvironment::new());
+//! let res = s_env
+//!     .eval_expression("((name: string): string => `Hello ${name}!`)('TS')")
+//!     .unwrap();
+//!
+//! assert_eq!(res, ScriptValue::String("Hello TS!".to_string()));
+//! ```
+
+use crate::core::{
+    compiled::CompiledScript, error::ScriptError, event_loop::EventLoop, value::ScriptValue,
+    ScriptingEnvironment, ANONYMOUS_SCRIPT_NAME,
+};
+use oxc_allocator::Allocator;
+use oxc_codegen::{Codegen, CodegenOptions};
+use oxc_parser::Parser;
+use oxc_semantic::SemanticBuilder;
+use oxc_span::SourceType;
+use oxc_transformer::{TransformOptions, Transformer};
+use std::{cell::RefCell, collections::HashMap, path::Path, rc::Rc};
+
+/// Prefix of the names of the scripts scriptit runs itself, they are plain JavaScript
+const INTERNAL_SCRIPT_PREFIX: &str = "scriptit:";
+
+/// JavaScript produced from TypeScript source
+pub struct TranspiledScript {
+    /// JavaScript code, ready to run
+    pub code: String,
+    script_name: String,
+    source_map: Option<sourcemap::SourceMap>,
+    // Columns added in front of the first line by the engine when it runs the code
+    column_offset: u32,
+}
+
+/// Strips types from TypeScript `source`, `script_name` is used to report errors
+pub fn transpile(source: &str, script_name: &str) -> Result<TranspiledScript, ScriptError> {
+    let compile_error = |errors: Vec<oxc_diagnostics::OxcDiagnostic>| {
+        let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+        ScriptError::CompileError(format!("{}: {}", script_name, messages.join("\n")))
+    };
+
+    let allocator = Allocator::default();
+    let path = Path::new(script_name);
+    let parsed = Parser::new(&allocator, source, SourceType::ts()).parse();
+    if !parsed.errors.is_empty() {
+        return Err(compile_error(parsed.errors));
+    }
+    let mut program = parsed.program;
+
+    let scoping = SemanticBuilder::new()
+        .build(&program)
+        .semantic
+        .into_scoping();
+    let transformed = Transformer::new(&allocator, path, &TransformOptions::default())
+        .build_with_scoping(scoping, &mut program);
+    if !transformed.errors.is_empty() {
+        return Err(compile_error(transformed.errors));
+    }
+
+    let generated = Codegen::new()
+        .with_options(CodegenOptions {
+            source_map_path: Some(path.to_path_buf()),
+            ..CodegenOptions::default()
+        })
+        .build(&program);
+    let source_map = generated
+        .map
+        .and_then(|map| sourcemap::SourceMap::from_slice(map.to_json_string().as_bytes()).ok());
+
+    Ok(TranspiledScript {
+        code: generated.code,
+        script_name: script_name.to_string(),
+        source_map,
+        column_offset: 0,
+    })
+}
+
+/// Strips types from a TypeScript expression, to evaluate with `eval_expression_named`
+///
+/// Errors are mapped knowing the engine wraps the expression before evaluating it, `column_offset`
+/// is the `expression_column_offset` of the environment evaluating it.
+pub fn transpile_expression(
+    source: &str,
+    script_name: &str,
+    column_offset: u32,
+) -> Result<TranspiledScript, ScriptError> {
+    let mut transpiled = transpile(source, script_name)?;
+    // The expression comes back as a statement
+    let expression_len = transpiled.code.trim_end().trim_end_matches(';').len();
+    transpiled.code.truncate(expression_len);
+    transpiled.column_offset = column_offset;
+    Ok(transpiled)
+}
+
+impl TranspiledScript {
+    /// Maps a 1-based position in the generated JavaScript to the TypeScript source
+    pub fn original_position(&self, line: u32, column: u32) -> Option<(u32, u32)> {
+        let token = self
+            .source_map
+            .as_ref()?
+            .lookup_token(line.checked_sub(1)?, column.checked_sub(1)?)?;
+        Some((token.get_src_line() + 1, token.get_src_col() + 1))
+    }
+
+    /// Rewrites every `script_name:line:column` location found in an error to the TypeScript source
+    pub fn map_error(&self, error: ScriptError) -> ScriptError {
+        match error {
+            ScriptError::CompileError(msg) => ScriptError::CompileError(self.map_locations(&msg)),
+            ScriptError::RuntimeError(msg) => ScriptError::RuntimeError(self.map_locations(&msg)),
+            other => other,
+        }
+    }
+
+    fn map_locations(&self, text: &str) -> String {
+        let prefix = format!("{}:", self.script_name);
+        let mut mapped = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(index) = rest.find(&prefix) {
+            let (before, location) = rest.split_at(index + prefix.len());
+            mapped.push_str(before);
+            rest = location;
+
+            // Expects `line:column`, anything else is left untouched
+            let line_len = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            if line_len == 0 || !rest[line_len..].starts_with(':') {
+                continue;
+            }
+            let column_start = line_len + 1;
+            let column_len = rest[column_start..]
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len() - column_start);
+            let line = rest[..line_len].parse::<u32>().ok();
+            let column = rest[column_start..column_start + column_len]
+                .parse::<u32>()
+                .ok();
+            if let (Some(line), Some(column)) = (line, column) {
+                let column = match line {
+                    1 => column.saturating_sub(self.column_offset).max(1),
+                    _ => column,
+                };
+                let (line, column) = self
+                    .original_position(line, column)
+                    .unwrap_or((line, column));
+                mapped.push_str(&format!("{}:{}", line, column));
+                rest = &rest[column_start + column_len..];
+            }
+        }
+        mapped.push_str(rest);
+        mapped
+    }
+}
+
+/// Runs TypeScript on any `ScriptingEnvironment`
+pub trait TypeScriptExt: ScriptingEnvironment {
+    /// Evaluates a single TS expression, `script_name` will be used in errors and stack traces
+    fn eval_typescript_expression_named(
+        &mut self,
+        source: &str,
+        script_name: &str,
+    ) -> Result<ScriptValue, ScriptError> {
+        let transpiled =
+            transpile_expression(source, script_name, self.expression_column_offset())?;
+        self.eval_expression_named(&transpiled.code, script_name)
+            .map_err(|error| transpiled.map_error(error))
+    }
+
+    /// Runs TypeScript code, `script_name` will be used in errors and stack traces
+    fn run_typescript_named(&mut self, source: &str, script_name: &str) -> Result<(), ScriptError> {
+        let transpiled = transpile(source, script_name)?;
+        self.run_named(&transpiled.code, script_name)
+            .map_err(|error| transpiled.map_error(error))
+    }
+
+    /// Evaluates a single TS expression
+    fn eval_typescript_expression(&mut self, source: &str) -> Result<ScriptValue, ScriptError> {
+        self.eval_typescript_expression_named(source, ANONYMOUS_SCRIPT_NAME)
+    }
+
+    /// Runs TypeScript code
+    fn run_typescript(&mut self, source: &str) -> Result<(), ScriptError> {
+        self.run_typescript_named(source, ANONYMOUS_SCRIPT_NAME)
+    }
+}
+
+impl<T: ScriptingEnvironment + ?Sized> TypeScriptExt for T {}
+
+/// Wraps an environment so that the code it runs and compiles is TypeScript
+///
+/// Plain JavaScript runs unchanged, as TypeScript is a superset of it. The scripts scriptit runs
+/// itself while installing capabilities are not transpiled.
+pub struct TypeScriptEnvironment<E: ScriptingEnvironment> {
+    inner: E,
+    // Source maps of the compiled scripts, by id, to map the errors they throw when run
+    compiled: HashMap<String, TranspiledScript>,
+}
+
+impl<E: ScriptingEnvironment> TypeScriptEnvironment<E> {
+    pub fn new(inner: E) -> TypeScriptEnvironment<E> {
+        TypeScriptEnvironment {
+            inner,
+            compiled: HashMap::new(),
+        }
+    }
+
+    /// The wrapped environment, which runs JavaScript
+    pub fn inner(&mut self) -> &mut E {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+}
+
+impl<E: ScriptingEnvironment> ScriptingEnvironment for TypeScriptEnvironment<E> {
+    /// Evaluates a single TS expression
+    fn eval_expression_named(
+        &mut self,
+        source: &str,
+        script_name: &str,
+    ) -> Result<ScriptValue, ScriptError> {
+        if script_name.starts_with(INTERNAL_SCRIPT_PREFIX) {
+            return self.inner.eval_expression_named(source, script_name);
+        }
+        self.inner
+            .eval_typescript_expression_named(source, script_name)
+    }
+
+    /// Runs TypeScript code
+    fn run_named(&mut self, source: &str, script_name: &str) -> Result<(), ScriptError> {
+        if script_name.starts_with(INTERNAL_SCRIPT_PREFIX) {
+            return self.inner.run_named(source, script_name);
+        }
+        self.inner.run_typescript_named(source, script_name)
+    }
+
+    fn compile_named(
+        &mut self,
+        source: &str,
+        script_name: &str,
+    ) -> Result<CompiledScript, ScriptError> {
+        let transpiled = transpile(source, script_name)?;
+        let script = self
+            .inner
+            .compile_named(&transpiled.code, script_name)
+            .map_err(|error| transpiled.map_error(error))?;
+        self.compiled.insert(script.id().to_string(), transpiled);
+        Ok(script)
+    }
+
+    fn compile_expression_named(
+        &mut self,
+        source: &str,
+        script_name: &str,
+    ) -> Result<CompiledScript, ScriptError> {
+        let transpiled =
+            transpile_expression(source, script_name, self.inner.expression_column_offset())?;
+        let script = self
+            .inner
+            .compile_expression_named(&transpiled.code, script_name)
+            .map_err(|error| transpiled.map_error(error))?;
+        self.compiled.insert(script.id().to_string(), transpiled);
+        Ok(script)
+    }
+
+    fn run_compiled(&mut self, script: &CompiledScript) -> Result<ScriptValue, ScriptError> {
+        let compiled = &self.compiled;
+        self.inner
+            .run_compiled(script)
+            .map_err(|error| match compiled.get(script.id()) {
+                Some(transpiled) => transpiled.map_error(error),
+                None => error,
+            })
+    }
+
+    fn release_compiled(&mut self, script: CompiledScript) {
+        self.compiled.remove(script.id());
+        self.inner.release_compiled(script);
+    }
+
+    fn expression_column_offset(&self) -> u32 {
+        self.inner.expression_column_offset()
+    }
+
+    fn event_loop(&self) -> Rc<RefCell<EventLoop>> {
+        self.inner.event_loop()
+    }
+
+    fn run_event_loop(&mut self) -> Result<(), ScriptError> {
+        self.inner.run_event_loop()
+    }
+
+    fn register_core_handler(
+        &mut self,
+        handler_name: &str,
+        handler_closure: Box<dyn FnMut(&str) -> Result<String, String>>,
+    ) {
+        self.inner
+            .register_core_handler(handler_name, handler_closure);
+    }
+
+    fn register_func(
+        &mut self,
+        func_name: &str,
+        handler_closure: Box<dyn FnMut(&Vec<ScriptValue>) -> Result<ScriptValue, ScriptError>>,
+    ) {
+        self.inner.register_func(func_name, handler_closure);
+    }
+}
@@ -0,0 +1,77 @@
+use scriptit::{
+    api,
+    backend::{Backend, BackendError, ScriptingEnvironmentFactory},
+    core::value::ScriptValue,
+};
+use serde_json::json;
+use wasm_bindgen_test::*;
+
+#[test]
+#[wasm_bindgen_test]
+fn default_factory_uses_the_platform_backend() {
+    let factory = ScriptingEnvironmentFactory::default();
+    assert_eq!(factory.backends(), &Backend::available()[..]);
+    let (backend, mut s_env) = factory.create().unwrap();
+    assert_eq!(backend, Backend::available()[0]);
+    s_env.register_func(
+        "double",
+        Box::new(|args| Ok(ScriptValue::from(args[0].as_i64().unwrap() * 2))),
+    );
+    api::web::install(&mut *s_env).unwrap();
+    let val = s_env
+        .eval_expression("[ScriptIt.funcs.double(21), typeof URL]")
+        .unwrap();
+    assert_eq!(val, json!([42, "function"]));
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn every_available_backend_runs_scripts() {
+    for backend in Backend::available() {
+        let mut s_env = backend.create().unwrap();
+        s_env.run("globalThis.answer = 40 + 2").unwrap();
+        assert_eq!(s_env.eval_expression("answer").unwrap(), json!(42));
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn config_parsing() {
+    let factory = ScriptingEnvironmentFactory::from_config(" QuickJS, v8,").unwrap();
+    assert_eq!(factory.backends(), &[Backend::QuickJs, Backend::V8]);
+    assert_eq!("boa".parse::<Backend>().unwrap().to_string(), "boa");
+    match ScriptingEnvironmentFactory::from_config("v8,spidermonkey") {
+        Err(BackendError::Unknown(name)) => assert_eq!(name, "spidermonkey"),
+        other => panic!("Expected a BackendError::Unknown, got {:?}", other),
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn falls_back_to_the_next_backend() {
+    // V8 and the host engine never are both compiled in
+    let missing = if Backend::V8.is_available() {
+        Backend::Wasm
+    } else {
+        Backend::V8
+    };
+    match missing.create() {
+        Err(BackendError::NotCompiled(backend)) => assert_eq!(backend, missing),
+        Err(other) => panic!("Expected a BackendError::NotCompiled, got {:?}", other),
+        Ok(_) => panic!("Expected a BackendError::NotCompiled"),
+    }
+
+    let fallback = Backend::available()[0];
+    let factory = ScriptingEnvironmentFactory::new(vec![missing, fallback]);
+    let (backend, mut s_env) = factory.create().unwrap();
+    assert_eq!(backend, fallback);
+    assert_eq!(s_env.eval_expression("1 + 1").unwrap(), json!(2));
+
+    match ScriptingEnvironmentFactory::new(vec![missing]).create() {
+        Err(BackendError::Exhausted(errors)) => {
+            assert!(matches!(errors[..], [BackendError::NotCompiled(_)]))
+        }
+        Err(other) => panic!("Expected a BackendError::Exhausted, got {:?}", other),
+        Ok(_) => panic!("Expected a BackendError::Exhausted"),
+    }
+}
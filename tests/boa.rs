@@ -0,0 +1,72 @@
+#![cfg(feature = "boa")]
+
+use scriptit::{
+    boa::BoaScriptingEnvironment,
+    core::{error::ScriptError, value::ScriptValue, ScriptingEnvironment},
+};
+use serde_json::json;
+
+#[test]
+fn eval_and_call_rust() {
+    let mut s_env = BoaScriptingEnvironment::new();
+    s_env.register_func(
+        "add",
+        Box::new(|args| {
+            let sum: i64 = args.iter().filter_map(|arg| arg.as_i64()).sum();
+            Ok(ScriptValue::from(sum))
+        }),
+    );
+    s_env.run("let base = 40;").unwrap();
+    let val = s_env
+        .eval_expression("{ sum: ScriptIt.funcs.add(base, 2), console: typeof console }")
+        .unwrap();
+    assert_eq!(val, json!({ "sum": 42, "console": "undefined" }));
+}
+
+#[test]
+fn compiled_scripts_run_again() {
+    let mut s_env = BoaScriptingEnvironment::new();
+    s_env.run("globalThis.count = 0").unwrap();
+    let script = s_env.compile_expression("++count").unwrap();
+    assert_eq!(s_env.run_compiled(&script).unwrap(), json!(1));
+    assert_eq!(s_env.run_compiled(&script).unwrap(), json!(2));
+    s_env.release_compiled(script);
+}
+
+#[test]
+fn errors() {
+    let mut s_env = BoaScriptingEnvironment::new();
+    match s_env.run("let = ;") {
+        Err(ScriptError::CompileError(_)) => {}
+        other => panic!("Expected a ScriptError::CompileError, got {:?}", other),
+    }
+    match s_env.run_named("throw new TypeError('bad rule')", "rules/my_rule.js") {
+        Err(ScriptError::RuntimeError(msg)) => {
+            assert!(msg.contains("TypeError: bad rule"));
+            assert!(msg.contains("rules/my_rule.js"));
+        }
+        other => panic!("Expected a ScriptError::RuntimeError got {:?}", other),
+    }
+}
+
+#[test]
+fn promises_and_timers() {
+    let mut s_env = BoaScriptingEnvironment::new();
+    s_env
+        .run(
+            "globalThis.steps = [];
+            setTimeout(() => steps.push('timeout'), 1);
+            Promise.resolve().then(() => steps.push('microtask'));",
+        )
+        .unwrap();
+    // Promise jobs run right after the script, like on V8
+    assert_eq!(
+        s_env.eval_expression("steps").unwrap(),
+        json!(["microtask"])
+    );
+    s_env.run_event_loop().unwrap();
+    assert_eq!(
+        s_env.eval_expression("steps").unwrap(),
+        json!(["microtask", "timeout"])
+    );
+}
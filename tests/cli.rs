@@ -0,0 +1,92 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::{
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+fn scriptit(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_scriptit"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+fn temp_script(source: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("scriptit-cli-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("config.json"), "{\"retries\":3}").unwrap();
+    let path = dir.join("script.js");
+    std::fs::write(&path, source).unwrap();
+    path
+}
+
+#[test]
+fn eval_prints_json() {
+    let output = scriptit(&["--eval", "({ sum: 1 + 1, list: [true, null] })"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "{\"list\":[true,null],\"sum\":2}\n");
+}
+
+#[test]
+fn runs_files_with_capabilities() {
+    let script = temp_script(
+        "const config = JSON.parse(ScriptIt.fs.readText('config.json'));
+        console.log('retries:', config.retries);
+        globalThis.state = 'waiting';
+        setTimeout(() => (state = new URL('/done', 'https://example.com').pathname), 10);",
+    );
+    let root = script.parent().unwrap().to_str().unwrap();
+    let output = scriptit(&[
+        script.to_str().unwrap(),
+        "--console",
+        "--fs-root",
+        root,
+        "--web",
+        "--timers",
+        "-e",
+        "state",
+    ]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "retries: 3\n\"/done\"\n");
+
+    // Without --timers, timers never fire
+    let output = scriptit(&[
+        script.to_str().unwrap(),
+        "--console",
+        "--fs-root",
+        root,
+        "-e",
+        "state",
+    ]);
+    assert_eq!(stdout(&output), "retries: 3\n\"waiting\"\n");
+}
+
+#[test]
+fn exit_codes() {
+    let code = |args: &[&str]| scriptit(args).status.code();
+    assert_eq!(code(&["-e", "notDefined"]), Some(1));
+    assert_eq!(code(&["--unknown-flag"]), Some(2));
+    assert_eq!(code(&[]), Some(2));
+    assert_eq!(code(&["--fs-write", "-e", "1"]), Some(2));
+    assert_eq!(code(&["-e", "let = ;"]), Some(3));
+    assert_eq!(code(&["-e", "undefined"]), Some(4));
+    assert_eq!(code(&["does/not/exist.js"]), Some(7));
+    assert_eq!(
+        code(&["--timeout=200", "-e", "(() => { while (true) {} })()"]),
+        Some(6)
+    );
+    assert_eq!(code(&["--help"]), Some(0));
+}
+
+#[cfg(all(not(feature = "v8"), feature = "quickjs"))]
+#[test]
+fn reject_heap_limits_that_overflow() {
+    let output = scriptit(&["--heap-limit", "18446744073709551615", "-e", "1"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("too large"));
+}
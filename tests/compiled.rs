@@ -0,0 +1,87 @@
+use std::{cell::RefCell, rc::Rc};
+
+use scriptit::{
+    core::{
+        error::ScriptError,
+        value::{ScriptNumber, ScriptValue},
+        ScriptingEnvironment,
+    },
+    platform::PlatformScriptingEnvironment,
+};
+use wasm_bindgen_test::*;
+
+#[test]
+#[wasm_bindgen_test]
+fn run_compiled_expression_many_times() {
+    let count = Rc::new(RefCell::new(0_u32));
+    let closure_count = Rc::clone(&count);
+    let mut s_env = PlatformScriptingEnvironment::new();
+    s_env.register_func(
+        "count",
+        Box::new(move |_| {
+            *closure_count.borrow_mut() += 1;
+            let res = *closure_count.borrow();
+            Ok(ScriptValue::Number(ScriptNumber::from(res)))
+        }),
+    );
+    let compiled = s_env
+        .compile_expression("ScriptIt.funcs.count() * 2")
+        .unwrap();
+
+    for i in 1..=3 {
+        let val = s_env.run_compiled(&compiled).unwrap();
+        assert_eq!(val, ScriptValue::Number(ScriptNumber::from(i * 2)));
+    }
+    assert_eq!(*count.borrow(), 3);
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn run_compiled_script_many_times() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    s_env.run("globalThis.total = 0;").unwrap();
+    let compiled = s_env.compile("globalThis.total += 5;").unwrap();
+
+    for _ in 0..4 {
+        let val = s_env.run_compiled(&compiled).unwrap();
+        assert_eq!(val, ScriptValue::Null);
+    }
+    let val = s_env.eval_expression("globalThis.total").unwrap();
+    assert_eq!(val, ScriptValue::Number(ScriptNumber::from(20)));
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn compile_error_on_compile() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    match s_env.compile("import async return") {
+        Err(ScriptError::CompileError(_)) => {}
+        other => panic!("Expected a ScriptError::CompileError, got {:?}", other),
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn runtime_error_on_run_compiled() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    let compiled = s_env
+        .compile_named("iDontExist();", "rules/my_rule.js")
+        .unwrap();
+    match s_env.run_compiled(&compiled) {
+        Err(ScriptError::RuntimeError(msg)) => assert!(msg.contains("rules/my_rule.js")),
+        other => panic!("Expected a ScriptError::RuntimeError got {:?}", other),
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn released_compiled_script_cant_run() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    let compiled = s_env.compile_expression("1 + 1").unwrap();
+    let handle = compiled.clone();
+    s_env.release_compiled(compiled);
+    match s_env.run_compiled(&handle) {
+        Err(ScriptError::RuntimeError(_)) => {}
+        other => panic!("Expected a ScriptError::RuntimeError got {:?}", other),
+    }
+}
@@ -0,0 +1,57 @@
+use scriptit::{
+    api::console::{self, ConsoleLevel},
+    core::ScriptingEnvironment,
+    platform::PlatformScriptingEnvironment,
+};
+use std::{cell::RefCell, rc::Rc};
+use wasm_bindgen_test::*;
+
+#[test]
+#[wasm_bindgen_test]
+fn levels() {
+    let logs = Rc::new(RefCell::new(Vec::new()));
+    let mut s_env = PlatformScriptingEnvironment::new();
+    let handler_logs = Rc::clone(&logs);
+    console::install(&mut s_env, move |level: ConsoleLevel, message: &str| {
+        handler_logs.borrow_mut().push((level, message.to_string()));
+    })
+    .unwrap();
+    s_env
+        .run(
+            "console.debug('d'); console.log('l'); console.info('i');
+            console.warn('w'); console.error('e');",
+        )
+        .unwrap();
+    assert_eq!(
+        *logs.borrow(),
+        vec![
+            (ConsoleLevel::Debug, String::from("d")),
+            (ConsoleLevel::Log, String::from("l")),
+            (ConsoleLevel::Info, String::from("i")),
+            (ConsoleLevel::Warn, String::from("w")),
+            (ConsoleLevel::Error, String::from("e")),
+        ]
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn formatting() {
+    let logs = Rc::new(RefCell::new(Vec::new()));
+    let mut s_env = PlatformScriptingEnvironment::new();
+    let handler_logs = Rc::clone(&logs);
+    console::install(&mut s_env, move |level: ConsoleLevel, message: &str| {
+        handler_logs.borrow_mut().push((level, message.to_string()));
+    })
+    .unwrap();
+    s_env
+        .run(
+            "const cycle = {}; cycle.self = cycle;
+            console.log('values:', 1, [true, null], undefined, cycle);
+            console.error(new TypeError('bad input'));",
+        )
+        .unwrap();
+    let logs = logs.borrow();
+    assert_eq!(logs[0].1, "values: 1 [true,null] undefined [object Object]");
+    assert!(logs[1].1.starts_with("TypeError: bad input"));
+}
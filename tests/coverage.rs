@@ -0,0 +1,76 @@
+#![cfg(all(not(target_arch = "wasm32"), feature = "v8"))]
+
+use scriptit::{core::ScriptingEnvironment, platform::PlatformScriptingEnvironment};
+
+const HELPERS_SRC: &str = "function double(x) {
+    return x * 2;
+}
+function unused(x) {
+    return x * 3;
+}
+globalThis.result = double(21);
+";
+
+#[test]
+fn collect_coverage_per_script() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    s_env.start_coverage().unwrap();
+    s_env.run_named(HELPERS_SRC, "lib/helpers.js").unwrap();
+    s_env.run("globalThis.notReported = true;").unwrap();
+    let report = s_env.take_coverage().unwrap();
+    s_env.stop_coverage().unwrap();
+
+    assert_eq!(report.scripts.len(), 1);
+    let script = report.script("lib/helpers.js").unwrap();
+    let double = script
+        .functions
+        .iter()
+        .find(|function| function.function_name == "double")
+        .unwrap();
+    assert_eq!(double.ranges[0].count, 1);
+    let unused = script
+        .functions
+        .iter()
+        .find(|function| function.function_name == "unused")
+        .unwrap();
+    assert_eq!(unused.ranges[0].count, 0);
+}
+
+#[test]
+fn export_coverage() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    s_env.start_coverage().unwrap();
+    s_env.run_named(HELPERS_SRC, "lib/helpers.js").unwrap();
+    let report = s_env.take_coverage().unwrap();
+
+    let v8_json: serde_json::Value = serde_json::from_str(&report.to_v8_json()).unwrap();
+    assert_eq!(v8_json["result"][0]["url"], "lib/helpers.js");
+
+    let lcov = report.to_lcov();
+    assert!(lcov.contains("SF:lib/helpers.js\n"));
+    assert!(lcov.contains("FNDA:1,double\n"));
+    assert!(lcov.contains("FNDA:0,unused\n"));
+    assert!(lcov.contains("DA:2,1\n"));
+    assert!(lcov.contains("DA:5,0\n"));
+    assert!(lcov.ends_with("end_of_record\n"));
+}
+
+#[test]
+fn expression_coverage_matches_source() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    s_env.start_coverage().unwrap();
+    let source = "[1, 2].map((x) => {\n    return x * 2;\n})";
+    s_env.eval_expression_named(source, "lib/expr.js").unwrap();
+    let report = s_env.take_coverage().unwrap();
+
+    let script = report.script("lib/expr.js").unwrap();
+    assert_eq!(script.source.as_deref(), Some(source));
+    let arrow = script
+        .functions
+        .iter()
+        .find(|function| function.ranges[0].count == 2)
+        .unwrap();
+    assert_eq!(arrow.ranges[0].start_offset, source.find("(x)").unwrap());
+    let lcov = report.to_lcov();
+    assert!(lcov.contains("DA:2,2\n"));
+}
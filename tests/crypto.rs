@@ -0,0 +1,90 @@
+use scriptit::{api::crypto, core::ScriptingEnvironment, platform::PlatformScriptingEnvironment};
+use serde_json::json;
+use wasm_bindgen_test::*;
+
+#[test]
+#[wasm_bindgen_test]
+fn get_random_values() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    crypto::install(&mut s_env).unwrap();
+    let val = s_env
+        .eval_expression(
+            "(() => {
+                const array = new Uint32Array(64);
+                const filled = crypto.getRandomValues(array);
+                const errorName = (f) => {
+                    try {
+                        f();
+                    } catch (e) {
+                        return e.name;
+                    }
+                };
+                return [
+                    filled === array,
+                    array.some((value) => value !== 0),
+                    errorName(() => crypto.getRandomValues(new Float64Array(4))),
+                    errorName(() => crypto.getRandomValues(new Uint8Array(65537))),
+                ];
+            })()",
+        )
+        .unwrap();
+    assert_eq!(
+        val,
+        json!([true, true, "TypeMismatchError", "QuotaExceededError"])
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn random_uuid() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    crypto::install(&mut s_env).unwrap();
+    let val = s_env
+        .eval_expression(
+            "(() => {
+                const uuid = crypto.randomUUID();
+                return [
+                    /^[0-9a-f]{8}-[0-9a-f]{4}-4[0-9a-f]{3}-[89ab][0-9a-f]{3}-[0-9a-f]{12}$/.test(uuid),
+                    uuid !== crypto.randomUUID(),
+                ];
+            })()",
+        )
+        .unwrap();
+    assert_eq!(val, json!([true, true]));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn digest() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    crypto::install(&mut s_env).unwrap();
+    s_env
+        .run(
+            "(async () => {
+                const data = new Uint8Array([97, 98, 99]);
+                const hex = (buffer) =>
+                    Array.from(new Uint8Array(buffer), (byte) => byte.toString(16).padStart(2, '0')).join('');
+                globalThis.hashes = [
+                    hex(await crypto.subtle.digest('SHA-1', data)),
+                    hex(await crypto.subtle.digest({ name: 'sha-256' }, data.buffer)),
+                    hex(await crypto.subtle.digest('SHA-512', data)),
+                ];
+                globalThis.unsupported = await crypto.subtle.digest('MD5', data).catch((e) => e.name);
+            })()",
+        )
+        .unwrap();
+    s_env.run_event_loop().unwrap();
+
+    let val = s_env.eval_expression("[hashes, unsupported]").unwrap();
+    assert_eq!(
+        val,
+        json!([
+            [
+                "a9993e364706816aba3e25717850c26c9cd0d89d",
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+                "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+            ],
+            "NotSupportedError"
+        ])
+    );
+}
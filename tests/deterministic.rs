@@ -0,0 +1,136 @@
+use scriptit::{
+    api::crypto,
+    core::{
+        deterministic::{self, DeterministicOptions},
+        value::ScriptValue,
+        ScriptingEnvironment,
+    },
+    platform::PlatformScriptingEnvironment,
+};
+use serde_json::json;
+use std::time::Duration;
+use wasm_bindgen_test::*;
+
+fn random_numbers(seed: u64) -> ScriptValue {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    let options = DeterministicOptions {
+        seed,
+        ..Default::default()
+    };
+    deterministic::install(&mut s_env, options).unwrap();
+    s_env
+        .eval_expression("Array.from({ length: 5 }, () => Math.random())")
+        .unwrap()
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn seeded_random() {
+    let numbers = random_numbers(42);
+    assert_eq!(numbers, random_numbers(42));
+    assert_ne!(numbers, random_numbers(43));
+    for number in numbers.as_array().unwrap() {
+        let number = number.as_f64().unwrap();
+        assert!((0.0..1.0).contains(&number));
+    }
+
+    let mut s_env = PlatformScriptingEnvironment::new();
+    deterministic::install(&mut s_env, Default::default()).unwrap();
+    let val = s_env.eval_expression("Math.max(1, 2)").unwrap();
+    assert_eq!(val, json!(2));
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn virtual_date() {
+    let options = DeterministicOptions {
+        start_time: 1_600_000_000_000,
+        ..Default::default()
+    };
+    let clock = options.clock.clone();
+    let mut s_env = PlatformScriptingEnvironment::new();
+    deterministic::install(&mut s_env, options).unwrap();
+
+    clock.advance(Duration::from_millis(1500));
+    let val = s_env
+        .eval_expression(
+            "[
+                Date.now(),
+                new Date().toISOString(),
+                new Date().getHours(),
+                new Date().getTimezoneOffset(),
+                Date(),
+                new Date(2020, 0, 1, 10).toISOString(),
+                new Date('2020-01-01T10:00').toISOString(),
+                new Date() instanceof Date,
+            ]",
+        )
+        .unwrap();
+    assert_eq!(
+        val,
+        json!([
+            1_600_000_001_500u64,
+            "2020-09-13T12:26:41.500Z",
+            12,
+            0,
+            "Sun Sep 13 2020 12:26:41 GMT+0000 (Coordinated Universal Time)",
+            "2020-01-01T10:00:00.000Z",
+            "2020-01-01T10:00:00.000Z",
+            true
+        ])
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn timers_follow_the_virtual_clock() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    deterministic::install(&mut s_env, Default::default()).unwrap();
+    s_env
+        .run(
+            "globalThis.times = [];
+            setTimeout(() => times.push(Date.now()), 1000);
+            setTimeout(() => times.push(Date.now()), 250);",
+        )
+        .unwrap();
+    s_env.run_event_loop().unwrap();
+
+    let val = s_env.eval_expression("times").unwrap();
+    assert_eq!(val, json!([250, 1000]));
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn nondeterministic_apis_are_removed() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    deterministic::install(&mut s_env, Default::default()).unwrap();
+    let val = s_env
+        .eval_expression(
+            "[typeof WeakRef, typeof FinalizationRegistry, typeof SharedArrayBuffer, typeof Atomics]",
+        )
+        .unwrap();
+    assert_eq!(
+        val,
+        json!(["undefined", "undefined", "undefined", "undefined"])
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn entropy_handlers_are_disabled() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    crypto::install(&mut s_env).unwrap();
+    deterministic::install(&mut s_env, Default::default()).unwrap();
+    let val = s_env
+        .eval_expression(
+            "['crypto$randomBytes', 'crypto$randomUUID'].map((handler) => {
+                try {
+                    return ScriptIt.core.callToRust(handler, '16');
+                } catch (e) {
+                    return String(e.message || e).includes('not available in deterministic mode');
+                }
+            }).concat(typeof crypto)",
+        )
+        .unwrap();
+    assert_eq!(val, json!([true, true, "undefined"]));
+}
@@ -82,3 +82,27 @@ fn multi_statement_fail() {
         other => panic!("Expected a ScriptError::RuntimeError got {:?}", other),
     }
 }
+
+#[test]
+#[wasm_bindgen_test]
+fn named_runtime_error() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    match s_env.run_named("1 + 1;\niDontExist();", "rules/my_rule.js") {
+        Err(ScriptError::RuntimeError(msg)) => assert!(
+            msg.contains("rules/my_rule.js"),
+            "Expected the error to refer to the script name, got {}",
+            msg
+        ),
+        other => panic!("Expected a ScriptError::RuntimeError got {:?}", other),
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn named_eval_expression() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    let val = s_env
+        .eval_expression_named("12 + 3", "rules/my_rule.js")
+        .unwrap();
+    assert_eq!(val, ScriptValue::Number(ScriptNumber::from(15)));
+}
@@ -0,0 +1,321 @@
+#![cfg(feature = "derive")]
+
+use scriptit::{
+    core::{
+        error::ScriptError,
+        export::{self, ScriptClass, ScriptFunction, TypeDefinitions},
+        value::{ScriptValue, Serde},
+        ScriptingEnvironment,
+    },
+    platform::PlatformScriptingEnvironment,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use wasm_bindgen_test::*;
+
+/// Adds two numbers, `b` defaults to 1
+#[scriptit::function]
+fn add(a: i64, b: Option<i64>) -> i64 {
+    a + b.unwrap_or(1)
+}
+
+#[scriptit::function(name = "shout")]
+fn to_upper_case(text: &str, words: &[String]) -> String {
+    format!("{} {}", text.to_uppercase(), words.join(" ").to_uppercase())
+}
+
+#[scriptit::function]
+fn parse_port(port: String) -> Result<u16, String> {
+    port.parse()
+        .map_err(|_| format!("`{}` is not a port", port))
+}
+
+#[scriptit::function]
+fn nothing() {}
+
+#[derive(Serialize, Deserialize)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+#[scriptit::function]
+fn midpoint(a: Serde<Point>, b: Serde<Point>) -> Serde<Point> {
+    Serde(Point {
+        x: (a.0.x + b.0.x) / 2.0,
+        y: (a.0.y + b.0.y) / 2.0,
+    })
+}
+
+pub struct Inventory {
+    items: HashMap<String, u32>,
+}
+
+/// Counts items
+#[scriptit::class]
+impl Inventory {
+    pub fn new(items: HashMap<String, u32>) -> Inventory {
+        Inventory { items }
+    }
+
+    /// Adds `count` items, returns how many there are now
+    pub fn add(&mut self, item: &str, count: u32) -> u32 {
+        let total = self.items.entry(item.to_string()).or_insert(0);
+        *total += count;
+        *total
+    }
+
+    #[scriptit(name = "countOf")]
+    pub fn count_of(&self, item: &str) -> u32 {
+        self.get(item)
+    }
+
+    pub fn take(&mut self, item: &str) -> Result<u32, ScriptError> {
+        self.items
+            .remove(item)
+            .ok_or_else(|| ScriptError::RuntimeError(format!("No {}", item)))
+    }
+
+    pub fn empty() -> Vec<String> {
+        Vec::new()
+    }
+
+    fn get(&self, item: &str) -> u32 {
+        self.items.get(item).copied().unwrap_or(0)
+    }
+}
+
+pub struct Registry;
+
+#[scriptit::class(name = "Settings")]
+impl Registry {
+    pub fn version() -> &'static str {
+        "1.0"
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn functions() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    export::register_function::<add>(&mut s_env);
+    export::register_function::<to_upper_case>(&mut s_env);
+    export::register_function::<parse_port>(&mut s_env);
+    export::register_function::<nothing>(&mut s_env);
+    export::register_function::<midpoint>(&mut s_env);
+    export::register_class::<Inventory>(&mut s_env).unwrap();
+    export::register_class::<Registry>(&mut s_env).unwrap();
+    let val = s_env
+        .eval_expression(
+            "[
+                ScriptIt.funcs.add(1, 2),
+                ScriptIt.funcs.add(1),
+                ScriptIt.funcs.shout('hi', ['you', 'there']),
+                ScriptIt.funcs.parse_port('8080'),
+                ScriptIt.funcs.nothing(),
+                ScriptIt.funcs.midpoint({ x: 0, y: 0 }, { x: 2, y: 4 }),
+            ]",
+        )
+        .unwrap();
+    assert_eq!(
+        val,
+        json!([3, 2, "HI YOU THERE", 8080, null, { "x": 1, "y": 2 }])
+    );
+    // Still callable from Rust
+    assert_eq!(add(2, None), 3);
+    assert_eq!(
+        add::call(&[ScriptValue::from(5)]).unwrap(),
+        ScriptValue::from(6)
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn function_errors() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    export::register_function::<add>(&mut s_env);
+    export::register_function::<to_upper_case>(&mut s_env);
+    export::register_function::<parse_port>(&mut s_env);
+    export::register_function::<nothing>(&mut s_env);
+    export::register_function::<midpoint>(&mut s_env);
+    export::register_class::<Inventory>(&mut s_env).unwrap();
+    export::register_class::<Registry>(&mut s_env).unwrap();
+    let error = s_env
+        .eval_expression("ScriptIt.funcs.parse_port('http')")
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("`http` is not a port"),
+        "{}",
+        error
+    );
+
+    let val = s_env
+        .eval_expression(
+            "[[], ['x'], [1, 2, 3]].map((args) => {
+                try {
+                    ScriptIt.funcs.add(...args);
+                    return 'ok';
+                } catch (e) {
+                    return 'error';
+                }
+            })",
+        )
+        .unwrap();
+    assert_eq!(val, json!(["error", "error", "error"]));
+    match add::call(&[ScriptValue::from("x")]) {
+        Err(ScriptError::CastError { type_from, type_to }) => {
+            assert_eq!((type_from, type_to), ("ScriptValue::String", "i64"))
+        }
+        other => panic!("Expected a CastError, got {:?}", other),
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn classes() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    export::register_function::<add>(&mut s_env);
+    export::register_function::<to_upper_case>(&mut s_env);
+    export::register_function::<parse_port>(&mut s_env);
+    export::register_function::<nothing>(&mut s_env);
+    export::register_function::<midpoint>(&mut s_env);
+    export::register_class::<Inventory>(&mut s_env).unwrap();
+    export::register_class::<Registry>(&mut s_env).unwrap();
+    let val = s_env
+        .eval_expression(
+            "(() => {
+                const { Inventory, Settings } = ScriptIt.classes;
+                const inventory = new Inventory({ apples: 2 });
+                const other = new Inventory({});
+                inventory.add('apples', 3);
+                inventory.add('pears', 1);
+                other.add('apples', 10);
+                let taken;
+                try {
+                    inventory.take('plums');
+                } catch (e) {
+                    taken = 'error';
+                }
+                return [
+                    inventory.countOf('apples'),
+                    inventory.take('pears'),
+                    inventory.countOf('pears'),
+                    taken,
+                    other.countOf('apples'),
+                    Inventory.empty(),
+                    Settings.version(),
+                    inventory instanceof Inventory,
+                    typeof inventory.get,
+                ];
+            })()",
+        )
+        .unwrap();
+    assert_eq!(
+        val,
+        json!([5, 1, 0, "error", 10, [], "1.0", true, "undefined"])
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn freed_instances() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    export::register_function::<add>(&mut s_env);
+    export::register_function::<to_upper_case>(&mut s_env);
+    export::register_function::<parse_port>(&mut s_env);
+    export::register_function::<nothing>(&mut s_env);
+    export::register_function::<midpoint>(&mut s_env);
+    export::register_class::<Inventory>(&mut s_env).unwrap();
+    export::register_class::<Registry>(&mut s_env).unwrap();
+    let val = s_env
+        .eval_expression(
+            "(() => {
+                const inventory = new ScriptIt.classes.Inventory({});
+                inventory.free();
+                try {
+                    inventory.add('apples', 1);
+                    return 'ok';
+                } catch (e) {
+                    return e instanceof TypeError;
+                }
+            })()",
+        )
+        .unwrap();
+    assert_eq!(val, ScriptValue::from(true));
+
+    let error = s_env
+        .eval_expression("new ScriptIt.classes.Settings()")
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("Settings has no constructor"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn metadata() {
+    let metadata = <add as ScriptFunction>::METADATA;
+    assert_eq!(metadata.name, "add");
+    assert_eq!(metadata.docs, "Adds two numbers, `b` defaults to 1");
+    assert_eq!(metadata.params[1].ty, "number | null");
+    assert!(metadata.params[1].optional);
+    assert_eq!(<to_upper_case as ScriptFunction>::METADATA.name, "shout");
+
+    let class = <Inventory as ScriptClass>::METADATA;
+    assert_eq!(class.name, "Inventory");
+    assert!(class.constructor.is_some());
+    let names: Vec<&str> = class.methods.iter().map(|m| m.function.name).collect();
+    assert_eq!(names, ["add", "countOf", "take", "empty"]);
+    assert!(<Registry as ScriptClass>::METADATA.constructor.is_none());
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn type_definitions() {
+    let definitions = TypeDefinitions::new()
+        .function::<add>()
+        .function::<to_upper_case>()
+        .function::<parse_port>()
+        .function::<nothing>()
+        .function::<midpoint>()
+        .class::<Inventory>()
+        .to_string();
+    assert_eq!(
+        definitions,
+        "declare namespace ScriptIt {
+    namespace funcs {
+        /**
+         * Adds two numbers, `b` defaults to 1
+         */
+        function add(a: number, b?: number | null): number;
+        function shout(text: string, words: string[]): string;
+        function parse_port(port: string): number;
+        function nothing(): void;
+        function midpoint(a: unknown, b: unknown): unknown;
+    }
+    namespace classes {
+        /**
+         * Counts items
+         */
+        class Inventory {
+            constructor(items: Record<string, number>);
+            /**
+             * Adds `count` items, returns how many there are now
+             */
+            add(item: string, count: number): number;
+            countOf(item: string): number;
+            take(item: string): number;
+            static empty(): string[];
+            /**
+             * Frees the Rust value behind the instance
+             */
+            free(): void;
+        }
+    }
+}
+"
+    );
+}
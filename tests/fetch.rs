@@ -0,0 +1,142 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use scriptit::{
+    api::fetch::{self, FetchHandler, FetchRequest, FetchResponder, FetchResponse},
+    core::ScriptingEnvironment,
+    platform::PlatformScriptingEnvironment,
+};
+use serde_json::json;
+use std::{thread, time::Duration};
+
+fn router(request: FetchRequest) -> Result<FetchResponse, String> {
+    match (request.method.as_str(), request.url.as_str()) {
+        ("GET", "/status") => {
+            Ok(FetchResponse::new(200, "{\"up\":true}").header("Content-Type", "application/json"))
+        }
+        ("POST", "/echo") => {
+            let content_type = request
+                .headers
+                .iter()
+                .find(|(name, _)| name == "content-type")
+                .map(|(_, value)| value.clone())
+                .unwrap_or_default();
+            Ok(FetchResponse::new(201, request.body.unwrap_or_default())
+                .header("Content-Type", &content_type))
+        }
+        ("GET", "/latin1") => Ok(FetchResponse::new(200, vec![b'a', 0xe9, b'b'])),
+        ("GET", "/down") => Err(String::from("connection refused")),
+        _ => Ok(FetchResponse::new(404, "Not found")),
+    }
+}
+
+#[test]
+fn fetch_from_a_router() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    fetch::install(&mut s_env, router).unwrap();
+    s_env
+        .run(
+            "
+            globalThis.results = [];
+            (async () => {
+                const status = await fetch('/status');
+                results.push([status.status, status.ok, status.headers.get('content-type'), await status.json()]);
+
+                const echo = await fetch('/echo', {
+                    method: 'post',
+                    headers: { 'Content-Type': 'application/json' },
+                    body: JSON.stringify({ name: 'héllo' }),
+                });
+                results.push([echo.status, echo.headers.get('Content-Type'), await echo.json()]);
+
+                const missing = await fetch(new Request('/missing'));
+                results.push([missing.status, missing.ok, await missing.text()]);
+            })().catch((err) => results.push(String(err)));
+            ",
+        )
+        .unwrap();
+    s_env.run_event_loop().unwrap();
+
+    let val = s_env.eval_expression("results").unwrap();
+    assert_eq!(
+        val,
+        json!([
+            [200, true, "application/json", { "up": true }],
+            [201, "application/json", { "name": "héllo" }],
+            [404, false, "Not found"]
+        ])
+    );
+}
+
+#[test]
+fn replace_invalid_utf8_in_text() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    fetch::install(&mut s_env, router).unwrap();
+    s_env
+        .run("fetch('/latin1').then((res) => res.text()).then((text) => { globalThis.text = text; });")
+        .unwrap();
+    s_env.run_event_loop().unwrap();
+
+    let val = s_env.eval_expression("text").unwrap();
+    assert_eq!(val, json!("a\u{fffd}b"));
+}
+
+struct DroppingHandler;
+
+impl FetchHandler for DroppingHandler {
+    fn fetch(&mut self, _request: FetchRequest, _responder: FetchResponder) {}
+}
+
+#[test]
+fn failed_requests_reject() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    fetch::install(&mut s_env, router).unwrap();
+    s_env
+        .run("fetch('/down').catch((err) => { globalThis.error = err.message; });")
+        .unwrap();
+    s_env.run_event_loop().unwrap();
+    let val = s_env.eval_expression("error").unwrap();
+    assert_eq!(val, json!("Failed to fetch /down: connection refused"));
+
+    let mut s_env = PlatformScriptingEnvironment::new();
+    fetch::install(&mut s_env, DroppingHandler).unwrap();
+    s_env
+        .run("fetch('/any').catch((err) => { globalThis.error = err.message; });")
+        .unwrap();
+    s_env.run_event_loop().unwrap();
+    let val = s_env.eval_expression("error").unwrap();
+    assert_eq!(
+        val,
+        json!("Failed to fetch /any: The request was dropped by the host")
+    );
+}
+
+#[test]
+fn respond_from_another_thread() {
+    struct SlowHandler;
+
+    impl FetchHandler for SlowHandler {
+        fn fetch(&mut self, request: FetchRequest, responder: FetchResponder) {
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(20));
+                responder.respond(Ok(FetchResponse::new(200, request.url)));
+            });
+        }
+    }
+
+    let mut s_env = PlatformScriptingEnvironment::new();
+    fetch::install(&mut s_env, SlowHandler).unwrap();
+    s_env
+        .run(
+            "
+            globalThis.texts = [];
+            Promise.all([fetch('/a'), fetch('/b')])
+                .then((responses) => Promise.all(responses.map((res) => res.text())))
+                .then((bodies) => { globalThis.texts = bodies; });
+            ",
+        )
+        .unwrap();
+    s_env.run_event_loop().unwrap();
+
+    let val = s_env.eval_expression("texts").unwrap();
+    assert_eq!(val, json!(["/a", "/b"]));
+}
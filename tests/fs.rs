@@ -0,0 +1,155 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use scriptit::{
+    api::fs::{self, FsMode},
+    core::ScriptingEnvironment,
+    platform::PlatformScriptingEnvironment,
+};
+use serde_json::json;
+use std::path::PathBuf;
+
+fn temp_root() -> PathBuf {
+    let root = std::env::temp_dir().join(format!("scriptit-fs-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(root.join("data")).unwrap();
+    std::fs::write(root.join("data/config.json"), "{\"debug\":true}").unwrap();
+    root
+}
+
+#[test]
+fn read_and_write_files() {
+    let root = temp_root();
+    let mut s_env = PlatformScriptingEnvironment::new();
+    fs::install(&mut s_env, &root, FsMode::ReadWrite).unwrap();
+
+    let val = s_env
+        .eval_expression(
+            "(() => {
+                const config = JSON.parse(ScriptIt.fs.readText('data/config.json'));
+                ScriptIt.fs.writeText('/data/out.txt', 'héllo');
+                return [
+                    config,
+                    Array.from(ScriptIt.fs.readBytes('data/./out.txt')),
+                    ScriptIt.fs.list('data').map((entry) => entry.name),
+                    ScriptIt.fs.stat('data/out.txt').size,
+                    ScriptIt.fs.stat('data').isDirectory,
+                ];
+            })()",
+        )
+        .unwrap();
+    assert_eq!(
+        val,
+        json!([
+            { "debug": true },
+            [104, 195, 169, 108, 108, 111],
+            ["config.json", "out.txt"],
+            6,
+            true
+        ])
+    );
+    assert_eq!(
+        std::fs::read_to_string(root.join("data/out.txt")).unwrap(),
+        "héllo"
+    );
+
+    s_env.run("ScriptIt.fs.remove('data/out.txt')").unwrap();
+    assert!(!root.join("data/out.txt").exists());
+    assert!(s_env.run("ScriptIt.fs.remove('/')").is_err());
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn read_only_mode() {
+    let root = temp_root();
+    let mut s_env = PlatformScriptingEnvironment::new();
+    fs::install(&mut s_env, &root, FsMode::ReadOnly).unwrap();
+
+    assert!(s_env
+        .run("ScriptIt.fs.readText('data/config.json')")
+        .is_ok());
+    let err = s_env
+        .run("ScriptIt.fs.writeText('data/config.json', '{}')")
+        .unwrap_err();
+    assert!(err.to_string().contains("read-only"));
+    assert!(s_env.run("ScriptIt.fs.remove('data/config.json')").is_err());
+    assert!(root.join("data/config.json").exists());
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn reject_paths_outside_of_the_root() {
+    let root = temp_root();
+    let outside = root.with_extension("outside");
+    std::fs::create_dir_all(&outside).unwrap();
+    std::fs::write(outside.join("secret.txt"), "secret").unwrap();
+
+    let mut s_env = PlatformScriptingEnvironment::new();
+    fs::install(&mut s_env, &root, FsMode::ReadWrite).unwrap();
+
+    let secret_path = format!(
+        "../{}/secret.txt",
+        outside.file_name().unwrap().to_string_lossy()
+    );
+    for src in &[
+        format!("ScriptIt.fs.readText('{}')", secret_path),
+        format!("ScriptIt.fs.writeText('{}', 'pwned')", secret_path),
+        String::from("ScriptIt.fs.list('data/../..')"),
+    ] {
+        let err = s_env.run(src).unwrap_err();
+        assert!(err.to_string().contains("outside of the filesystem root"));
+    }
+
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+        let err = s_env
+            .run("ScriptIt.fs.readText('link/secret.txt')")
+            .unwrap_err();
+        assert!(err.to_string().contains("outside of the filesystem root"));
+    }
+    assert_eq!(
+        std::fs::read_to_string(outside.join("secret.txt")).unwrap(),
+        "secret"
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+    std::fs::remove_dir_all(outside).unwrap();
+}
+
+#[test]
+#[cfg(unix)]
+fn resolve_symbolic_links() {
+    let root = temp_root();
+    let outside = root.with_extension("outside");
+    std::fs::create_dir_all(&outside).unwrap();
+    std::os::unix::fs::symlink("data", root.join("inner")).unwrap();
+    std::os::unix::fs::symlink(outside.join("created.txt"), root.join("dangling")).unwrap();
+    std::os::unix::fs::symlink("../outside.txt", root.join("data/relative")).unwrap();
+
+    let mut s_env = PlatformScriptingEnvironment::new();
+    fs::install(&mut s_env, &root, FsMode::ReadWrite).unwrap();
+
+    let val = s_env
+        .eval_expression("JSON.parse(ScriptIt.fs.readText('inner/config.json'))")
+        .unwrap();
+    assert_eq!(val, json!({ "debug": true }));
+    for src in &[
+        "ScriptIt.fs.writeText('dangling', 'pwned')",
+        "ScriptIt.fs.writeText('data/relative/../../x.txt', 'pwned')",
+    ] {
+        let err = s_env.run(src).unwrap_err();
+        assert!(err.to_string().contains("outside of the filesystem root"));
+    }
+    s_env
+        .run("ScriptIt.fs.writeText('data/relative', 'inside')")
+        .unwrap();
+    assert!(!outside.join("created.txt").exists());
+    assert_eq!(
+        std::fs::read_to_string(root.join("outside.txt")).unwrap(),
+        "inside"
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+    std::fs::remove_dir_all(outside).unwrap();
+}
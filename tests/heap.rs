@@ -0,0 +1,49 @@
+#![cfg(all(not(target_arch = "wasm32"), feature = "v8"))]
+
+use scriptit::{
+    core::{value::ScriptValue, ScriptingEnvironment},
+    platform::PlatformScriptingEnvironment,
+};
+
+#[test]
+fn heap_statistics_grow_with_script_objects() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    let before = s_env.heap_statistics();
+    assert!(before.used_heap_size > 0);
+    assert!(before.used_heap_size <= before.total_heap_size);
+    assert!(before.total_heap_size <= before.heap_size_limit);
+
+    s_env
+        .run("globalThis.retained = Array.from({ length: 100000 }, (_, i) => ({ i }));")
+        .unwrap();
+    let after = s_env.heap_statistics();
+    assert!(after.used_heap_size > before.used_heap_size);
+}
+
+#[test]
+fn heap_statistics_count_handlers() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    assert_eq!(s_env.heap_statistics().handler_count, 0);
+    s_env.register_func("noop", Box::new(|_| Ok(ScriptValue::Null)));
+    assert_eq!(s_env.heap_statistics().handler_count, 1);
+}
+
+#[test]
+fn write_heap_snapshot() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    s_env
+        .run("class RetainedThing {}; globalThis.retained = new RetainedThing();")
+        .unwrap();
+    let path = std::env::temp_dir().join(format!("scriptit-{}.heapsnapshot", std::process::id()));
+    s_env.write_heap_snapshot(&path).unwrap();
+
+    let snapshot = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let snapshot: serde_json::Value = serde_json::from_str(&snapshot).unwrap();
+    assert!(snapshot["snapshot"]["node_count"].as_u64().unwrap() > 0);
+    assert!(snapshot["strings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|string| string == "RetainedThing"));
+}
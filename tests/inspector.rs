@@ -0,0 +1,81 @@
+#![cfg(all(not(target_arch = "wasm32"), feature = "v8"))]
+
+use scriptit::{
+    core::{value::ScriptValue, ScriptingEnvironment},
+    platform::{inspector::InspectorFrontend, PlatformScriptingEnvironment},
+};
+use std::thread;
+
+/// Waits for the message matching `predicate`, skipping the others
+fn recv_until(
+    frontend: &InspectorFrontend,
+    predicate: impl Fn(&ScriptValue) -> bool,
+) -> ScriptValue {
+    loop {
+        let message = frontend.recv().expect("Inspector session closed");
+        let message: ScriptValue = serde_json::from_str(&message).unwrap();
+        if predicate(&message) {
+            return message;
+        }
+    }
+}
+
+fn is_response(id: u64) -> impl Fn(&ScriptValue) -> bool {
+    move |message| message["id"] == id
+}
+
+#[test]
+fn runtime_evaluate() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    let frontend = s_env.connect_inspector();
+    frontend
+        .send(r#"{"id":1,"method":"Runtime.evaluate","params":{"expression":"20 + 22"}}"#)
+        .unwrap();
+    s_env.poll_inspector();
+
+    let response = recv_until(&frontend, is_response(1));
+    assert_eq!(response["result"]["result"]["value"], 42);
+}
+
+#[test]
+fn pause_on_breakpoint_and_inspect() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    let frontend = s_env.connect_inspector();
+    frontend
+        .send(r#"{"id":1,"method":"Debugger.enable"}"#)
+        .unwrap();
+    frontend
+        .send(r#"{"id":2,"method":"Debugger.setBreakpointByUrl","params":{"url":"debugged.js","lineNumber":2}}"#)
+        .unwrap();
+    s_env.poll_inspector();
+    recv_until(&frontend, is_response(2));
+
+    let debugger = thread::spawn(move || {
+        let paused = recv_until(&frontend, |message| message["method"] == "Debugger.paused");
+        let call_frame_id = paused["params"]["callFrames"][0]["callFrameId"].clone();
+        let evaluate = serde_json::json!({
+            "id": 3,
+            "method": "Debugger.evaluateOnCallFrame",
+            "params": { "callFrameId": call_frame_id, "expression": "answer" },
+        });
+        frontend.send(&evaluate.to_string()).unwrap();
+        let evaluated = recv_until(&frontend, is_response(3));
+        frontend
+            .send(r#"{"id":4,"method":"Debugger.resume"}"#)
+            .unwrap();
+        evaluated["result"]["result"]["value"].clone()
+    });
+
+    s_env
+        .run_named(
+            "(function() {\n  const answer = 42;\n  globalThis.done = true;\n})();",
+            "debugged.js",
+        )
+        .unwrap();
+
+    assert_eq!(debugger.join().unwrap(), 42);
+    assert_eq!(
+        s_env.eval_expression("globalThis.done").unwrap(),
+        ScriptValue::Bool(true)
+    );
+}
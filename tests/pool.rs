@@ -0,0 +1,100 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use scriptit::{
+    core::{
+        value::{ScriptNumber, ScriptValue},
+        ScriptingEnvironment,
+    },
+    pool::ScriptingPool,
+};
+use std::{
+    future::Future,
+    sync::Arc,
+    task::{Context, Poll, Wake},
+    thread::{self, Thread},
+};
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let waker = Arc::new(ThreadWaker(thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+#[test]
+fn setup_is_applied_to_every_environment() {
+    let pool = ScriptingPool::builder()
+        .size(2)
+        .setup(|s_env| {
+            s_env.run("globalThis.answer = 42;").unwrap();
+        })
+        .build();
+
+    let first = pool.checkout();
+    let second = pool.checkout();
+    for s_env in [&first, &second].iter() {
+        let val = s_env.eval_expression("globalThis.answer").unwrap();
+        assert_eq!(val, ScriptValue::Number(ScriptNumber::from(42)));
+    }
+}
+
+#[test]
+#[should_panic(expected = "at least one environment")]
+fn empty_pool_is_rejected() {
+    ScriptingPool::builder().size(0).build();
+}
+
+#[test]
+fn checkin_keeps_state_unless_reset() {
+    let pool = ScriptingPool::builder().size(1).build();
+    pool.checkout().run("globalThis.leftover = true;").unwrap();
+    let val = pool
+        .checkout()
+        .eval_expression("typeof globalThis.leftover")
+        .unwrap();
+    assert_eq!(val, ScriptValue::String("boolean".to_string()));
+
+    let pool = ScriptingPool::builder()
+        .size(1)
+        .reset_on_return(true)
+        .build();
+    pool.checkout().run("globalThis.leftover = true;").unwrap();
+    let val = pool
+        .checkout()
+        .eval_expression("typeof globalThis.leftover")
+        .unwrap();
+    assert_eq!(val, ScriptValue::String("undefined".to_string()));
+}
+
+#[test]
+fn execute_concurrently() {
+    let pool = ScriptingPool::builder().size(2).build();
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            let pool = pool.clone();
+            thread::spawn(move || {
+                block_on(pool.execute(move |s_env| s_env.eval_expression(&format!("{} * 2", i))))
+                    .unwrap()
+                    .unwrap()
+            })
+        })
+        .collect();
+
+    for (i, handle) in handles.into_iter().enumerate() {
+        let val = handle.join().unwrap();
+        assert_eq!(val, ScriptValue::Number(ScriptNumber::from(i * 2)));
+    }
+}
@@ -0,0 +1,81 @@
+#![cfg(all(not(target_arch = "wasm32"), feature = "v8"))]
+
+use scriptit::{
+    core::{value::ScriptValue, ScriptingEnvironment},
+    platform::PlatformScriptingEnvironment,
+};
+
+#[test]
+fn record_cpu_profile() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    s_env.register_func("slowHandler", Box::new(|_| Ok(ScriptValue::Null)));
+
+    s_env.start_profiling().unwrap();
+    s_env
+        .run_named(
+            "function hotLoop() {
+                const start = Date.now();
+                let i = 0;
+                while (Date.now() - start < 100) {
+                    i += 1;
+                    ScriptIt.funcs.slowHandler(i);
+                }
+            }
+            hotLoop();",
+            "profiled.js",
+        )
+        .unwrap();
+    let profile = s_env.stop_profiling().unwrap();
+
+    let profile: ScriptValue = serde_json::from_str(&profile).unwrap();
+    let nodes = profile["nodes"].as_array().unwrap();
+    assert!(profile["startTime"].as_f64().unwrap() <= profile["endTime"].as_f64().unwrap());
+    assert!(nodes
+        .iter()
+        .any(|node| node["callFrame"]["functionName"] == "hotLoop"
+            && node["callFrame"]["url"] == "profiled.js"));
+}
+
+#[test]
+fn profile_while_collecting_coverage() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    s_env.start_coverage().unwrap();
+    s_env.start_profiling().unwrap();
+    s_env
+        .run_named("function first() { return 1; }\nfirst();", "first.js")
+        .unwrap();
+    s_env.stop_profiling().unwrap();
+
+    // Coverage is still collected once profiling stops
+    s_env
+        .run_named("function second() { return 2; }\nsecond();", "second.js")
+        .unwrap();
+    let report = s_env.take_coverage().unwrap();
+    let second = report.script("second.js").unwrap();
+    assert!(second
+        .functions
+        .iter()
+        .any(|function| function.function_name == "second" && function.ranges[0].count == 1));
+
+    // And profiling keeps running once coverage stops
+    s_env.start_profiling().unwrap();
+    s_env.stop_coverage().unwrap();
+    s_env
+        .run_named(
+            "function third() {
+                const start = Date.now();
+                while (Date.now() - start < 100) {}
+            }
+            third();",
+            "third.js",
+        )
+        .unwrap();
+    let profile = s_env.stop_profiling().unwrap();
+
+    let profile: ScriptValue = serde_json::from_str(&profile).unwrap();
+    assert!(profile["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|node| node["callFrame"]["functionName"] == "third"));
+}
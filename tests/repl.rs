@@ -0,0 +1,98 @@
+#![cfg(all(not(target_arch = "wasm32"), feature = "repl"))]
+
+use scriptit::{
+    core::{error::ScriptError, value::ScriptValue, ScriptingEnvironment},
+    platform::PlatformScriptingEnvironment,
+    repl::{Evaluation, ReplSession},
+};
+
+fn value(text: &str) -> Evaluation {
+    Evaluation::Value(String::from(text))
+}
+
+#[test]
+fn expressions_and_statements() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    let mut session = ReplSession::new(&mut s_env);
+    assert_eq!(session.eval("const base = 40").unwrap(), Evaluation::Done);
+    assert_eq!(session.eval("base + 2 // answer").unwrap(), value("42"));
+    assert_eq!(
+        session.eval("({ list: [1], name: 'x' })").unwrap(),
+        value("{\n  \"list\": [\n    1\n  ],\n  \"name\": \"x\"\n}")
+    );
+    assert_eq!(
+        session.eval("function double(x) { return x * 2 }").unwrap(),
+        Evaluation::Done
+    );
+    assert_eq!(session.eval("double").unwrap(), value("[Function: double]"));
+    assert_eq!(
+        session
+            .eval("[undefined, 10n, new TypeError('no')].map(String)")
+            .unwrap(),
+        value("[\n  \"undefined\",\n  \"10\",\n  \"TypeError: no\"\n]")
+    );
+    assert_eq!(session.eval("undefined").unwrap(), value("undefined"));
+    match session.eval("missing") {
+        Err(ScriptError::RuntimeError(_)) => {}
+        other => panic!("Expected a ScriptError::RuntimeError, got {:?}", other),
+    }
+}
+
+#[test]
+fn multi_line_input() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    let mut session = ReplSession::new(&mut s_env);
+    for input in &[
+        "function f() {",
+        "[1, 2",
+        "`line",
+        "/* comment",
+        "g(1,",
+        "1 +",
+    ] {
+        assert!(
+            session.is_incomplete(input),
+            "{} should be incomplete",
+            input
+        );
+        assert_eq!(session.eval(input).unwrap(), Evaluation::Incomplete);
+    }
+    for input in &["let = ;", "}", "a b", "1 + 1"] {
+        assert!(
+            !session.is_incomplete(input),
+            "{} should be complete",
+            input
+        );
+    }
+    assert_eq!(
+        session
+            .eval("[1, 2].map((x) => {\n  return `${x}`;\n})")
+            .unwrap(),
+        value("[\n  \"1\",\n  \"2\"\n]")
+    );
+}
+
+#[test]
+fn completion() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    s_env.register_func("answer", Box::new(|_| Ok(ScriptValue::from(42))));
+    s_env.register_func("ask", Box::new(|_| Ok(ScriptValue::Null)));
+    s_env
+        .run("globalThis.settings = { retries: 3, region: 'eu' }")
+        .unwrap();
+    let mut session = ReplSession::new(&mut s_env);
+
+    let line = "1 + ScriptIt.funcs.a";
+    assert_eq!(
+        session.complete(line, line.len()),
+        (19, vec![String::from("answer"), String::from("ask")])
+    );
+    assert_eq!(
+        session.complete("settings.re", 11),
+        (9, vec![String::from("region"), String::from("retries")])
+    );
+    let (start, candidates) = session.complete("Mat", 3);
+    assert_eq!((start, candidates), (0, vec![String::from("Math")]));
+    // Only identifier paths get evaluated
+    assert_eq!(session.complete("f().x", 5), (5, vec![]));
+}
@@ -0,0 +1,179 @@
+use scriptit::{
+    api::storage::{self, MemoryStorage, StorageBackend},
+    core::ScriptingEnvironment,
+    platform::PlatformScriptingEnvironment,
+};
+use serde_json::json;
+use wasm_bindgen_test::*;
+
+#[test]
+#[wasm_bindgen_test]
+fn local_storage_persists_across_environments() {
+    let backend = MemoryStorage::new();
+
+    let mut s_env = PlatformScriptingEnvironment::new();
+    storage::install(&mut s_env, backend.clone(), "app").unwrap();
+    s_env
+        .run(
+            "localStorage.setItem('b', 2);
+            localStorage.setItem('a', 'one');
+            localStorage.setItem('c', 'three');
+            localStorage.removeItem('c');",
+        )
+        .unwrap();
+
+    let mut s_env = PlatformScriptingEnvironment::new();
+    storage::install(&mut s_env, backend.clone(), "app").unwrap();
+    let val = s_env
+        .eval_expression(
+            "[
+                localStorage.getItem('a'),
+                localStorage.getItem('b'),
+                localStorage.getItem('c'),
+                localStorage.length,
+                localStorage.key(0),
+                localStorage.key(2),
+            ]",
+        )
+        .unwrap();
+    assert_eq!(val, json!(["one", "2", null, 2, "a", null]));
+
+    s_env.run("localStorage.clear()").unwrap();
+    assert_eq!(backend.clone().keys("app").unwrap(), Vec::<String>::new());
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn namespaces_are_isolated() {
+    let backend = MemoryStorage::new();
+
+    let mut first = PlatformScriptingEnvironment::new();
+    storage::install(&mut first, backend.clone(), "first").unwrap();
+    let mut second = PlatformScriptingEnvironment::new();
+    storage::install(&mut second, backend.clone(), "second").unwrap();
+
+    first.run("localStorage.setItem('key', 'first')").unwrap();
+    second.run("localStorage.setItem('key', 'second')").unwrap();
+    second.run("localStorage.clear()").unwrap();
+
+    let val = first
+        .eval_expression("localStorage.getItem('key')")
+        .unwrap();
+    assert_eq!(val, json!("first"));
+    assert_eq!(
+        backend.clone().get("first", "key").unwrap(),
+        Some(String::from("first"))
+    );
+    assert_eq!(backend.clone().get("second", "key").unwrap(), None);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn async_storage_api() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    storage::install(&mut s_env, MemoryStorage::new(), "app").unwrap();
+
+    s_env
+        .run(
+            "(async () => {
+                await ScriptIt.storage.set('user', { name: 'Ada', visits: 3 });
+                localStorage.setItem('raw', 'not json');
+                globalThis.result = [
+                    await ScriptIt.storage.get('user'),
+                    await ScriptIt.storage.get('raw'),
+                    await ScriptIt.storage.get('missing'),
+                    await ScriptIt.storage.keys(),
+                ];
+                await ScriptIt.storage.remove('user');
+                globalThis.keys = await ScriptIt.storage.keys();
+            })()",
+        )
+        .unwrap();
+    s_env.run_event_loop().unwrap();
+
+    let val = s_env.eval_expression("[result, keys]").unwrap();
+    assert_eq!(
+        val,
+        json!([
+            [{ "name": "Ada", "visits": 3 }, "not json", null, ["raw", "user"]],
+            ["raw"]
+        ])
+    );
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn reject_values_json_cannot_store() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    storage::install(&mut s_env, MemoryStorage::new(), "app").unwrap();
+
+    s_env
+        .run(
+            "ScriptIt.storage.set('missing', undefined)
+                .catch((err) => { globalThis.error = err instanceof TypeError; })
+                .then(() => ScriptIt.storage.keys())
+                .then((keys) => { globalThis.keys = keys; });",
+        )
+        .unwrap();
+    s_env.run_event_loop().unwrap();
+
+    let val = s_env.eval_expression("[error, keys]").unwrap();
+    assert_eq!(val, json!([true, []]));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn file_storage() {
+    let dir = std::env::temp_dir().join(format!("scriptit-storage-{}", uuid::Uuid::new_v4()));
+
+    let mut s_env = PlatformScriptingEnvironment::new();
+    let backend = storage::FileStorage::new(&dir).unwrap();
+    storage::install(&mut s_env, backend, "../app").unwrap();
+    s_env.run("localStorage.setItem('theme', 'dark')").unwrap();
+    let mut files: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    files.sort();
+    assert_eq!(files, vec!["%2E%2E%2Fapp.json", ".lock"]);
+
+    let mut s_env = PlatformScriptingEnvironment::new();
+    let backend = storage::FileStorage::new(&dir).unwrap();
+    storage::install(&mut s_env, backend, "../app").unwrap();
+    let val = s_env
+        .eval_expression("localStorage.getItem('theme')")
+        .unwrap();
+    assert_eq!(val, json!("dark"));
+
+    s_env.run("localStorage.clear()").unwrap();
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn file_storage_concurrent_writers() {
+    let dir = std::env::temp_dir().join(format!("scriptit-storage-{}", uuid::Uuid::new_v4()));
+
+    let writers: Vec<_> = (0..4)
+        .map(|writer| {
+            let dir = dir.clone();
+            std::thread::spawn(move || {
+                let mut backend = storage::FileStorage::new(&dir).unwrap();
+                for i in 0..20 {
+                    let key = format!("{}-{}", writer, i);
+                    backend.set("app", &key, "1").unwrap();
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    let mut backend = storage::FileStorage::new(&dir).unwrap();
+    assert_eq!(backend.keys("app").unwrap().len(), 80);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,72 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use scriptit::{
+    core::{
+        error::ScriptError,
+        value::{ScriptNumber, ScriptValue},
+        ScriptingEnvironment,
+    },
+    threaded::ThreadedScriptingEnvironment,
+};
+use std::{cell::RefCell, rc::Rc, thread};
+
+#[test]
+fn share_between_threads() {
+    let s_env = ThreadedScriptingEnvironment::new();
+    s_env.run("globalThis.calls = 0;").unwrap();
+
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let s_env = s_env.clone();
+            thread::spawn(move || s_env.run("globalThis.calls += 1;").unwrap())
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let val = s_env.eval_expression("globalThis.calls").unwrap();
+    assert_eq!(val, ScriptValue::Number(ScriptNumber::from(4)));
+}
+
+#[test]
+fn call_function() {
+    let s_env = ThreadedScriptingEnvironment::new();
+    s_env.run("function add(a, b) { return a + b; }").unwrap();
+    let val = s_env
+        .call_function(
+            "add",
+            vec![
+                ScriptValue::Number(ScriptNumber::from(40)),
+                ScriptValue::Number(ScriptNumber::from(2)),
+            ],
+        )
+        .unwrap();
+    assert_eq!(val, ScriptValue::Number(ScriptNumber::from(42)));
+}
+
+#[test]
+fn setup_registers_non_send_handlers() {
+    let s_env = ThreadedScriptingEnvironment::with_setup(|s_env| {
+        let count = Rc::new(RefCell::new(0_u32));
+        s_env.register_func(
+            "count",
+            Box::new(move |_| {
+                *count.borrow_mut() += 1;
+                Ok(ScriptValue::Number(ScriptNumber::from(*count.borrow())))
+            }),
+        );
+    });
+    s_env.eval_expression("ScriptIt.funcs.count()").unwrap();
+    let val = s_env.eval_expression("ScriptIt.funcs.count()").unwrap();
+    assert_eq!(val, ScriptValue::Number(ScriptNumber::from(2)));
+}
+
+#[test]
+fn forward_errors() {
+    let s_env = ThreadedScriptingEnvironment::new();
+    match s_env.run_named("iDontExist();", "threaded.js") {
+        Err(ScriptError::RuntimeError(msg)) => assert!(msg.contains("threaded.js")),
+        other => panic!("Expected a ScriptError::RuntimeError got {:?}", other),
+    }
+}
@@ -0,0 +1,136 @@
+use scriptit::{
+    core::{
+        clock::{Clock, VirtualClock},
+        ScriptingEnvironment,
+    },
+    platform::PlatformScriptingEnvironment,
+};
+use serde_json::json;
+use std::{rc::Rc, time::Duration};
+use wasm_bindgen_test::*;
+
+#[test]
+#[wasm_bindgen_test]
+fn timeouts_fire_in_order() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    let clock = VirtualClock::new();
+    s_env
+        .event_loop()
+        .borrow_mut()
+        .set_clock(Rc::new(clock.clone()));
+    s_env
+        .run(
+            "
+            globalThis.fired = [];
+            setTimeout(() => fired.push('a'), 20);
+            setTimeout((name) => fired.push(name), 10, 'b');
+            setTimeout(() => fired.push('c'), 10);
+            ",
+        )
+        .unwrap();
+    s_env.run_event_loop().unwrap();
+
+    let val = s_env.eval_expression("fired").unwrap();
+    assert_eq!(val, json!(["b", "c", "a"]));
+    assert_eq!(clock.now(), Duration::from_millis(20));
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn clear_timers() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    let clock = VirtualClock::new();
+    s_env
+        .event_loop()
+        .borrow_mut()
+        .set_clock(Rc::new(clock.clone()));
+    s_env
+        .run(
+            "
+            globalThis.ticks = 0;
+            globalThis.cancelled = false;
+            const timeout = setTimeout(() => { cancelled = true; }, 1000);
+            clearTimeout(timeout);
+            const interval = setInterval(() => {
+                ticks += 1;
+                if (ticks === 3) {
+                    clearInterval(interval);
+                }
+            }, 10);
+            ",
+        )
+        .unwrap();
+    s_env.run_event_loop().unwrap();
+
+    let val = s_env.eval_expression("[ticks, cancelled]").unwrap();
+    assert_eq!(val, json!([3, false]));
+    assert_eq!(clock.now(), Duration::from_millis(30));
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn timer_errors_stop_the_event_loop() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    s_env
+        .event_loop()
+        .borrow_mut()
+        .set_clock(Rc::new(VirtualClock::new()));
+    s_env
+        .run("setTimeout(() => { throw new Error('timer failed'); }, 5);")
+        .unwrap();
+    let err = s_env.run_event_loop().unwrap_err();
+    assert!(err.to_string().contains("timer failed"));
+}
+
+// Microtasks of the host engine only run once the wasm test returns
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn microtasks_run_before_timers() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    s_env
+        .event_loop()
+        .borrow_mut()
+        .set_clock(Rc::new(VirtualClock::new()));
+    s_env
+        .run(
+            "
+            globalThis.order = [];
+            setTimeout(() => order.push('timeout'), 0);
+            queueMicrotask(() => order.push('microtask'));
+            order.push('sync');
+            ",
+        )
+        .unwrap();
+    s_env.run_event_loop().unwrap();
+
+    let val = s_env.eval_expression("order").unwrap();
+    assert_eq!(val, json!(["sync", "microtask", "timeout"]));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn system_clock_waits_for_timers() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    let start = std::time::Instant::now();
+    s_env
+        .run("globalThis.done = false; setTimeout(() => { done = true; }, 50);")
+        .unwrap();
+    s_env.run_event_loop().unwrap();
+
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    assert_eq!(s_env.eval_expression("done").unwrap(), json!(true));
+}
+
+// The host engine can't be blocked: timers that are not due are reported instead of dropped
+#[cfg(all(target_arch = "wasm32", not(target_os = "wasi")))]
+#[wasm_bindgen_test]
+fn system_clock_can_not_wait_on_wasm() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    s_env
+        .run("globalThis.done = false; setTimeout(() => { done = true; }, 50);")
+        .unwrap();
+    let err = s_env.run_event_loop().unwrap_err();
+
+    assert!(err.to_string().contains("1 timer(s)"), "{}", err);
+    assert_eq!(s_env.eval_expression("done").unwrap(), json!(false));
+}
@@ -0,0 +1,110 @@
+#![cfg(feature = "typescript")]
+
+use scriptit::{
+    core::{
+        error::ScriptError,
+        value::{ScriptNumber, ScriptValue},
+        ScriptingEnvironment,
+    },
+    platform::PlatformScriptingEnvironment,
+    typescript::{transpile, TypeScriptEnvironment, TypeScriptExt},
+};
+use wasm_bindgen_test::*;
+
+#[test]
+#[wasm_bindgen_test]
+fn run_typescript() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    s_env
+        .run_typescript(
+            "interface Rule { weight: number }
+            const rules: Rule[] = [{ weight: 2 }, { weight: 3 }];
+            globalThis.total = rules.reduce((acc: number, rule: Rule) => acc + rule.weight, 0);",
+        )
+        .unwrap();
+    let val = s_env.eval_expression("globalThis.total").unwrap();
+    assert_eq!(val, ScriptValue::Number(ScriptNumber::from(5)));
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn eval_typescript_expression() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    let val = s_env
+        .eval_typescript_expression("(12 as number) + (3 as number)")
+        .unwrap();
+    assert_eq!(val, ScriptValue::Number(ScriptNumber::from(15)));
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn typescript_environment() {
+    let mut s_env = TypeScriptEnvironment::new(PlatformScriptingEnvironment::new());
+    s_env.register_func(
+        "double",
+        Box::new(|args| Ok(ScriptValue::from(args[0].as_i64().unwrap() * 2))),
+    );
+    s_env
+        .run("const base: number = ScriptIt.funcs.double(5 as number);")
+        .unwrap();
+    let val = s_env.eval_expression("(base as number) + 1").unwrap();
+    assert_eq!(val, ScriptValue::Number(ScriptNumber::from(11)));
+
+    let script = s_env
+        .compile_expression("<number>base * (2 as number)")
+        .unwrap();
+    let val = s_env.run_compiled(&script).unwrap();
+    assert_eq!(val, ScriptValue::Number(ScriptNumber::from(20)));
+    s_env.release_compiled(script);
+}
+
+// Boa doesn't report the line and column of errors
+#[cfg(not(feature = "boa"))]
+#[test]
+#[wasm_bindgen_test]
+fn map_expression_error_columns() {
+    let mut s_env = TypeScriptEnvironment::new(PlatformScriptingEnvironment::new());
+    let error = s_env
+        .eval_expression_named("('a' as string) + missing", "expr.ts")
+        .unwrap_err();
+    assert!(error.to_string().contains("expr.ts:1:19"), "{}", error);
+
+    let script = s_env
+        .compile_expression_named("('a' as string) + missing", "compiled.ts")
+        .unwrap();
+    let error = s_env.run_compiled(&script).unwrap_err();
+    assert!(error.to_string().contains("compiled.ts:1:19"), "{}", error);
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn typescript_syntax_error() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    match s_env.run_typescript_named("const x: = 1;", "broken.ts") {
+        Err(ScriptError::CompileError(msg)) => assert!(msg.starts_with("broken.ts")),
+        other => panic!("Expected a ScriptError::CompileError, got {:?}", other),
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn map_positions_to_typescript_source() {
+    let transpiled = transpile(
+        "type Id = string;\ninterface User {\n  id: Id;\n}\nconst user: User = { id: 'a' };\nthrow new Error(user.id);\n",
+        "user.ts",
+    )
+    .unwrap();
+    let throw_line = transpiled
+        .code
+        .lines()
+        .position(|line| line.starts_with("throw"))
+        .unwrap() as u32
+        + 1;
+    assert_eq!(transpiled.original_position(throw_line, 1), Some((6, 1)));
+
+    let error = ScriptError::RuntimeError(format!("user.ts:{}:1: Uncaught Error: a", throw_line));
+    match transpiled.map_error(error) {
+        ScriptError::RuntimeError(msg) => assert_eq!(msg, "user.ts:6:1: Uncaught Error: a"),
+        other => panic!("Expected a ScriptError::RuntimeError got {:?}", other),
+    }
+}
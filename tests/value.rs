@@ -0,0 +1,152 @@
+use scriptit::{
+    core::{
+        error::ScriptError,
+        value::{self, from_args, FromScriptValue, IntoScriptValue, ScriptValue, Serde},
+        ScriptingEnvironment,
+    },
+    platform::PlatformScriptingEnvironment,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use wasm_bindgen_test::*;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn primitives() {
+    assert!(ScriptValue::from(true).cast::<bool>().unwrap());
+    assert_eq!(ScriptValue::from("hi").cast::<String>().unwrap(), "hi");
+    assert_eq!(ScriptValue::from(-3).cast::<i8>().unwrap(), -3);
+    assert_eq!(ScriptValue::from(2.5).cast::<f64>().unwrap(), 2.5);
+    assert_eq!(ScriptValue::from(7).cast::<f32>().unwrap(), 7.0);
+    // Integral floats are integers too, as in JS
+    assert_eq!(ScriptValue::from(4.0).cast::<u32>().unwrap(), 4);
+    assert_eq!(ScriptValue::from(f64::NAN), ScriptValue::Null);
+    assert_eq!(ScriptValue::from(()), ScriptValue::Null);
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn collections() {
+    let value = ScriptValue::from(vec![Some(1), None, Some(3)]);
+    assert_eq!(value, json!([1, null, 3]));
+    assert_eq!(
+        value.cast::<Vec<Option<u8>>>().unwrap(),
+        vec![Some(1), None, Some(3)]
+    );
+
+    let value = ScriptValue::from(HashMap::from([("a", 1), ("b", 2)]));
+    assert_eq!(value, json!({ "a": 1, "b": 2 }));
+    let map: HashMap<String, i64> = value.cast().unwrap();
+    assert_eq!(map["b"], 2);
+
+    let value = ScriptValue::from(("x", 1, false));
+    assert_eq!(value, json!(["x", 1, false]));
+    let tuple: (String, u8, bool) = value.cast().unwrap();
+    assert_eq!(tuple, (String::from("x"), 1, false));
+
+    let numbers: ScriptValue = (1..4).collect();
+    assert_eq!(numbers, json!([1, 2, 3]));
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn accessors() {
+    let mut value = ScriptValue::from(json!({ "list": [1, "two"], "flag": true }));
+    assert!(value.is_object());
+    assert_eq!(value["list"][1], "two");
+    assert_eq!(value["missing"]["deeper"], ScriptValue::Null);
+    assert_eq!(value.get("flag").and_then(ScriptValue::as_bool), Some(true));
+    assert_eq!(value.get(0), None);
+
+    *value.get_mut("flag").unwrap() = ScriptValue::from(false);
+    assert_eq!(value["flag"], false);
+    let list = value.get_mut("list").unwrap().take();
+    assert_eq!(list.as_array().unwrap().len(), 2);
+    assert!(value["list"].is_null());
+    assert_eq!(value.to_string(), r#"{"flag":false,"list":null}"#);
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn cast_errors() {
+    match ScriptValue::from("hi").cast::<u8>() {
+        Err(ScriptError::CastError { type_from, type_to }) => {
+            assert_eq!(type_from, "ScriptValue::String");
+            assert_eq!(type_to, "u8");
+        }
+        other => panic!("Expected a CastError, got {:?}", other),
+    }
+    // Out of range and fractional numbers
+    assert!(ScriptValue::from(300).cast::<u8>().is_err());
+    assert!(ScriptValue::from(-1).cast::<u64>().is_err());
+    assert!(ScriptValue::from(1.5).cast::<i32>().is_err());
+    // Tuples don't drop extra elements, missing ones are null
+    assert!(ScriptValue::from(vec![1, 2, 3]).cast::<(u8, u8)>().is_err());
+    assert_eq!(
+        ScriptValue::from(vec![1])
+            .cast::<(u8, Option<u8>)>()
+            .unwrap(),
+        (1, None)
+    );
+    assert_eq!(
+        ScriptValue::Null.cast::<Vec<u8>>().unwrap_err().to_string(),
+        "ScriptError::CastError: Casting from `ScriptValue::Null` to `alloc::vec::Vec<u8>` failed!"
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn serde_types() {
+    let value = Serde(Point { x: 1, y: -2 }).into_script_value().unwrap();
+    assert_eq!(value, json!({ "x": 1, "y": -2 }));
+    let Serde(point) = Serde::<Point>::from_script_value(&value).unwrap();
+    assert_eq!(point, Point { x: 1, y: -2 });
+
+    match value::from_value::<Point>(&ScriptValue::from(json!({ "x": 1 }))) {
+        Err(ScriptError::SerializationError(message)) => assert!(message.contains("`y`")),
+        other => panic!("Expected a SerializationError, got {:?}", other),
+    }
+    assert_eq!(
+        serde_json::Value::from(value::to_value(&Point { x: 0, y: 0 }).unwrap()),
+        json!({ "x": 0, "y": 0 })
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn function_arguments() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    s_env.register_func(
+        "move",
+        Box::new(|args| {
+            let (Serde(point), dx): (Serde<Point>, Option<i32>) = from_args(args)?;
+            Serde(Point {
+                x: point.x + dx.unwrap_or(1),
+                y: point.y,
+            })
+            .into_script_value()
+        }),
+    );
+    let val = s_env
+        .eval_expression(
+            "[ScriptIt.funcs.move({ x: 1, y: 2 }, 10), ScriptIt.funcs.move({ x: 1, y: 2 })]",
+        )
+        .unwrap();
+    assert_eq!(val, json!([{ "x": 11, "y": 2 }, { "x": 2, "y": 2 }]));
+
+    let error = s_env
+        .eval_expression("ScriptIt.funcs.move('nowhere')")
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("SerializationError"),
+        "{}",
+        error
+    );
+}
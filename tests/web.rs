@@ -0,0 +1,120 @@
+use scriptit::{api::web, core::ScriptingEnvironment, platform::PlatformScriptingEnvironment};
+use serde_json::json;
+use wasm_bindgen_test::*;
+
+#[test]
+#[wasm_bindgen_test]
+fn not_installed_by_default() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    assert!(s_env.run("new TextEncoder()").is_err());
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn text_encoding() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    web::install(&mut s_env).unwrap();
+    let val = s_env
+        .eval_expression(
+            "(() => {
+                const bytes = new TextEncoder().encode('héllo 👋');
+                return [Array.from(bytes), new TextDecoder().decode(bytes)];
+            })()",
+        )
+        .unwrap();
+    assert_eq!(
+        val,
+        json!([
+            [104, 195, 169, 108, 108, 111, 32, 240, 159, 145, 139],
+            "héllo 👋"
+        ])
+    );
+
+    let val = s_env
+        .eval_expression("new TextDecoder().decode(new Uint8Array([0x61, 0xff, 0x62]))")
+        .unwrap();
+    assert_eq!(val, json!("a\u{fffd}b"));
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn base64() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    web::install(&mut s_env).unwrap();
+    let val = s_env
+        .eval_expression("[btoa('scriptit'), atob('c2NyaXB0aXQ='), atob(btoa('a'))]")
+        .unwrap();
+    assert_eq!(val, json!(["c2NyaXB0aXQ=", "scriptit", "a"]));
+    assert!(s_env.run("btoa('👋')").is_err());
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn urls() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    web::install(&mut s_env).unwrap();
+    let val = s_env
+        .eval_expression(
+            "(() => {
+                const url = new URL('../api?page=2&sort=asc#top', 'https://user@example.com:8080/docs/guide');
+                url.searchParams.set('page', '3');
+                url.searchParams.append('q', 'a b');
+                return [url.href, url.hostname, url.port, url.pathname, url.hash, url.origin];
+            })()",
+        )
+        .unwrap();
+    assert_eq!(
+        val,
+        json!([
+            "https://user@example.com:8080/api?page=3&sort=asc&q=a+b#top",
+            "example.com",
+            "8080",
+            "/api",
+            "#top",
+            "https://example.com:8080"
+        ])
+    );
+    assert!(s_env.run("new URL('not a url')").is_err());
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn structured_clone() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    web::install(&mut s_env).unwrap();
+    let val = s_env
+        .eval_expression(
+            "(() => {
+                const original = { date: new Date(0), items: new Map([['a', 1]]) };
+                original.self = original;
+                const copy = structuredClone(original);
+                return [
+                    copy !== original,
+                    copy.self === copy,
+                    copy.date instanceof Date,
+                    copy.items.get('a'),
+                ];
+            })()",
+        )
+        .unwrap();
+    assert_eq!(val, json!([true, true, true, 1]));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn performance_uses_the_event_loop_clock() {
+    use scriptit::core::clock::VirtualClock;
+    use std::{rc::Rc, time::Duration};
+
+    let mut s_env = PlatformScriptingEnvironment::new();
+    let clock = VirtualClock::new();
+    s_env
+        .event_loop()
+        .borrow_mut()
+        .set_clock(Rc::new(clock.clone()));
+    web::install(&mut s_env).unwrap();
+
+    clock.advance(Duration::from_millis(1500));
+    let val = s_env.eval_expression("performance.now()").unwrap();
+    assert_eq!(val, json!(1500));
+}
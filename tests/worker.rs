@@ -0,0 +1,203 @@
+#![cfg(all(not(target_arch = "wasm32"), feature = "v8"))]
+
+use scriptit::{
+    core::{value::ScriptValue, ScriptingEnvironment},
+    platform::{worker::WorkerFuncs, PlatformScriptingEnvironment},
+};
+use serde_json::json;
+use std::{thread, time::Duration};
+
+#[test]
+fn message_round_trip() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    s_env
+        .run(
+            r#"
+            globalThis.received = null;
+            const worker = new Worker(`
+                onmessage = (event) => {
+                    const { values, at, tags } = event.data;
+                    postMessage({ doubled: values.map((v) => v * 2), at, tags });
+                    close();
+                };
+            `);
+            worker.onmessage = (event) => {
+                globalThis.received = {
+                    doubled: event.data.doubled,
+                    isDate: event.data.at instanceof Date,
+                    tags: [...event.data.tags],
+                };
+            };
+            worker.postMessage({ values: [1, 2, 3], at: new Date(0), tags: new Set(["a", "b"]) });
+            "#,
+        )
+        .unwrap();
+    s_env.run_event_loop().unwrap();
+
+    let val = s_env.eval_expression("received").unwrap();
+    assert_eq!(
+        val,
+        json!({ "doubled": [2, 4, 6], "isDate": true, "tags": ["a", "b"] })
+    );
+}
+
+#[test]
+fn inherit_registered_funcs() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    s_env.register_func(
+        "square",
+        Box::new(|args| {
+            let n = args.first().and_then(ScriptValue::as_i64).unwrap();
+            Ok(ScriptValue::from(n * n))
+        }),
+    );
+    s_env
+        .run(
+            r#"
+            globalThis.received = null;
+            const worker = new Worker("postMessage(ScriptIt.funcs.square(7)); close();");
+            worker.onmessage = (event) => { globalThis.received = event.data; };
+            "#,
+        )
+        .unwrap();
+    s_env.run_event_loop().unwrap();
+
+    assert_eq!(s_env.eval_expression("received").unwrap(), json!(49));
+}
+
+#[test]
+fn time_out_calls_to_a_busy_parent() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    s_env.set_worker_call_timeout(Duration::from_millis(50));
+    s_env.register_func("answer", Box::new(|_| Ok(ScriptValue::from(42))));
+    s_env
+        .run(
+            r#"
+            globalThis.received = null;
+            const worker = new Worker(`
+                try {
+                    postMessage(ScriptIt.funcs.answer());
+                } catch (e) {
+                    postMessage(e.message);
+                }
+                close();
+            `);
+            worker.onmessage = (event) => { globalThis.received = event.data; };
+            "#,
+        )
+        .unwrap();
+    // The parent does not run its event loop while the worker waits for an answer
+    thread::sleep(Duration::from_millis(300));
+    s_env.run_event_loop().unwrap();
+
+    let received = s_env.eval_expression("received").unwrap();
+    assert!(received
+        .as_str()
+        .unwrap()
+        .contains("did not answer within 50 ms"));
+}
+
+#[test]
+fn restrict_inherited_funcs() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    s_env.set_worker_funcs(WorkerFuncs::Only(vec![String::from("allowed")]));
+    s_env.register_func("allowed", Box::new(|_| Ok(ScriptValue::Null)));
+    s_env.register_func("secret", Box::new(|_| Ok(ScriptValue::Null)));
+    s_env
+        .run(
+            r#"
+            globalThis.received = null;
+            const worker = new Worker(`
+                postMessage([typeof ScriptIt.funcs.allowed, typeof ScriptIt.funcs.secret]);
+                close();
+            `);
+            worker.onmessage = (event) => { globalThis.received = event.data; };
+            "#,
+        )
+        .unwrap();
+    s_env.run_event_loop().unwrap();
+
+    assert_eq!(
+        s_env.eval_expression("received").unwrap(),
+        json!(["function", "undefined"])
+    );
+}
+
+#[test]
+fn report_worker_errors() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    s_env
+        .run(
+            r#"
+            globalThis.error = null;
+            const worker = new Worker("throw new Error('boom');");
+            worker.onerror = (event) => { globalThis.error = event.message; };
+            "#,
+        )
+        .unwrap();
+    s_env.run_event_loop().unwrap();
+
+    let error = s_env.eval_expression("error").unwrap();
+    assert!(error.as_str().unwrap().contains("boom"));
+}
+
+#[test]
+fn terminate_worker() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    s_env
+        .run(
+            r#"
+            globalThis.received = null;
+            const worker = new Worker("while (true) {}");
+            worker.onmessage = (event) => { globalThis.received = event.data; };
+            worker.terminate();
+            "#,
+        )
+        .unwrap();
+    s_env.run_event_loop().unwrap();
+
+    assert_eq!(
+        s_env.eval_expression("received").unwrap(),
+        ScriptValue::Null
+    );
+}
+
+#[test]
+fn ignore_handlers_set_by_scripts() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    s_env.set_worker_funcs(WorkerFuncs::Only(vec![String::from("allowed")]));
+    s_env.register_func("allowed", Box::new(|_| Ok(ScriptValue::from("allowed"))));
+    s_env.register_func("secret", Box::new(|_| Ok(ScriptValue::from("secret"))));
+    s_env
+        .run(
+            r#"
+            ScriptIt.core.registerFunc("allowed", "worker$terminate");
+            globalThis.received = null;
+            const worker = new Worker("postMessage(ScriptIt.funcs.allowed()); close();");
+            worker.onmessage = (event) => { globalThis.received = event.data; };
+            "#,
+        )
+        .unwrap();
+    s_env.run_event_loop().unwrap();
+
+    assert_eq!(s_env.eval_expression("received").unwrap(), json!("allowed"));
+}
+
+#[test]
+fn only_clone_standard_errors() {
+    let mut s_env = PlatformScriptingEnvironment::new();
+    let val = s_env
+        .eval_expression(
+            r#"(() => {
+                const clone = (err) => ScriptIt.core.deserialize(ScriptIt.core.serialize(err));
+                const forged = JSON.stringify({ $t: "error", id: 0, name: "Function", v: "x" });
+                return [
+                    clone(new RangeError("out")).constructor.name,
+                    clone(new AggregateError([], "all")).message,
+                    ScriptIt.core.deserialize(forged).constructor.name,
+                ];
+            })()"#,
+        )
+        .unwrap();
+    assert_eq!(val, json!(["RangeError", "all", "Error"]));
+}